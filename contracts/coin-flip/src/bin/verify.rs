@@ -0,0 +1,140 @@
+use std::{env, fs, process};
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{from_json, HexBinary, Uint128};
+
+use coin_flip::randomness::{check_proof_randomness, get_block_seed_at, replay_flip};
+use coin_flip::types::FlipProof;
+
+const USAGE: &str = "usage: verify [--drand-pubkey <hex>] <proofs.json>...
+
+Every file holds the output of the GetFlipProof query, a list of those,
+or the same wrapped in the {\"data\": ...} of the chain CLI.
+Reveals are checked against the commitments of the proof, drand beacons against
+the public key of the drand chain, needed to verify drand flips.";
+
+/// Output of the chain CLI smart query
+#[cw_serde]
+struct QueryOutput<T> {
+    data: T,
+}
+
+fn parse_proofs(json: &[u8]) -> Option<Vec<FlipProof>> {
+    if let Ok(proofs) = from_json::<Vec<FlipProof>>(json) {
+        return Some(proofs);
+    }
+    if let Ok(proof) = from_json::<FlipProof>(json) {
+        return Some(vec![proof]);
+    }
+    if let Ok(output) = from_json::<QueryOutput<Vec<FlipProof>>>(json) {
+        return Some(output.data);
+    }
+    from_json::<QueryOutput<FlipProof>>(json)
+        .ok()
+        .map(|output| vec![output.data])
+}
+
+/// Check the randomness, recompute the flip and list everything that doesn't match its proof
+fn verify_proof(proof: &FlipProof, drand_pubkey: Option<&[u8]>) -> Vec<String> {
+    let block_seed = get_block_seed_at(proof.tx_index, proof.block, proof.timestamp);
+    let replay = replay_flip(proof, &block_seed);
+    let mut mismatches = check_proof_randomness(proof, drand_pubkey);
+
+    if replay.seed != proof.seed {
+        mismatches.push(format!("seed is {}, expected {}", proof.seed, replay.seed));
+    }
+    if replay.outcome_random != proof.outcome_random {
+        mismatches.push(format!(
+            "outcome random is {:?}, expected {:?}",
+            proof.outcome_random, replay.outcome_random
+        ));
+    }
+    if replay.result != proof.result {
+        mismatches.push(format!(
+            "result is {}, expected {}",
+            proof.result, replay.result
+        ));
+    }
+
+    let payout = match (replay.result, proof.voucher) {
+        (true, false) => proof.pick.payout(proof.amount.amount),
+        // The bet of a voucher flip was never paid, only the profit is
+        (true, true) => proof.pick.payout(proof.amount.amount) - proof.amount.amount,
+        (false, _) => Uint128::zero(),
+    };
+    if payout != proof.payout {
+        mismatches.push(format!("payout is {}, expected {payout}", proof.payout));
+    }
+
+    mismatches
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let mut drand_pubkey = None;
+    let mut files = vec![];
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--drand-pubkey" => {
+                drand_pubkey = match args.next().and_then(|hex| HexBinary::from_hex(&hex).ok()) {
+                    Some(pubkey) => Some(pubkey),
+                    None => {
+                        eprintln!("{USAGE}");
+                        process::exit(2);
+                    }
+                }
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            }
+            _ => files.push(arg),
+        }
+    }
+
+    if files.is_empty() {
+        eprintln!("{USAGE}");
+        process::exit(2);
+    }
+
+    let mut failed = false;
+
+    for file in files {
+        let proofs = match fs::read(&file).map(|json| parse_proofs(&json)) {
+            Ok(Some(proofs)) => proofs,
+            Ok(None) => {
+                eprintln!("{file}: not a flip proof");
+                failed = true;
+                continue;
+            }
+            Err(err) => {
+                eprintln!("{file}: {err}");
+                failed = true;
+                continue;
+            }
+        };
+
+        for proof in proofs {
+            let mismatches = verify_proof(&proof, drand_pubkey.as_ref().map(HexBinary::as_slice));
+            let result = if proof.result { "won" } else { "lost" };
+
+            if mismatches.is_empty() {
+                println!(
+                    "flip {} of {}: OK, {:?} {result} with {:?}",
+                    proof.id, proof.wallet, proof.pick, proof.randomness
+                );
+            } else {
+                failed = true;
+                println!("flip {} of {}: MISMATCH", proof.id, proof.wallet);
+                for mismatch in mismatches {
+                    println!("  {mismatch}");
+                }
+            }
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+}
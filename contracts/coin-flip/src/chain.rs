@@ -0,0 +1,21 @@
+//! Bindings of the chain the contract is built for, picked with the `stargaze` or `kujira` feature
+
+#[cfg(all(feature = "stargaze", feature = "kujira"))]
+compile_error!("features `stargaze` and `kujira` can't be enabled together");
+
+#[cfg(not(any(feature = "stargaze", feature = "kujira")))]
+compile_error!("one of the features `stargaze` or `kujira` must be enabled");
+
+/// Custom msg of the chain, kujira needs none of its bindings
+#[cfg(feature = "stargaze")]
+pub type CustomMsg = sg_std::StargazeMsgWrapper;
+#[cfg(feature = "kujira")]
+pub type CustomMsg = cosmwasm_std::Empty;
+
+pub type Response = cosmwasm_std::Response<CustomMsg>;
+
+/// Staking denom of the chain, streak rewards are paid in it
+#[cfg(feature = "stargaze")]
+pub const NATIVE_DENOM: &str = "ustars";
+#[cfg(feature = "kujira")]
+pub const NATIVE_DENOM: &str = "ukuji";
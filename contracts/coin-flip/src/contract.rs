@@ -1,22 +1,37 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{ensure_eq, Binary, Deps, DepsMut, Env, MessageInfo, StdResult, Uint128};
+use cosmwasm_std::{
+    coin, ensure_eq, from_json, Binary, Deps, DepsMut, Env, HexBinary, MessageInfo, Order,
+    StdResult, Storage, Uint128,
+};
 use cw2::set_contract_version;
-use sg_std::Response;
 
-use coin_flip_v07 as ccf_v07;
+// Only used by the v0.7 migration
+#[cfg(feature = "stargaze")]
+use {
+    crate::types::{Fees, KeeperReward},
+    coin_flip_v07 as ccf_v07,
+    std::collections::HashSet,
+};
 
+use crate::chain::{Response, NATIVE_DENOM};
 use crate::error::ContractError;
-use crate::helpers::ensure_not_paused;
+use crate::helpers::{cw20_denom, ensure_not_paused, get_sent_funds};
 use crate::msg::{
-    ExecuteMsg, FlipExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, StreakExecuteMsg,
+    AutoBetExecuteMsg, ChallengeExecuteMsg, ExecuteMsg, FlipExecuteMsg, InstantiateMsg,
+    LeaderboardExecuteMsg, MigrateMsg, QueryMsg, ReceiveMsg, ReferralExecuteMsg, StreakExecuteMsg,
+    VoucherExecuteMsg,
 };
 use crate::state::{
-    ALLOWED_SEND_NFT, CONFIG, FEES, FLIPS, NFT_REWARDS, STREAK_REWARDS, TODO_FLIPS,
+    flips, get_next_flip_id, todo_flips, v08, ALLOWED_SEND_NFT, CONFIG, FEES, FLIP_ID, NFT_REWARDS,
+    STREAK_REWARDS,
+};
+use crate::types::{
+    CancelFeePolicy, Config, DenomLimit, Flip, RandomnessSource, TodoFlip, Wallets,
+    DEFAULT_CHALLENGE_EXPIRY, DEFAULT_FLIP_EXPIRY, DEFAULT_MAX_PENDING_FLIPS,
 };
-use crate::types::{Config, DenomLimit, Fees, Wallets};
 
 use crate::sudo::handle_sudo_msg;
 
@@ -24,6 +39,15 @@ use crate::sudo::handle_sudo_msg;
 const CONTRACT_NAME: &str = "cosmos-coin-flip";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Max length of the client seed a flipper can send
+const MAX_CLIENT_SEED_LEN: usize = 64;
+/// Max amount of ready flips resolved before the execute of a user, to keep its gas in check
+const LAZY_FLIPS_LIMIT: usize = 5;
+/// Amount of challenges returned by GetChallenges when no limit is given
+const DEFAULT_CHALLENGES_LIMIT: u32 = 10;
+/// Max amount of challenges returned by GetChallenges
+const MAX_CHALLENGES_LIMIT: u32 = 30;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -37,6 +61,32 @@ pub fn instantiate(
     deps.api.addr_validate(&msg.wallets.team)?;
     deps.api.addr_validate(&msg.wallets.reserve)?;
 
+    let flip_expiry = msg.flip_expiry.unwrap_or(DEFAULT_FLIP_EXPIRY);
+    if flip_expiry == 0 {
+        return Err(ContractError::InvalidFlipExpiry);
+    }
+
+    let max_pending_flips = msg.max_pending_flips.unwrap_or(DEFAULT_MAX_PENDING_FLIPS);
+    if max_pending_flips == 0 {
+        return Err(ContractError::InvalidMaxPendingFlips);
+    }
+
+    let keeper_reward = msg.keeper_reward.unwrap_or_default();
+    if !keeper_reward.is_valid() {
+        return Err(ContractError::InvalidKeeperReward);
+    }
+
+    let challenge_expiry = msg.challenge_expiry.unwrap_or(DEFAULT_CHALLENGE_EXPIRY);
+    if challenge_expiry == 0 {
+        return Err(ContractError::InvalidChallengeExpiry);
+    }
+
+    if let Some(leaderboard) = &msg.leaderboard {
+        if !leaderboard.is_valid(&msg.denoms) {
+            return Err(ContractError::InvalidLeaderboard);
+        }
+    }
+
     let sg721_addr = match msg.sg721_addr {
         Some(addr) => Some(deps.api.addr_validate(&addr)?),
         None => None,
@@ -45,7 +95,15 @@ pub fn instantiate(
     let mut denom_limits: HashMap<String, DenomLimit> = HashMap::with_capacity(msg.denoms.len());
 
     for (denom, min, max, bank) in msg.denom_limits {
-        denom_limits.insert(denom, DenomLimit { min, max, bank });
+        denom_limits.insert(
+            denom,
+            DenomLimit {
+                min,
+                max,
+                bank,
+                max_payout: None,
+            },
+        );
     }
 
     // Save config
@@ -55,7 +113,7 @@ pub fn instantiate(
             admin: info.sender.to_string(),
             denoms: msg.denoms.clone(),
             denom_limits,
-            flips_per_block_limit: msg.flips_per_block_limit.unwrap_or(10),
+            flips_per_block_limit: msg.flips_per_block_limit.unwrap_or(50),
             wallets: Wallets {
                 team: msg.wallets.team,
                 reserve: msg.wallets.reserve,
@@ -65,6 +123,14 @@ pub fn instantiate(
             is_paused: false,
             streak_nft_winning_amount: msg.streak_nft_winning_amount,
             nft_pool_max: msg.nft_pool_max,
+            randomness: msg.randomness.unwrap_or_default(),
+            flip_expiry,
+            cancel_fee_policy: msg.cancel_fee_policy.unwrap_or_default(),
+            max_pending_flips,
+            keeper_reward,
+            challenge_expiry,
+            jackpot_odds: msg.jackpot_odds.unwrap_or_default(),
+            leaderboard: msg.leaderboard,
         },
     )?;
 
@@ -73,9 +139,6 @@ pub fn instantiate(
         FEES.save(deps.storage, denom, &Uint128::zero())?;
     }
 
-    FLIPS.save(deps.storage, &vec![])?;
-    TODO_FLIPS.save(deps.storage, &vec![])?;
-
     STREAK_REWARDS.save(deps.storage, &msg.streak_rewards)?;
     let allowed_send_nft_addrs = msg
         .allowed_to_send_nft
@@ -90,7 +153,7 @@ pub fn instantiate(
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
@@ -103,65 +166,304 @@ pub fn execute(
             token_id,
             msg: _,
         }) => streak_execute::receive_nft(deps, info, &config, sender, token_id),
+        ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            sender,
+            amount: funds,
+            msg,
+        }) => {
+            ensure_not_paused(&config)?;
+            // The token contract calls us, the tokens are paid by the sender as a coin of the token denom
+            let info = MessageInfo {
+                sender: deps.api.addr_validate(&sender)?,
+                funds: vec![coin(funds.u128(), cw20_denom(&info.sender))],
+            };
+            let lazy_response =
+                flip_execute::do_lazy_flips(deps.branch(), &env, &config, &info.funds)?;
+            let sent_funds = get_sent_funds(&lazy_response.messages)?;
+            let response = match from_json(&msg)? {
+                ReceiveMsg::StartFlip {
+                    pick,
+                    amount,
+                    commitment,
+                    client_seed,
+                    referrer,
+                } => {
+                    let referral_event =
+                        referral_execute::bind_referrer(deps.branch(), &info.sender, referrer)?;
+                    flip_execute::execute_start_flips(
+                        deps,
+                        env,
+                        info,
+                        &config,
+                        vec![(pick, amount)],
+                        commitment.map(|commitment| vec![commitment]),
+                        client_seed,
+                        None,
+                        &sent_funds,
+                    )?
+                    .add_events(referral_event)
+                }
+            };
+            Ok(merge_responses(lazy_response, response))
+        }
         ExecuteMsg::Streak(StreakExecuteMsg::Claim {}) => {
             ensure_not_paused(&config)?;
-            streak_execute::execute_claim(deps, info)
+            let lazy_response =
+                flip_execute::do_lazy_flips(deps.branch(), &env, &config, &info.funds)?;
+            let response = streak_execute::execute_claim(deps, info)?;
+            Ok(merge_responses(lazy_response, response))
+        }
+        ExecuteMsg::Flip(FlipExecuteMsg::StartFlip {
+            pick,
+            amount,
+            commitment,
+            client_seed,
+            use_voucher,
+            voucher_denom,
+            referrer,
+        }) => {
+            ensure_not_paused(&config)?;
+            let lazy_response =
+                flip_execute::do_lazy_flips(deps.branch(), &env, &config, &info.funds)?;
+            let sent_funds = get_sent_funds(&lazy_response.messages)?;
+            let referral_event =
+                referral_execute::bind_referrer(deps.branch(), &info.sender, referrer)?;
+            let response = flip_execute::execute_start_flips(
+                deps,
+                env,
+                info,
+                &config,
+                vec![(pick, amount)],
+                commitment.map(|commitment| vec![commitment]),
+                client_seed,
+                use_voucher
+                    .unwrap_or_default()
+                    .then(|| voucher_denom.unwrap_or_else(|| NATIVE_DENOM.to_string())),
+                &sent_funds,
+            )?
+            .add_events(referral_event);
+            Ok(merge_responses(lazy_response, response))
+        }
+        ExecuteMsg::Flip(FlipExecuteMsg::StartFlips {
+            picks,
+            commitments,
+            client_seed,
+            referrer,
+        }) => {
+            ensure_not_paused(&config)?;
+            let lazy_response =
+                flip_execute::do_lazy_flips(deps.branch(), &env, &config, &info.funds)?;
+            let sent_funds = get_sent_funds(&lazy_response.messages)?;
+            let referral_event =
+                referral_execute::bind_referrer(deps.branch(), &info.sender, referrer)?;
+            let response = flip_execute::execute_start_flips(
+                deps,
+                env,
+                info,
+                &config,
+                picks,
+                commitments,
+                client_seed,
+                None,
+                &sent_funds,
+            )?
+            .add_events(referral_event);
+            Ok(merge_responses(lazy_response, response))
+        }
+        ExecuteMsg::Flip(FlipExecuteMsg::RevealFlip { id, secret }) => {
+            ensure_not_paused(&config)?;
+            let lazy_response =
+                flip_execute::do_lazy_flips(deps.branch(), &env, &config, &info.funds)?;
+            let response = flip_execute::execute_reveal_flip(deps, info, id, secret)?;
+            Ok(merge_responses(lazy_response, response))
         }
-        ExecuteMsg::Flip(FlipExecuteMsg::StartFlip { pick, amount }) => {
+        ExecuteMsg::Flip(FlipExecuteMsg::DoFlips {
+            round,
+            signature,
+            house_seed,
+            limit,
+        }) => {
             ensure_not_paused(&config)?;
-            flip_execute::execute_start_flip(deps, env, info, &config, pick, amount)
+            flip_execute::execute_do_flips(
+                deps, env, info, &config, round, signature, house_seed, limit,
+            )
         }
-        ExecuteMsg::Flip(FlipExecuteMsg::DoFlips {}) => {
+        // Refunds and cancels stay open while paused, so funds are never stuck.
+        // They don't resolve ready flips, so they stay cheap and predictable for the user
+        ExecuteMsg::Flip(FlipExecuteMsg::Refund {}) => {
+            flip_execute::execute_refund(deps, env, info, &config)
+        }
+        ExecuteMsg::Flip(FlipExecuteMsg::CancelFlip {}) => {
+            flip_execute::execute_cancel_flip(deps, env, info, &config)
+        }
+        ExecuteMsg::Challenge(ChallengeExecuteMsg::Cancel { id }) => {
+            challenge_execute::execute_cancel_challenge(deps, env, info, id)
+        }
+        ExecuteMsg::AutoBet(AutoBetExecuteMsg::Withdraw {}) => {
+            auto_bet_execute::execute_withdraw_auto_bet(deps, info)
+        }
+        ExecuteMsg::Referral(ReferralExecuteMsg::ClaimReferralRewards {}) => {
+            referral_execute::execute_claim_referral_rewards(deps, info)
+        }
+        ExecuteMsg::Challenge(ChallengeExecuteMsg::Open { pick, amount }) => {
+            ensure_not_paused(&config)?;
+            let lazy_response =
+                flip_execute::do_lazy_flips(deps.branch(), &env, &config, &info.funds)?;
+            let response =
+                challenge_execute::execute_open_challenge(deps, env, info, &config, pick, amount)?;
+            Ok(merge_responses(lazy_response, response))
+        }
+        ExecuteMsg::Challenge(ChallengeExecuteMsg::Accept { id }) => {
+            ensure_not_paused(&config)?;
+            let lazy_response =
+                flip_execute::do_lazy_flips(deps.branch(), &env, &config, &info.funds)?;
+            let response =
+                challenge_execute::execute_accept_challenge(deps, env, info, &config, id)?;
+            Ok(merge_responses(lazy_response, response))
+        }
+        ExecuteMsg::AutoBet(AutoBetExecuteMsg::Start { strategy }) => {
+            ensure_not_paused(&config)?;
+            let lazy_response =
+                flip_execute::do_lazy_flips(deps.branch(), &env, &config, &info.funds)?;
+            let sent_funds = get_sent_funds(&lazy_response.messages)?;
+            let response = auto_bet_execute::execute_start_auto_bet(
+                deps,
+                env,
+                info,
+                &config,
+                strategy,
+                &sent_funds,
+            )?;
+            Ok(merge_responses(lazy_response, response))
+        }
+        ExecuteMsg::Voucher(VoucherExecuteMsg::Grant {
+            wallet,
+            amount,
+            flips,
+            expires_in,
+        }) => voucher_execute::execute_grant_voucher(
+            deps, env, info, &config, wallet, amount, flips, expires_in,
+        ),
+        ExecuteMsg::Voucher(VoucherExecuteMsg::Revoke { wallet, id }) => {
+            voucher_execute::execute_revoke_voucher(deps, info, &config, wallet, id)
+        }
+        ExecuteMsg::Leaderboard(LeaderboardExecuteMsg::FinalizeEpoch { epoch }) => {
+            ensure_not_paused(&config)?;
+            leaderboard_execute::execute_finalize_epoch(deps, env, info, &config, epoch)
+        }
+        ExecuteMsg::NoisReceive { callback } => {
             ensure_not_paused(&config)?;
-            flip_execute::execute_do_flips(deps, env, &config)
+            flip_execute::execute_nois_callback(deps, env, info, &config, callback)
         }
         ExecuteMsg::Sudo(sudo_msg) => handle_sudo_msg(deps, info, env, config, sudo_msg),
     }
 }
 
+/// Add the response of the user execute to the one of the flips resolved before it
+fn merge_responses(lazy_response: Response, response: Response) -> Response {
+    let mut merged = lazy_response
+        .add_submessages(response.messages)
+        .add_attributes(response.attributes)
+        .add_events(response.events);
+    merged.data = response.data;
+    merged
+}
+
 mod flip_execute {
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
 
     use cw_utils::must_pay;
 
-    use cosmwasm_std::{coin, ensure, to_json_binary, BankMsg, CosmosMsg, Event, Uint128, WasmMsg};
-    use sg_std::StargazeMsgWrapper;
-    use sha256::Sha256Digest;
-
-    use crate::helpers::ensure_correct_funds;
-    use crate::state::{get_next_flip_id, FEES, FLIPS, FLIP_ID, NFT_REWARDS, SCORES};
-    use crate::types::{Flip, FlipScore, PickTypes, TodoFlip};
+    use cosmwasm_std::{
+        coin, ensure, to_json_binary, Addr, BankMsg, Coin, CosmosMsg, Event, Order, Storage,
+        Uint128, WasmMsg,
+    };
+    use cw_storage_plus::Bound;
+
+    use crate::chain::{CustomMsg, NATIVE_DENOM};
+    use crate::drand::{derive_randomness, next_round, round_time, verify_beacon};
+    use crate::helpers::{ensure_correct_funds, query_denom_balance, send_coin_msg};
+    use crate::msg::{NoisCallback, NoisProxyExecuteMsg};
+    use crate::randomness::{
+        get_beacon_signature, get_block_seed, get_flip_randomness, get_jackpot_random,
+        get_operator_commitment, get_prize_random, get_tx_index, is_valid_commitment, roll_flip,
+        verify_reveal, FlipOutcome, FlipRandomness,
+    };
+    use crate::state::{
+        flips, get_held_funds, get_jackpot, get_next_flip_id, has_todo_flips, prune_flips,
+        todo_flips, CHALLENGE_ESCROW, DRAND_BEACONS, FEES, FLIP_ID, FLIP_PROOFS, HOUSE_CHAIN,
+        HOUSE_SEEDS, JACKPOTS, NFT_REWARDS, NOIS_JOBS, OPERATOR_ROUND, OPERATOR_SEEDS, SCORES,
+    };
+    use crate::types::{
+        DrandBeacon, Flip, FlipCommit, FlipProof, FlipScore, PickTypes, RandomnessSource,
+        ReferralShare, TodoFlip, MAX_WIN_CHANCE, MIN_WIN_CHANCE,
+    };
 
+    use super::auto_bet_execute::{continue_auto_bet, get_pending_auto_bet, stop_auto_bet};
+    use super::leaderboard_execute::score_flip;
+    use super::referral_execute::{credit_referrers, hold_referral_share, release_referral_shares};
+    use super::voucher_execute::take_voucher;
     use super::*;
 
-    pub(crate) fn execute_start_flip(
+    /// Register a flip for every pick, all paid with the single coin sent (CW20 tokens come as one),
+    /// or with a voucher of the flipper for a single pick
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn execute_start_flips(
         deps: DepsMut,
         env: Env,
         info: MessageInfo,
         config: &Config,
-        pick: PickTypes,
-        amount: Uint128,
+        picks: Vec<(PickTypes, Uint128)>,
+        commitments: Option<Vec<String>>,
+        client_seed: Option<String>,
+        voucher_denom: Option<String>,
+        sent_funds: &HashMap<String, Uint128>,
     ) -> Result<Response, ContractError> {
-        let mut todo_flips = TODO_FLIPS.load(deps.storage)?;
+        ensure!(!picks.is_empty(), ContractError::NoPicks);
 
-        // Make sure the user doesn't have flip waiting already
+        // Make sure the user doesn't have too many flips waiting already
+        let pending_flips = todo_flips()
+            .idx
+            .wallet
+            .prefix(info.sender.clone())
+            .keys(deps.storage, None, None, Order::Ascending)
+            .count();
         ensure!(
-            todo_flips.iter().all(|x| x.wallet != info.sender),
-            ContractError::AlreadyStartedFlip
+            pending_flips + picks.len() <= config.max_pending_flips as usize,
+            ContractError::TooManyPendingFlips(config.max_pending_flips)
         );
 
-        // Make sure we only have 10 waiting flips max
-        ensure!(
-            (todo_flips.len() as u64) < config.flips_per_block_limit,
-            ContractError::BlockLimitReached
-        );
+        ensure_block_limit(deps.storage, &env, config, picks.len())?;
 
-        // Verify we only have one coin sent
-        if info.funds.len() != 1 {
-            return Err(ContractError::WrongFundsAmount);
+        // Make sure the client seed is not too long
+        if let Some(client_seed) = &client_seed {
+            ensure!(
+                client_seed.len() <= MAX_CLIENT_SEED_LEN,
+                ContractError::ClientSeedTooLong {
+                    max_len: MAX_CLIENT_SEED_LEN
+                }
+            );
         }
 
-        let funds = info.funds[0].clone();
+        // A voucher pays for the flip, no funds are sent then
+        let voucher = if let Some(voucher_denom) = voucher_denom {
+            ensure!(info.funds.is_empty(), ContractError::WrongFundsAmount);
+            ensure!(picks.len() == 1, ContractError::VoucherSinglePick);
+            let bet = coin(picks[0].1.u128(), voucher_denom);
+            Some(take_voucher(deps.storage, &env, &info.sender, &bet)?)
+        } else {
+            // Verify we only have one coin sent
+            if info.funds.len() != 1 {
+                return Err(ContractError::WrongFundsAmount);
+            }
+            None
+        };
+
+        // Voucher flips are in the denom of the voucher
+        let funds = match &voucher {
+            Some(voucher) => coin(0, voucher.amount.denom.clone()),
+            None => info.funds[0].clone(),
+        };
         // Verify the sent funds is in supported denom.
         let denom = if config.denoms.iter().any(|x| x == &funds.denom) {
             funds.denom
@@ -174,360 +476,2517 @@ mod flip_execute {
           return Err(ContractError::NoBetLimits { denom});
         };
 
-        ensure!(
-            amount <= bet_limits.max,
-            ContractError::OverTheLimitBet {
-                max_limit: (bet_limits.max / Uint128::new(1000000)).to_string()
+        for (pick, amount) in &picks {
+            ensure!(
+                pick.is_valid(),
+                ContractError::InvalidWinChance {
+                    min: MIN_WIN_CHANCE,
+                    max: MAX_WIN_CHANCE
+                }
+            );
+            if let Some(max_payout) = bet_limits.max_payout {
+                ensure!(
+                    pick.payout(*amount) <= max_payout,
+                    ContractError::OverThePayoutLimit {
+                        max_limit: (max_payout / Uint128::new(1000000)).to_string()
+                    }
+                );
             }
-        );
+            ensure!(
+                amount <= &bet_limits.max,
+                ContractError::OverTheLimitBet {
+                    max_limit: (bet_limits.max / Uint128::new(1000000)).to_string()
+                }
+            );
+            ensure!(
+                amount >= &bet_limits.min,
+                ContractError::UnderTheLimitBet {
+                    min_limit: (bet_limits.min / Uint128::new(1000000)).to_string()
+                }
+            );
+        }
+
+        // Make sure the paid amount is correct (funds sent is the amounts + fees)
+        let amounts: Vec<Uint128> = picks.iter().map(|(_, amount)| *amount).collect();
+        let fee_amounts = if voucher.is_some() {
+            // Voucher flips are free, they pay no flip fee either
+            vec![Uint128::zero()]
+        } else {
+            let fee_amounts = ensure_correct_funds(funds.amount, &amounts, config.fees.flip_bps)?;
+            let total_amount: Uint128 = amounts.iter().sum();
+            let total_fee: Uint128 = fee_amounts.iter().sum();
+            let should_pay_amount = total_amount.checked_add(total_fee)?;
+            let paid_amount = must_pay(&info, &denom)?;
+
+            ensure_eq!(
+                should_pay_amount,
+                paid_amount,
+                ContractError::WrongPaidAmount
+            );
+            fee_amounts
+        };
+        // A won voucher flip only pays the profit, its bet was never paid
+        let total_payout: Uint128 = picks
+            .iter()
+            .map(|(pick, amount)| match voucher {
+                Some(_) => pick.payout(*amount) - amount,
+                None => pick.payout(*amount),
+            })
+            .sum();
+
+        // Make sure we have funds to pay for all the flips,
+        // without what the flips resolved before are sending out already
+        let fees = FEES.load(deps.storage, denom.clone())?;
+        let held_funds = get_held_funds(deps.storage, &denom)?;
+        let sent_funds = sent_funds.get(&denom).copied().unwrap_or_default();
+        let balance = query_denom_balance(&deps.querier, &env.contract.address, &denom)?;
+        let bank_balance = balance
+            .checked_sub(fees + held_funds + sent_funds)
+            .map_err(|_| ContractError::ContractMissingFunds(denom.clone()))?;
         ensure!(
-            amount >= bet_limits.min,
-            ContractError::UnderTheLimitBet {
-                min_limit: (bet_limits.min / Uint128::new(1000000)).to_string()
-            }
+            bank_balance >= total_payout,
+            ContractError::ContractMissingFunds(denom)
         );
 
-        // Make sure the paid amount is correct (funds sent is the amount + fee)
-        let fee_amount = ensure_correct_funds(funds.amount, amount, config.fees.flip_bps)?;
-        let should_pay_amount = amount.checked_add(fee_amount)?;
-        let paid_amount = must_pay(&info, &denom)?;
+        // In commit-reveal mode, bind every flip to its commitment and the current operator seed
+        let commits: Vec<Option<FlipCommit>> = match config.randomness {
+            RandomnessSource::CommitReveal { .. } => {
+                let Some(commitments) = commitments else {
+                    return Err(ContractError::MissingCommitment);
+                };
+                ensure!(
+                    commitments.len() == picks.len(),
+                    ContractError::WrongCommitmentsAmount
+                );
+                ensure!(
+                    commitments
+                        .iter()
+                        .all(|commitment| is_valid_commitment(commitment)),
+                    ContractError::InvalidCommitment
+                );
+
+                let operator_round = OPERATOR_ROUND
+                    .may_load(deps.storage)?
+                    .ok_or(ContractError::NoOperatorCommitment)?;
+                let operator_seed = OPERATOR_SEEDS.load(deps.storage, operator_round)?;
+                ensure!(
+                    operator_seed.seed.is_none(),
+                    ContractError::NoOperatorCommitment
+                );
 
-        ensure_eq!(
-            should_pay_amount,
-            paid_amount,
-            ContractError::WrongPaidAmount
+                commitments
+                    .into_iter()
+                    .map(|commitment| {
+                        Some(FlipCommit {
+                            commitment: commitment.to_lowercase(),
+                            secret: None,
+                            operator_round,
+                        })
+                    })
+                    .collect()
+            }
+            _ => {
+                ensure!(commitments.is_none(), ContractError::UnexpectedCommitment);
+                vec![None; picks.len()]
+            }
+        };
+
+        let house_seed_index = get_house_seed_index(deps.storage, config)?;
+        let drand_round = get_drand_round(&env, config);
+
+        let mut msgs: Vec<CosmosMsg<CustomMsg>> = vec![];
+        let mut start_flip_events: Vec<Event> = vec![];
+
+        for (((pick, amount), fee_amount), commit) in
+            picks.into_iter().zip(fee_amounts).zip(commits)
+        {
+            let id = get_next_flip_id(deps.storage);
+            FLIP_ID.save(deps.storage, &id)?;
+
+            let nois_job_id = request_nois_randomness(deps.storage, config, id, &mut msgs)?;
+            let (jackpot_share, referral_share) =
+                collect_fee(deps.storage, config, &info.sender, &denom, fee_amount)?;
+
+            // Everything is correct, save this to_do_flip
+            todo_flips().save(
+                deps.storage,
+                id,
+                &TodoFlip {
+                    id,
+                    wallet: info.sender.clone(),
+                    amount: coin(amount.u128(), denom.clone()),
+                    pick,
+                    block: env.block.height,
+                    timestamp: env.block.time,
+                    commit,
+                    drand_round,
+                    nois_job_id,
+                    house_seed_index,
+                    fee: fee_amount,
+                    jackpot_share,
+                    referral_shares: referral_share.into_iter().collect(),
+                    client_seed: client_seed.clone(),
+                    opponent: None,
+                    auto_bet: false,
+                    voucher: voucher.is_some(),
+                },
+            )?;
+
+            let mut start_flip_event = Event::new("start_flip")
+                .add_attribute("id", id.to_string())
+                .add_attribute("flipper", info.sender.clone());
+            if let Some(drand_round) = drand_round {
+                start_flip_event =
+                    start_flip_event.add_attribute("drand_round", drand_round.to_string());
+            }
+            start_flip_events.push(start_flip_event);
+        }
+
+        Ok(Response::default()
+            .add_events(start_flip_events)
+            .add_messages(msgs))
+    }
+
+    /// Make sure we don't start more flips than the limit in this block
+    pub(crate) fn ensure_block_limit(
+        storage: &dyn Storage,
+        env: &Env,
+        config: &Config,
+        new_flips: usize,
+    ) -> Result<(), ContractError> {
+        let block_flips = todo_flips()
+            .idx
+            .block
+            .prefix(env.block.height)
+            .keys(storage, None, None, Order::Ascending)
+            .count();
+        ensure!(
+            (block_flips + new_flips) as u64 <= config.flips_per_block_limit,
+            ContractError::BlockLimitReached
         );
 
-        // Make sure we have funds to pay for the flip
-        let mut fees = FEES.load(deps.storage, denom.clone())?;
-        let balance = deps
-            .querier
-            .query_balance(&env.contract.address, denom.clone())?;
+        Ok(())
+    }
+
+    /// In hash-chain mode, new flips are tied to the next house seed to reveal,
+    /// so the house can't pick the seed a flip is resolved with.
+    /// Errors if there is no house seed left to resolve new flips with
+    pub(crate) fn get_house_seed_index(
+        storage: &dyn Storage,
+        config: &Config,
+    ) -> Result<Option<u64>, ContractError> {
+        if config.randomness != RandomnessSource::HashChain {
+            return Ok(None);
+        }
+
+        let mut house_chain = HOUSE_CHAIN
+            .may_load(storage)?
+            .ok_or(ContractError::NoHouseChain)?;
         ensure!(
-            balance.amount - fees >= amount * Uint128::new(2),
-            ContractError::ContractMissingFunds(denom)
+            house_chain.rotate_if_used_up(),
+            ContractError::HouseChainUsedUp
         );
+        Ok(Some(house_chain.revealed + 1))
+    }
 
-        // Save fees
-        fees = fees.checked_add(fee_amount)?;
-        FEES.save(deps.storage, denom.clone(), &fees)?;
+    /// In drand mode, new flips are tied to the first round published after they started
+    pub(crate) fn get_drand_round(env: &Env, config: &Config) -> Option<u64> {
+        match config.randomness {
+            RandomnessSource::Drand {
+                genesis_time,
+                period,
+                ..
+            } => Some(next_round(genesis_time, period, env.block.time)),
+            _ => None,
+        }
+    }
 
-        let id = get_next_flip_id(deps.storage);
-        FLIP_ID.save(deps.storage, &id)?;
+    /// In nois mode, request randomness for the flip from the proxy, it will call us back with it
+    pub(crate) fn request_nois_randomness(
+        storage: &mut dyn Storage,
+        config: &Config,
+        id: u64,
+        msgs: &mut Vec<CosmosMsg<CustomMsg>>,
+    ) -> Result<Option<String>, ContractError> {
+        let RandomnessSource::Nois { proxy, .. } = &config.randomness else {
+            return Ok(None);
+        };
 
-        // Everything is correct, save this to_do_flip
-        todo_flips.push(TodoFlip {
-            id,
-            wallet: info.sender,
-            amount: coin(amount.u128(), denom),
-            pick,
-            block: env.block.height,
-            timestamp: env.block.time,
-        });
-        TODO_FLIPS.save(deps.storage, &todo_flips)?;
+        let job_id = format!("flip-{id}");
+        NOIS_JOBS.save(storage, job_id.clone(), &id)?;
 
-        Ok(Response::default()
-            .add_event(Event::new("start_flip").add_attribute("id", id.to_string())))
+        msgs.push(
+            WasmMsg::Execute {
+                contract_addr: proxy.to_string(),
+                msg: to_json_binary(&NoisProxyExecuteMsg::GetNextRandomness {
+                    job_id: job_id.clone(),
+                })?,
+                funds: vec![],
+            }
+            .into(),
+        );
+        Ok(Some(job_id))
     }
 
-    pub(crate) fn execute_do_flips(
+    pub(crate) fn execute_reveal_flip(
         deps: DepsMut,
+        info: MessageInfo,
+        id: u64,
+        secret: String,
+    ) -> Result<Response, ContractError> {
+        let Some(mut todo_flip) = todo_flips()
+            .may_load(deps.storage, id)?
+            .filter(|todo_flip| todo_flip.wallet == info.sender)
+        else {
+            return Err(ContractError::FlipNotFound(id));
+        };
+
+        let Some(commit) = todo_flip.commit.as_mut() else {
+            return Err(ContractError::UnexpectedCommitment);
+        };
+
+        ensure!(commit.secret.is_none(), ContractError::AlreadyRevealed);
+        ensure!(
+            verify_reveal(&commit.commitment, &secret),
+            ContractError::InvalidReveal
+        );
+
+        commit.secret = Some(secret);
+        todo_flips().save(deps.storage, id, &todo_flip)?;
+
+        Ok(Response::default().add_event(
+            Event::new("reveal_flip")
+                .add_attribute("flipper", info.sender)
+                .add_attribute("flip_id", id.to_string()),
+        ))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn execute_do_flips(
+        mut deps: DepsMut,
         env: Env,
+        info: MessageInfo,
         config: &Config,
+        round: Option<u64>,
+        signature: Option<HexBinary>,
+        house_seed: Option<String>,
+        limit: Option<u32>,
     ) -> Result<Response, ContractError> {
-        let todo_flips = TODO_FLIPS.load(deps.storage)?;
         // Make sure we have flips
-        ensure!(!todo_flips.is_empty(), ContractError::NoFlipsToDo);
+        ensure!(has_todo_flips(deps.storage), ContractError::NoFlipsToDo);
+        ensure!(limit != Some(0), ContractError::InvalidDoFlipsLimit);
+        let limit = limit.map(|limit| limit as usize);
+
+        // In drand mode, verify and save the beacon so flips tied to its round can be done
+        if let RandomnessSource::Drand {
+            pubkey,
+            genesis_time,
+            period,
+        } = &config.randomness
+        {
+            let (Some(round), Some(signature)) = (round, signature) else {
+                return Err(ContractError::MissingBeacon);
+            };
 
-        let mut save_todo_flips: Vec<TodoFlip> = vec![];
-        let mut flip_denoms: HashMap<String, Uint128> = HashMap::with_capacity(1);
+            ensure!(
+                round_time(*genesis_time, *period, round) <= env.block.time.seconds(),
+                ContractError::BeaconFromFuture(round)
+            );
+            ensure!(
+                verify_beacon(pubkey.as_slice(), round, signature.as_slice()),
+                ContractError::InvalidBeacon
+            );
 
-        // Make sure we have flips to do
-        let filtered_todo_flips: Vec<TodoFlip> = todo_flips
-            .into_iter()
-            .filter(|todo_flip| {
-                if todo_flip.block >= env.block.height {
-                    save_todo_flips.push(todo_flip.clone());
-                    return false;
-                }
+            DRAND_BEACONS.save(
+                deps.storage,
+                round,
+                &DrandBeacon {
+                    randomness: derive_randomness(signature.as_slice()),
+                    signature,
+                },
+            )?;
+        }
 
-                match flip_denoms.get(todo_flip.amount.denom.as_str()) {
-                    Some(amount) => flip_denoms.insert(
-                        todo_flip.amount.denom.clone(),
-                        *amount + todo_flip.amount.amount,
-                    ),
-                    None => {
-                        flip_denoms.insert(todo_flip.amount.denom.clone(), todo_flip.amount.amount)
-                    }
-                };
+        // In hash-chain mode, verify the revealed house seed and move the chain head to it.
+        // Without one, only the flips tied to a seed revealed already can be done
+        let mut house_chain_attributes = vec![];
+        let missing_house_seed =
+            config.randomness == RandomnessSource::HashChain && house_seed.is_none();
+        if let (RandomnessSource::HashChain, Some(house_seed)) = (&config.randomness, house_seed) {
+            let mut house_chain = HOUSE_CHAIN
+                .may_load(deps.storage)?
+                .ok_or(ContractError::NoHouseChain)?;
+            ensure!(
+                house_chain.rotate_if_used_up(),
+                ContractError::HouseChainUsedUp
+            );
+            ensure!(
+                verify_reveal(&house_chain.head, &house_seed),
+                ContractError::InvalidHouseSeed
+            );
 
-                true
-            })
-            .collect();
+            house_chain.head = house_seed.clone();
+            house_chain.remaining -= 1;
+            house_chain.revealed += 1;
+            HOUSE_CHAIN.save(deps.storage, &house_chain)?;
+            HOUSE_SEEDS.save(deps.storage, house_chain.revealed, &house_seed)?;
+
+            house_chain_attributes = vec![
+                ("house_seed", house_seed),
+                ("house_chain_remaining", house_chain.remaining.to_string()),
+            ];
+        }
+
+        let (ready_flips, refund_flips) = get_ready_flips(deps.storage, &env, config, limit)?;
+        let (response, done_flips) = do_ready_flips(
+            deps.branch(),
+            &env,
+            config,
+            ready_flips,
+            refund_flips,
+            Some(&info.sender),
+            &[],
+        )?;
 
         // Make sure that we have flips to do, else error
         ensure!(
-            !filtered_todo_flips.is_empty(),
-            ContractError::NoFlipsToDoThisBlock
+            done_flips > 0 || !missing_house_seed,
+            ContractError::MissingHouseSeed
         );
+        ensure!(done_flips > 0, ContractError::NoFlipsToDoThisBlock);
 
-        // Save the todo flips that are not ready to be flipped yet
-        TODO_FLIPS.save(deps.storage, &save_todo_flips)?;
+        // Every ready flip was done, unless we stopped at the limit
+        let ready_flips_left = match limit {
+            Some(limit) => count_ready_flips(deps.storage, &env, config, limit)?,
+            None => 0,
+        };
 
-        // Make sure we have funds to pay for all the flips
-        for (denom, total_amount) in flip_denoms {
-            let fees = FEES.load(deps.storage, denom.clone())?;
+        Ok(response
+            .add_attribute("flip_action", "do_flips")
+            .add_attribute("ready_flips_left", ready_flips_left.to_string())
+            .add_attributes(house_chain_attributes))
+    }
 
-            let contract_balance = deps
-                .querier
-                .query_balance(&env.contract.address, denom.clone())?;
+    /// Resolve the ready flips before the execute of a user, so players don't wait for a keeper
+    /// and their own ready flips are scored before the execute checks them.
+    /// Flips the bank can't pay are left to the keeper, so they never fail the execute of the user,
+    /// the funds the user sent are not part of the bank.
+    /// Nothing is resolved while paused, and house seeds are only revealed by DoFlips,
+    /// so hash-chain flips are left to it
+    pub(crate) fn do_lazy_flips(
+        deps: DepsMut,
+        env: &Env,
+        config: &Config,
+        received_funds: &[Coin],
+    ) -> Result<Response, ContractError> {
+        if config.is_paused || config.randomness == RandomnessSource::HashChain {
+            return Ok(Response::default());
+        }
 
-            ensure!(
-                contract_balance.amount - fees >= total_amount * Uint128::new(2),
-                ContractError::ContractMissingFunds(denom)
-            );
+        let (ready_flips, refund_flips) =
+            get_ready_flips(deps.storage, env, config, Some(LAZY_FLIPS_LIMIT))?;
+        if ensure_bank_covers(deps.as_ref(), env, &ready_flips, received_funds).is_err() {
+            return Ok(Response::default().add_attribute("lazy_flips_skipped", "bank"));
         }
 
-        let mut msgs: Vec<CosmosMsg<StargazeMsgWrapper>> = vec![];
-        let mut response = Response::default();
-        let rand = get_random(&env);
-        let mut last_flips = FLIPS.load(deps.storage)?;
+        let (response, done_flips) = do_ready_flips(
+            deps,
+            env,
+            config,
+            ready_flips,
+            refund_flips,
+            None,
+            received_funds,
+        )?;
+        if done_flips == 0 {
+            return Ok(response);
+        }
 
-        for todo_flip in filtered_todo_flips {
-            // Get flip result (won or lost)
-            let flip_result = do_a_flip(&todo_flip, rand);
+        Ok(response.add_attribute("lazy_flips", done_flips.to_string()))
+    }
 
-            // Handle score (needed the streak info in Flip)
-            let mut score = match SCORES.load(deps.storage, &todo_flip.wallet) {
-                Ok(mut score) => score.update(flip_result, env.clone()),
-                Err(_) => FlipScore::new(flip_result, env.clone()),
-            };
+    /// Waiting flips that have their randomness and the nois flips that timed out,
+    /// among the oldest waiting flips up to the limit, so flips still waiting for their
+    /// randomness can't make the scan unbounded
+    #[allow(clippy::type_complexity)]
+    fn get_ready_flips(
+        storage: &dyn Storage,
+        env: &Env,
+        config: &Config,
+        limit: Option<usize>,
+    ) -> StdResult<(Vec<(TodoFlip, FlipRandomness)>, Vec<TodoFlip>)> {
+        let mut ready_flips: Vec<(TodoFlip, FlipRandomness)> = vec![];
+        let mut refund_flips: Vec<TodoFlip> = vec![];
+
+        for todo_flip in get_started_todo_flips(storage, env).take(limit.unwrap_or(usize::MAX)) {
+            let todo_flip = todo_flip?;
+            match get_flip_randomness(storage, env, config, &todo_flip)? {
+                Some(FlipRandomness::NoisTimeout) => refund_flips.push(todo_flip),
+                Some(flip_randomness) => ready_flips.push((todo_flip, flip_randomness)),
+                None => {}
+            }
+        }
 
-            // Create new flip
-            let flip = Flip {
-                wallet: todo_flip.wallet.clone(),
-                amount: todo_flip.amount.clone(),
-                result: flip_result,
-                streak: score.streak.clone(),
-                timestamp: env.block.time,
-            };
+        Ok((ready_flips, refund_flips))
+    }
 
-            // check if flipper did enough streak to win NFT (12)
-            if is_streak_nft_winner(config, &score) {
-                let mut nft_pool = NFT_REWARDS.load(deps.storage)?;
-                let mut streak_event = Event::new("streak-claim")
-                    .add_attribute("flipper", todo_flip.wallet.to_string())
-                    .add_attribute("flip_id", todo_flip.id.to_string());
+    /// Resolve the ready flips and refund the nois flips that timed out.
+    /// The keeper, if any, is paid for the flips it resolved.
+    /// Returns the amount of flips that were done
+    fn do_ready_flips(
+        mut deps: DepsMut,
+        env: &Env,
+        config: &Config,
+        filtered_todo_flips: Vec<(TodoFlip, FlipRandomness)>,
+        refund_todo_flips: Vec<TodoFlip>,
+        keeper: Option<&Addr>,
+        received_funds: &[Coin],
+    ) -> Result<(Response, usize), ContractError> {
+        let done_flips = filtered_todo_flips.len() + refund_todo_flips.len();
+        if done_flips == 0 {
+            return Ok((Response::default(), 0));
+        }
+
+        // Remove the flips we are doing, the others wait for their randomness
+        for todo_flip in filtered_todo_flips
+            .iter()
+            .map(|(todo_flip, _)| todo_flip)
+            .chain(refund_todo_flips.iter())
+        {
+            todo_flips().remove(deps.storage, todo_flip.id)?;
+        }
+
+        let keeper_bounty = match keeper {
+            Some(_) => get_keeper_bounty(
+                deps.storage,
+                config,
+                filtered_todo_flips.iter().map(|(todo_flip, _)| todo_flip),
+            )?,
+            None => vec![],
+        };
+        // The keeper is paid out of the fees before the flips fund the jackpot with them
+        for bounty in &keeper_bounty {
+            FEES.update(deps.storage, bounty.denom.clone(), |fees| {
+                Ok::<_, ContractError>(fees.unwrap_or_default().checked_sub(bounty.amount)?)
+            })?;
+        }
+
+        let mut response = resolve_flips(
+            deps.branch(),
+            env,
+            config,
+            filtered_todo_flips,
+            received_funds,
+        )?;
+
+        // Pay the keeper for resolving the flips, out of their fees
+        if let (Some(keeper), false) = (keeper, keeper_bounty.is_empty()) {
+            response = response
+                .add_event(
+                    Event::new("keeper_reward")
+                        .add_attribute("keeper", keeper.clone())
+                        .add_attribute(
+                            "reward",
+                            keeper_bounty
+                                .iter()
+                                .map(|bounty| bounty.to_string())
+                                .collect::<Vec<_>>()
+                                .join(","),
+                        ),
+                )
+                .add_messages(
+                    keeper_bounty
+                        .into_iter()
+                        .map(|bounty| send_coin_msg(keeper.clone(), bounty))
+                        .collect::<StdResult<Vec<_>>>()?,
+                );
+        }
+
+        // Refund flips we never got randomness for
+        for todo_flip in refund_todo_flips {
+            let refund_msgs = refund_flip(deps.storage, &todo_flip, true)?;
+            response = response
+                .add_messages(refund_msgs)
+                .add_event(refund_event(todo_flip, "nois_timeout"));
+        }
+
+        Ok((response, done_flips))
+    }
+
+    /// Waiting flips started before this block, the only ones that can be ready, oldest first
+    pub(crate) fn get_started_todo_flips<'a>(
+        storage: &'a dyn Storage,
+        env: &Env,
+    ) -> impl Iterator<Item = StdResult<TodoFlip>> + 'a {
+        todo_flips()
+            .idx
+            .block
+            .range(
+                storage,
+                None,
+                Some(Bound::exclusive((env.block.height, 0))),
+                Order::Ascending,
+            )
+            .map(|item| item.map(|(_, todo_flip)| todo_flip))
+    }
+
+    /// Amount of waiting flips that have their randomness already, among the oldest ones
+    /// up to the limit, enough for the keeper to know another batch is ready
+    fn count_ready_flips(
+        storage: &dyn Storage,
+        env: &Env,
+        config: &Config,
+        limit: usize,
+    ) -> StdResult<usize> {
+        let mut ready_flips = 0;
+        for todo_flip in get_started_todo_flips(storage, env).take(limit) {
+            if get_flip_randomness(storage, env, config, &todo_flip?)?.is_some() {
+                ready_flips += 1;
+            }
+        }
+
+        Ok(ready_flips)
+    }
+
+    pub(crate) fn execute_nois_callback(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        config: &Config,
+        callback: NoisCallback,
+    ) -> Result<Response, ContractError> {
+        // Only our proxy can call us back
+        let RandomnessSource::Nois { proxy, .. } = &config.randomness else {
+            return Err(ContractError::Unauthorized);
+        };
+        ensure_eq!(&info.sender, proxy, ContractError::Unauthorized);
+
+        let NoisCallback {
+            job_id, randomness, ..
+        } = callback;
+
+        let id = NOIS_JOBS
+            .may_load(deps.storage, job_id.clone())?
+            .ok_or_else(|| ContractError::NoisJobNotFound(job_id.clone()))?;
+        NOIS_JOBS.remove(deps.storage, job_id.clone());
+
+        let todo_flip = todo_flips()
+            .may_load(deps.storage, id)?
+            .ok_or(ContractError::FlipNotFound(id))?;
+        todo_flips().remove(deps.storage, id)?;
+
+        let flip_randomness = FlipRandomness::Nois {
+            job_id,
+            randomness: randomness.to_hex(),
+        };
+
+        Ok(
+            resolve_flips(deps, &env, config, vec![(todo_flip, flip_randomness)], &[])?
+                .add_attribute("flip_action", "nois_callback"),
+        )
+    }
+
+    pub(crate) fn execute_refund(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        config: &Config,
+    ) -> Result<Response, ContractError> {
+        let mut refund_todo_flips: Vec<TodoFlip> = vec![];
+        let mut refund_error = ContractError::NoFlipsToRefund;
+
+        // Either player of an accepted challenge can refund it
+        let opponent_todo_flips = todo_flips()
+            .idx
+            .opponent
+            .prefix(info.sender.clone())
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, todo_flip)| todo_flip))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        for todo_flip in get_wallet_todo_flips(deps.storage, &info.sender)?
+            .into_iter()
+            .chain(opponent_todo_flips)
+        {
+            // The player could see the outcome coming and only refund the losing flips
+            if todo_flip.commit.is_some() || todo_flip.drand_round.is_some() {
+                refund_error = ContractError::FlipNotRefundable;
+                continue;
+            }
+
+            let expires_at = todo_flip.block + config.flip_expiry;
+            if env.block.height < expires_at {
+                refund_error = ContractError::FlipNotExpired(expires_at);
+                continue;
+            }
+
+            refund_todo_flips.push(todo_flip);
+        }
+
+        if refund_todo_flips.is_empty() {
+            return Err(refund_error);
+        }
+
+        for todo_flip in &refund_todo_flips {
+            todo_flips().remove(deps.storage, todo_flip.id)?;
+        }
+
+        let mut response = Response::default().add_attribute("flip_action", "refund");
+        for todo_flip in refund_todo_flips {
+            let refund_msgs = refund_flip(deps.storage, &todo_flip, true)?;
+            response = response
+                .add_messages(refund_msgs)
+                .add_event(refund_event(todo_flip, "expired"));
+        }
+
+        Ok(response)
+    }
+
+    pub(crate) fn execute_cancel_flip(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        config: &Config,
+    ) -> Result<Response, ContractError> {
+        // Accepted PvP challenges are a deal between two players, they can't be cancelled,
+        // auto-bets are stopped with a withdraw, and voucher flips have nothing to refund
+        let wallet_todo_flips: Vec<TodoFlip> = get_wallet_todo_flips(deps.storage, &info.sender)?
+            .into_iter()
+            .filter(|x| x.opponent.is_none() && !x.auto_bet && !x.voucher)
+            .collect();
+        ensure!(
+            !wallet_todo_flips.is_empty(),
+            ContractError::NoFlipsToCancel
+        );
+
+        // Only flips started in this block, DoFlips can resolve the others already
+        let cancel_todo_flips: Vec<TodoFlip> = wallet_todo_flips
+            .into_iter()
+            .filter(|x| x.block == env.block.height)
+            .collect();
+        ensure!(
+            !cancel_todo_flips.is_empty(),
+            ContractError::CancelWindowPassed
+        );
+
+        for todo_flip in &cancel_todo_flips {
+            todo_flips().remove(deps.storage, todo_flip.id)?;
+        }
+
+        let refund_fee = config.cancel_fee_policy == CancelFeePolicy::FullRefund;
+        let mut response = Response::default().add_attribute("flip_action", "cancel_flip");
+        for todo_flip in cancel_todo_flips {
+            let refund_msgs = refund_flip(deps.storage, &todo_flip, refund_fee)?;
+            let kept_fee = if refund_fee {
+                Uint128::zero()
+            } else {
+                todo_flip.fee
+            };
+
+            response = response.add_messages(refund_msgs).add_event(
+                Event::new("cancel_flip")
+                    .add_attribute("id", todo_flip.id.to_string())
+                    .add_attribute("flipper", todo_flip.wallet)
+                    .add_attribute("flip_amount", todo_flip.amount.to_string())
+                    .add_attribute("kept_fee", kept_fee),
+            );
+        }
+
+        Ok(response)
+    }
+
+    /// Waiting flips of a wallet, oldest first
+    fn get_wallet_todo_flips(storage: &dyn Storage, wallet: &Addr) -> StdResult<Vec<TodoFlip>> {
+        todo_flips()
+            .idx
+            .wallet
+            .prefix(wallet.clone())
+            .range(storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, todo_flip)| todo_flip))
+            .collect()
+    }
+
+    /// Reward of the DoFlips caller for resolving these flips, never more than the fees we hold
+    pub(crate) fn get_keeper_bounty<'a>(
+        storage: &dyn Storage,
+        config: &Config,
+        todo_flips: impl IntoIterator<Item = &'a TodoFlip>,
+    ) -> StdResult<Vec<Coin>> {
+        let mut bounty: BTreeMap<String, Uint128> = BTreeMap::new();
+        for todo_flip in todo_flips {
+            *bounty.entry(todo_flip.amount.denom.clone()).or_default() +=
+                config.keeper_reward.calculate(todo_flip.paid_fees());
+        }
+
+        let mut keeper_bounty = vec![];
+        for (denom, amount) in bounty {
+            let fees = FEES.may_load(storage, denom.clone())?.unwrap_or_default();
+            let amount = amount.min(fees);
+            if !amount.is_zero() {
+                keeper_bounty.push(coin(amount.u128(), denom));
+            }
+        }
+
+        Ok(keeper_bounty)
+    }
+
+    /// Send back the bet of a flip that is never going to be resolved, with the flip fee if asked
+    /// A PvP flip sends back the bets of both players,
+    /// an auto-bet flip stops its auto-bet and sends back the budget too,
+    /// a voucher flip was never paid, its free flip is lost
+    fn refund_flip(
+        storage: &mut dyn Storage,
+        todo_flip: &TodoFlip,
+        refund_fee: bool,
+    ) -> Result<Vec<CosmosMsg<CustomMsg>>, ContractError> {
+        if let Some(job_id) = &todo_flip.nois_job_id {
+            NOIS_JOBS.remove(storage, job_id.clone());
+        }
+        if todo_flip.voucher {
+            return Ok(vec![]);
+        }
+
+        let mut wallets = vec![todo_flip.wallet.clone()];
+        if let Some(opponent) = &todo_flip.opponent {
+            // The stakes are not part of the bank, they leave the escrow
+            let stakes = todo_flip.amount.amount * Uint128::new(2);
+            CHALLENGE_ESCROW.update(storage, todo_flip.amount.denom.clone(), |escrow| {
+                Ok::<_, ContractError>(escrow.unwrap_or_default().checked_sub(stakes)?)
+            })?;
+            wallets.push(opponent.clone());
+        }
+
+        let mut refund_amount = todo_flip.amount.amount;
+        if refund_fee {
+            // The jackpot and the referrers give back their share of the fee,
+            // the rest comes out of the bank with the fees that might be distributed already
+            JACKPOTS.update(storage, todo_flip.amount.denom.clone(), |jackpot| {
+                Ok::<_, ContractError>(
+                    jackpot
+                        .unwrap_or_default()
+                        .saturating_sub(todo_flip.jackpot_share),
+                )
+            })?;
+            let referral_shares = release_referral_shares(
+                storage,
+                &todo_flip.amount.denom,
+                &todo_flip.referral_shares,
+            )?;
+            let refund_fees = (todo_flip.fee * Uint128::new(wallets.len() as u128))
+                .saturating_sub(todo_flip.jackpot_share + referral_shares);
+            FEES.update(storage, todo_flip.amount.denom.clone(), |fees| {
+                Ok::<_, ContractError>(fees.unwrap_or_default().saturating_sub(refund_fees))
+            })?;
+            refund_amount += todo_flip.fee;
+        }
+
+        let mut refund_msgs = wallets
+            .into_iter()
+            .map(|wallet| {
+                send_coin_msg(
+                    wallet,
+                    coin(refund_amount.u128(), todo_flip.amount.denom.clone()),
+                )
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        if let Some(auto_bet) = get_pending_auto_bet(storage, todo_flip)? {
+            refund_msgs.extend(stop_auto_bet(storage, auto_bet)?.map(Into::into));
+        }
+
+        Ok(refund_msgs)
+    }
+
+    /// Move the jackpot share of the fees paid for the flip from the fees to the jackpot
+    fn fund_jackpot(
+        storage: &mut dyn Storage,
+        config: &Config,
+        denom: &str,
+        paid_fee: Uint128,
+    ) -> Result<Uint128, ContractError> {
+        let share = config.fees.jackpot_share(paid_fee);
+        if share.is_zero() {
+            return Ok(share);
+        }
+
+        JACKPOTS.update(storage, denom.to_string(), |jackpot| {
+            Ok::<_, ContractError>(jackpot.unwrap_or_default().checked_add(share)?)
+        })?;
+        Ok(share)
+    }
+
+    /// Add a flip fee paid by the wallet to the fees, its referrer and the jackpot take their
+    /// share right away, so a distribution before the flip is resolved can't pay it out.
+    /// Returns both shares, given back if the flip is refunded
+    pub(crate) fn collect_fee(
+        storage: &mut dyn Storage,
+        config: &Config,
+        wallet: &Addr,
+        denom: &str,
+        fee: Uint128,
+    ) -> Result<(Uint128, Option<ReferralShare>), ContractError> {
+        let referral_share = hold_referral_share(storage, config, wallet, denom, fee)?;
+        let jackpot_share = fund_jackpot(storage, config, denom, fee)?;
+        let shares = jackpot_share
+            + referral_share
+                .as_ref()
+                .map(|share| share.amount)
+                .unwrap_or_default();
+        FEES.update(storage, denom.to_string(), |fees| {
+            Ok::<_, ContractError>(
+                fees.unwrap_or_default()
+                    .checked_add(fee.checked_sub(shares)?)?,
+            )
+        })?;
+        Ok((jackpot_share, referral_share))
+    }
+
+    /// Draw the jackpot of the flip denom, returns the whole jackpot if the flip won it
+    fn draw_jackpot(
+        storage: &mut dyn Storage,
+        config: &Config,
+        todo_flip: &TodoFlip,
+        seed: &str,
+    ) -> Result<Option<Coin>, ContractError> {
+        if config.jackpot_odds == 0
+            || !get_jackpot_random(seed, todo_flip).is_multiple_of(config.jackpot_odds)
+        {
+            return Ok(None);
+        }
+
+        let denom = todo_flip.amount.denom.clone();
+        let jackpot = get_jackpot(storage, &denom)?;
+        if jackpot.is_zero() {
+            return Ok(None);
+        }
+        JACKPOTS.remove(storage, denom.clone());
+
+        Ok(Some(coin(jackpot.u128(), denom)))
+    }
+
+    fn jackpot_event(todo_flip: &TodoFlip, winner: &Addr, jackpot: &Coin) -> Event {
+        Event::new("jackpot")
+            .add_attribute("flip_id", todo_flip.id.to_string())
+            .add_attribute("winner", winner.to_string())
+            .add_attribute("jackpot", jackpot.to_string())
+    }
+
+    fn refund_event(todo_flip: TodoFlip, reason: &str) -> Event {
+        Event::new("refund_flip")
+            .add_attribute("flipper", todo_flip.wallet)
+            .add_attribute("flip_id", todo_flip.id.to_string())
+            .add_attribute("flip_amount", todo_flip.amount.to_string())
+            .add_attribute("fee", todo_flip.fee)
+            .add_attribute("reason", reason)
+    }
+
+    /// Event of a resolved flip, with the randomness it was resolved with
+    fn get_flip_event(todo_flip: &TodoFlip, flip_randomness: &FlipRandomness) -> Event {
+        let mut flip_event = Event::new("flip")
+            .add_attribute("flipper", todo_flip.wallet.clone())
+            .add_attribute("flip_id", todo_flip.id.to_string())
+            .add_attribute("flip_amount", todo_flip.amount.to_string())
+            .add_attribute("flip_pick", format!("{:?}", todo_flip.pick));
+        if let Some(client_seed) = &todo_flip.client_seed {
+            flip_event = flip_event.add_attribute("client_seed", client_seed);
+        }
+
+        // Add the randomness we resolve with to the event
+        match flip_randomness {
+            FlipRandomness::Block => {}
+            FlipRandomness::Revealed {
+                player_secret,
+                operator_seed,
+            } => {
+                flip_event = flip_event
+                    .add_attribute("player_secret", player_secret)
+                    .add_attribute("operator_seed", operator_seed);
+            }
+            FlipRandomness::PlayerTimeout => {
+                flip_event = flip_event.add_attribute("timeout", "player");
+            }
+            FlipRandomness::OperatorTimeout => {
+                flip_event = flip_event.add_attribute("timeout", "operator");
+            }
+            FlipRandomness::Drand { round, randomness } => {
+                flip_event = flip_event
+                    .add_attribute("drand_round", round.to_string())
+                    .add_attribute("drand_randomness", randomness);
+            }
+            FlipRandomness::Nois { job_id, randomness } => {
+                flip_event = flip_event
+                    .add_attribute("nois_job_id", job_id)
+                    .add_attribute("nois_randomness", randomness);
+            }
+            FlipRandomness::HouseSeed { seed } => {
+                flip_event = flip_event.add_attribute("house_seed", seed);
+            }
+            FlipRandomness::NoisTimeout => {}
+        };
+
+        if let Some(commit) = &todo_flip.commit {
+            flip_event = flip_event
+                .add_attribute("commitment", commit.commitment.clone())
+                .add_attribute("operator_round", commit.operator_round.to_string());
+        }
+
+        flip_event
+    }
+
+    /// Pay and score the given flips with their randomness,
+    /// the funds received by the current execute are not part of the bank
+    fn resolve_flips(
+        mut deps: DepsMut,
+        env: &Env,
+        config: &Config,
+        ready_flips: Vec<(TodoFlip, FlipRandomness)>,
+        received_funds: &[Coin],
+    ) -> Result<Response, ContractError> {
+        // PvP flips are paid with the stakes of both players, they don't need the bank
+        let (challenge_flips, mut ready_flips): (Vec<_>, Vec<_>) = ready_flips
+            .into_iter()
+            .partition(|(todo_flip, _)| todo_flip.opponent.is_some());
+
+        // A wallet can have several flips in the batch, score them in the order they started
+        ready_flips.sort_by_key(|(todo_flip, _)| todo_flip.id);
+
+        ensure_bank_covers(deps.as_ref(), env, &ready_flips, received_funds)?;
+
+        let mut msgs: Vec<CosmosMsg<CustomMsg>> = vec![];
+        // What the flips of this batch send out of the bank, auto-bets can't bet with it,
+        // nor with the funds received
+        let mut paid_out: HashMap<String, Uint128> = HashMap::new();
+        for received in received_funds {
+            *paid_out.entry(received.denom.clone()).or_default() += received.amount;
+        }
+        let mut response = Response::default();
+        let block_seed = get_block_seed(env);
+
+        for (todo_flip, flip_randomness) in ready_flips {
+            // Timed out nois flips are refunded, never resolved
+            if flip_randomness == FlipRandomness::NoisTimeout {
+                continue;
+            }
+            let flip_event = get_flip_event(&todo_flip, &flip_randomness);
+
+            // Get flip result (won or lost)
+            let FlipOutcome {
+                seed,
+                outcome_random,
+                result: flip_result,
+            } = roll_flip(&todo_flip, &flip_randomness, &block_seed);
+            let payout = if flip_result {
+                todo_flip.payout()
+            } else {
+                Uint128::zero()
+            };
+
+            // Auto-bet flips are paid into the budget, unless the auto-bet was withdrawn since
+            let auto_bet = get_pending_auto_bet(deps.storage, &todo_flip)?;
+
+            // Handle score (needed the streak info in Flip)
+            let mut score = match SCORES.load(deps.storage, &todo_flip.wallet) {
+                Ok(mut score) => score.update(flip_result, env.clone()),
+                Err(_) => FlipScore::new(flip_result, env.clone()),
+            };
+
+            // Create new flip
+            let flip = Flip {
+                id: todo_flip.id,
+                wallet: todo_flip.wallet.clone(),
+                amount: todo_flip.amount.clone(),
+                result: flip_result,
+                streak: score.streak.clone(),
+                timestamp: env.block.time,
+                block: env.block.height,
+                client_seed: todo_flip.client_seed.clone(),
+            };
+
+            // check if flipper did enough streak to win NFT (12)
+            if is_streak_nft_winner(config, &score) {
+                let mut nft_pool = NFT_REWARDS.load(deps.storage)?;
+                let mut streak_event = Event::new("streak-claim")
+                    .add_attribute("flipper", todo_flip.wallet.to_string())
+                    .add_attribute("flip_id", todo_flip.id.to_string());
+
+                if nft_pool.is_empty() {
+                    let rewards = STREAK_REWARDS.load(deps.storage)?;
+                    let to_send = rewards[rewards.len() - 1].clone();
+                    *paid_out.entry(NATIVE_DENOM.to_string()).or_default() += to_send.reward;
+
+                    msgs.push(
+                        BankMsg::Send {
+                            to_address: todo_flip.wallet.to_string(),
+                            amount: vec![coin(to_send.reward.u128(), NATIVE_DENOM)],
+                        }
+                        .into(),
+                    );
+
+                    //add claim amount to event
+                    streak_event = streak_event
+                        .clone()
+                        .add_attribute("claim", format!("{}{NATIVE_DENOM}", to_send.reward.u128()));
+                } else {
+                    // get the random index of the NFT to send
+                    let winning_nft_index =
+                        get_prize_random(&seed, &todo_flip) % nft_pool.len() as u64;
+                    let nft_to_send = nft_pool[winning_nft_index as usize].clone();
+
+                    // Send the NFT to the winner
+                    msgs.push(
+                        WasmMsg::Execute {
+                            contract_addr: nft_to_send.contract_addr.to_string(),
+                            msg: to_json_binary(&cw721::Cw721ExecuteMsg::TransferNft {
+                                recipient: todo_flip.wallet.to_string().clone(),
+                                token_id: nft_to_send.token_id.clone(),
+                            })?,
+                            funds: vec![],
+                        }
+                        .into(),
+                    );
+
+                    nft_pool.remove(winning_nft_index as usize);
+                    NFT_REWARDS.save(deps.storage, &nft_pool)?;
+
+                    //add claim amount to event
+                    streak_event = streak_event.clone().add_attribute(
+                        "claim",
+                        format!("{}/{}", nft_to_send.contract_addr, nft_to_send.token_id),
+                    );
+                }
+
+                // Reset the streak score of the flipper
+                score.streak.reset();
+
+                // Add event to response
+                response = response.add_event(streak_event);
+            }
+
+            // Save the score
+            SCORES.save(deps.storage, &todo_flip.wallet, &score)?;
+            score_flip(
+                deps.storage,
+                env,
+                config,
+                &todo_flip.wallet,
+                &todo_flip.amount,
+                flip_result,
+                payout,
+            )?;
+
+            // Save the flip so we can easily get stats later, the oldest ones are dropped
+            flips().save(deps.storage, flip.id, &flip)?;
+            prune_flips(deps.storage, flip.id)?;
+
+            // The referrer earns its share once the flip is resolved
+            credit_referrers(
+                deps.storage,
+                &todo_flip.amount.denom,
+                &todo_flip.referral_shares,
+            )?;
+
+            // Every flip funded the jackpot, a rolled one can win it whole
+            if outcome_random.is_some() {
+                if let Some(jackpot) = draw_jackpot(deps.storage, config, &todo_flip, &seed)? {
+                    response =
+                        response.add_event(jackpot_event(&todo_flip, &todo_flip.wallet, &jackpot));
+                    *paid_out.entry(jackpot.denom.clone()).or_default() += jackpot.amount;
+                    msgs.push(send_coin_msg(todo_flip.wallet.clone(), jackpot)?);
+                }
+            }
+
+            // Send funds if they won
+            if flip_result && auto_bet.is_none() {
+                *paid_out.entry(todo_flip.amount.denom.clone()).or_default() += payout;
+                msgs.push(send_coin_msg(
+                    todo_flip.wallet.clone(),
+                    coin(payout.u128(), todo_flip.amount.denom.clone()),
+                )?);
+            }
+
+            // Keep everything needed to verify the flip later
+            let operator_commitment = get_operator_commitment(deps.storage, &todo_flip)?;
+            let signature = get_beacon_signature(deps.storage, &todo_flip)?;
+            FLIP_PROOFS.save(
+                deps.storage,
+                todo_flip.id,
+                &FlipProof {
+                    id: todo_flip.id,
+                    wallet: todo_flip.wallet.clone(),
+                    amount: todo_flip.amount.clone(),
+                    pick: todo_flip.pick.clone(),
+                    fee: todo_flip.fee,
+                    payout,
+                    result: flip_result,
+                    client_seed: todo_flip.client_seed.clone(),
+                    start_block: todo_flip.block,
+                    block: env.block.height,
+                    timestamp: env.block.time,
+                    randomness: flip_randomness,
+                    tx_index: get_tx_index(env),
+                    commitment: todo_flip
+                        .commit
+                        .as_ref()
+                        .map(|commit| commit.commitment.clone()),
+                    operator_commitment,
+                    drand_round: todo_flip.drand_round,
+                    signature,
+                    seed,
+                    outcome_random,
+                    opponent: None,
+                    voucher: todo_flip.voucher,
+                },
+            )?;
+
+            response = response.clone().add_event(
+                flip_event.add_attribute("result", if flip_result { "won" } else { "lost" }),
+            );
+
+            if let Some(auto_bet) = auto_bet {
+                let auto_bet_event = continue_auto_bet(
+                    deps.branch(),
+                    env,
+                    config,
+                    auto_bet,
+                    &todo_flip,
+                    payout,
+                    paid_out
+                        .get(&todo_flip.amount.denom)
+                        .copied()
+                        .unwrap_or_default(),
+                    &mut msgs,
+                )?;
+                response = response.add_event(auto_bet_event);
+            }
+        }
+
+        let response = resolve_challenge_flips(
+            deps.storage,
+            env,
+            config,
+            challenge_flips,
+            response.add_messages(msgs),
+        )?;
+
+        Ok(response)
+    }
+
+    /// Make sure the bank can pay every flip that is not PvP, PvP flips are paid by their stakes.
+    /// The funds received by the current execute are in the balance already, not in the bank
+    fn ensure_bank_covers(
+        deps: Deps,
+        env: &Env,
+        ready_flips: &[(TodoFlip, FlipRandomness)],
+        received_funds: &[Coin],
+    ) -> Result<(), ContractError> {
+        let mut flip_denoms: HashMap<String, Uint128> = HashMap::with_capacity(1);
+        for (todo_flip, _) in ready_flips {
+            if todo_flip.opponent.is_none() {
+                *flip_denoms
+                    .entry(todo_flip.amount.denom.clone())
+                    .or_default() += todo_flip.payout();
+            }
+        }
+
+        // Make sure we have funds to pay for all the flips
+        for (denom, total_payout) in flip_denoms {
+            let fees = FEES.load(deps.storage, denom.clone())?;
+            let held_funds = get_held_funds(deps.storage, &denom)?;
+            let received: Uint128 = received_funds
+                .iter()
+                .filter(|received| received.denom == denom)
+                .map(|received| received.amount)
+                .sum();
+
+            let contract_balance =
+                query_denom_balance(&deps.querier, &env.contract.address, &denom)?;
+
+            let bank_balance = contract_balance
+                .checked_sub(fees + held_funds + received)
+                .map_err(|_| ContractError::ContractMissingFunds(denom.clone()))?;
+            ensure!(
+                bank_balance >= total_payout,
+                ContractError::ContractMissingFunds(denom)
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Pay both stakes of every PvP flip to its winner,
+    /// PvP flips don't count for scores and streaks, they are not paid by the house
+    fn resolve_challenge_flips(
+        storage: &mut dyn Storage,
+        env: &Env,
+        config: &Config,
+        challenge_flips: Vec<(TodoFlip, FlipRandomness)>,
+        mut response: Response,
+    ) -> Result<Response, ContractError> {
+        let block_seed = get_block_seed(env);
+
+        for (todo_flip, flip_randomness) in challenge_flips {
+            let Some(opponent) = todo_flip.opponent.clone() else {
+                continue;
+            };
+            // Timed out nois flips are refunded, never resolved
+            if flip_randomness == FlipRandomness::NoisTimeout {
+                continue;
+            }
+            let flip_event = get_flip_event(&todo_flip, &flip_randomness);
+
+            // The flipper wins with its pick, the opponent otherwise
+            let FlipOutcome {
+                seed,
+                outcome_random,
+                result: flip_result,
+            } = roll_flip(&todo_flip, &flip_randomness, &block_seed);
+            let winner = if flip_result {
+                todo_flip.wallet.clone()
+            } else {
+                opponent.clone()
+            };
+
+            let stakes = todo_flip.amount.amount * Uint128::new(2);
+            CHALLENGE_ESCROW.update(storage, todo_flip.amount.denom.clone(), |escrow| {
+                Ok::<_, ContractError>(escrow.unwrap_or_default().checked_sub(stakes)?)
+            })?;
+            credit_referrers(storage, &todo_flip.amount.denom, &todo_flip.referral_shares)?;
+
+            // The jackpot goes to the winner of the challenge
+            if outcome_random.is_some() {
+                if let Some(jackpot) = draw_jackpot(storage, config, &todo_flip, &seed)? {
+                    response = response
+                        .add_event(jackpot_event(&todo_flip, &winner, &jackpot))
+                        .add_message(send_coin_msg(winner.clone(), jackpot)?);
+                }
+            }
+
+            let operator_commitment = get_operator_commitment(storage, &todo_flip)?;
+            let signature = get_beacon_signature(storage, &todo_flip)?;
+            FLIP_PROOFS.save(
+                storage,
+                todo_flip.id,
+                &FlipProof {
+                    id: todo_flip.id,
+                    wallet: todo_flip.wallet.clone(),
+                    amount: todo_flip.amount.clone(),
+                    pick: todo_flip.pick.clone(),
+                    fee: todo_flip.fee,
+                    payout: if flip_result { stakes } else { Uint128::zero() },
+                    result: flip_result,
+                    client_seed: todo_flip.client_seed.clone(),
+                    start_block: todo_flip.block,
+                    block: env.block.height,
+                    timestamp: env.block.time,
+                    randomness: flip_randomness,
+                    tx_index: get_tx_index(env),
+                    commitment: todo_flip
+                        .commit
+                        .as_ref()
+                        .map(|commit| commit.commitment.clone()),
+                    operator_commitment,
+                    drand_round: todo_flip.drand_round,
+                    signature,
+                    seed,
+                    outcome_random,
+                    opponent: Some(opponent.clone()),
+                    voucher: false,
+                },
+            )?;
+
+            response = response
+                .add_message(BankMsg::Send {
+                    to_address: winner.to_string(),
+                    amount: vec![coin(stakes.u128(), todo_flip.amount.denom.clone())],
+                })
+                .add_event(
+                    flip_event
+                        .add_attribute("opponent", opponent)
+                        .add_attribute("winner", winner)
+                        .add_attribute("result", if flip_result { "won" } else { "lost" }),
+                );
+        }
+
+        Ok(response)
+    }
+
+    fn is_streak_nft_winner(config: &Config, score: &FlipScore) -> bool {
+        score.streak.amount == config.streak_nft_winning_amount
+    }
+}
+
+mod streak_execute {
+    use cosmwasm_std::{coins, BankMsg, Event};
+
+    use crate::{
+        chain::NATIVE_DENOM,
+        state::{ALLOWED_SEND_NFT, NFT_REWARDS, SCORES, STREAK_REWARDS},
+        types::NftReward,
+    };
+
+    use super::*;
+
+    pub(crate) fn receive_nft(
+        deps: DepsMut,
+        info: MessageInfo,
+        config: &Config,
+        sender: String,
+        token_id: String,
+    ) -> Result<Response, ContractError> {
+        // Verify the sender can send NFTs to the contract
+        let allowed_to_send_nfts = ALLOWED_SEND_NFT.load(deps.storage)?;
+
+        if !allowed_to_send_nfts.contains(&deps.api.addr_validate(&sender)?) {
+            return Err(ContractError::UnauthorizedToSendNft);
+        }
+
+        // Verify our NFT rewards pool is not full
+        let mut nft_rewards = NFT_REWARDS.load(deps.storage)?;
+
+        if nft_rewards.len() as u32 >= config.nft_pool_max {
+            return Err(ContractError::MaxNFTStreakRewardsReached);
+        }
+
+        // info.sender is the NFT contract address, token_id is the NFT id
+        // Add the NFT to the pool of NFTs
+        nft_rewards.push(NftReward::new(info.sender.clone(), token_id.clone()));
+        NFT_REWARDS.save(deps.storage, &nft_rewards)?;
+
+        Ok(Response::default()
+            .add_attribute("action", "add_nft")
+            .add_attribute("nft_contract", info.sender)
+            .add_attribute("token_id", token_id)
+            .add_attribute("nft_pool_size", nft_rewards.len().to_string()))
+    }
+
+    pub(crate) fn execute_claim(
+        deps: DepsMut,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        let streak_rewards = STREAK_REWARDS.load(deps.storage)?;
+        let mut score = SCORES.load(deps.storage, &info.sender)?;
+
+        // make sure score is higher or equal to the lowest streak reward
+        if score.streak.amount < streak_rewards[0].streak {
+            return Err(ContractError::LowStreak(streak_rewards[0].streak));
+        }
+
+        // find the matching reward to claim
+        match streak_rewards
+            .iter()
+            .find(|r| r.streak == score.streak.amount)
+        {
+            Some(reward) => {
+                score.streak.reset();
+                SCORES.save(deps.storage, &info.sender, &score)?;
+
+                Ok(Response::default()
+                    .add_event(
+                        Event::new("streak-claim")
+                            .add_attribute("flipper", info.sender.to_string())
+                            .add_attribute("streak", reward.streak.to_string())
+                            .add_attribute("claim", format!("{}{NATIVE_DENOM}", reward.reward)),
+                    )
+                    .add_message(BankMsg::Send {
+                        to_address: info.sender.to_string(),
+                        amount: coins(reward.reward.u128(), NATIVE_DENOM),
+                    }))
+            }
+            None => Err(ContractError::NotEligibleForStreakReward(
+                score.streak.amount,
+            )),
+        }
+    }
+}
+
+mod challenge_execute {
+    use cosmwasm_std::{coin, ensure, BankMsg, CosmosMsg, Event};
+    use cw_utils::must_pay;
+
+    use crate::chain::CustomMsg;
+    use crate::helpers::ensure_correct_funds;
+    use crate::state::{
+        challenges, get_next_challenge_id, get_next_flip_id, todo_flips, CHALLENGE_ESCROW,
+        CHALLENGE_ID, FLIP_ID,
+    };
+    use crate::types::{Challenge, PickTypes, TodoFlip};
+
+    use super::flip_execute::{
+        collect_fee, ensure_block_limit, get_drand_round, get_house_seed_index,
+        request_nois_randomness,
+    };
+    use super::*;
+
+    /// Commit-reveal flips need a secret from the flipper, challenges don't have one
+    fn ensure_challenges_supported(config: &Config) -> Result<(), ContractError> {
+        ensure!(
+            !matches!(config.randomness, RandomnessSource::CommitReveal { .. }),
+            ContractError::ChallengesNotSupported
+        );
+        Ok(())
+    }
+
+    pub(crate) fn execute_open_challenge(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        config: &Config,
+        pick: PickTypes,
+        amount: Uint128,
+    ) -> Result<Response, ContractError> {
+        ensure_challenges_supported(config)?;
+        ensure!(
+            matches!(pick, PickTypes::Heads | PickTypes::Tails),
+            ContractError::InvalidChallengePick
+        );
+
+        // Verify we only have one coin sent
+        if info.funds.len() != 1 {
+            return Err(ContractError::WrongFundsAmount);
+        }
+
+        let funds = info.funds[0].clone();
+        // Verify the sent funds is in supported denom.
+        let denom = if config.denoms.iter().any(|x| x == &funds.denom) {
+            funds.denom
+        } else {
+            return Err(ContractError::WrongDenom { denom: funds.denom });
+        };
+
+        // There is no max stake, the bank never pays a challenge
+        let Some(bet_limits) = config.denom_limits.get(&denom) else {
+            return Err(ContractError::NoBetLimits { denom });
+        };
+        ensure!(
+            amount >= bet_limits.min,
+            ContractError::UnderTheLimitBet {
+                min_limit: (bet_limits.min / Uint128::new(1000000)).to_string()
+            }
+        );
+
+        let fee = ensure_correct_funds(funds.amount, &[amount], config.fees.flip_bps)?[0];
+
+        // Stake and fee are held until the challenge is accepted or cancelled
+        CHALLENGE_ESCROW.update(deps.storage, denom.clone(), |escrow| {
+            Ok::<_, ContractError>(escrow.unwrap_or_default().checked_add(funds.amount)?)
+        })?;
+
+        let id = get_next_challenge_id(deps.storage)?;
+        CHALLENGE_ID.save(deps.storage, &id)?;
+
+        let challenge = Challenge {
+            id,
+            creator: info.sender,
+            amount: coin(amount.u128(), denom),
+            pick,
+            fee,
+            block: env.block.height,
+            expires_at: env.block.height + config.challenge_expiry,
+        };
+        challenges().save(deps.storage, id, &challenge)?;
+
+        Ok(Response::default().add_event(
+            Event::new("open_challenge")
+                .add_attribute("id", id.to_string())
+                .add_attribute("creator", challenge.creator)
+                .add_attribute("amount", challenge.amount.to_string())
+                .add_attribute("pick", format!("{:?}", challenge.pick))
+                .add_attribute("expires_at", challenge.expires_at.to_string()),
+        ))
+    }
+
+    /// The accepted challenge becomes a flip of the creator against the opponent,
+    /// done by DoFlips like any other flip
+    pub(crate) fn execute_accept_challenge(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        config: &Config,
+        id: u64,
+    ) -> Result<Response, ContractError> {
+        ensure_challenges_supported(config)?;
+
+        let challenge = challenges()
+            .may_load(deps.storage, id)?
+            .ok_or(ContractError::ChallengeNotFound(id))?;
+        ensure!(
+            env.block.height < challenge.expires_at,
+            ContractError::ChallengeExpired(id)
+        );
+        ensure!(
+            info.sender != challenge.creator,
+            ContractError::OwnChallenge
+        );
+
+        // The opponent pays the same stake and fee as the creator
+        let paid_amount = must_pay(&info, &challenge.amount.denom)?;
+        ensure_eq!(
+            paid_amount,
+            challenge.amount.amount.checked_add(challenge.fee)?,
+            ContractError::WrongPaidAmount
+        );
+
+        ensure_block_limit(deps.storage, &env, config, 1)?;
+        let house_seed_index = get_house_seed_index(deps.storage, config)?;
+
+        challenges().remove(deps.storage, id)?;
+
+        // Both stakes stay held for the winner, the fees are ours now
+        let denom = challenge.amount.denom.clone();
+        CHALLENGE_ESCROW.update(deps.storage, denom.clone(), |escrow| {
+            Ok::<_, ContractError>(
+                escrow
+                    .unwrap_or_default()
+                    .checked_add(challenge.amount.amount)?
+                    .checked_sub(challenge.fee)?,
+            )
+        })?;
+        // Both players paid the fee
+        let mut jackpot_share = Uint128::zero();
+        let mut referral_shares = vec![];
+        for wallet in [&challenge.creator, &info.sender] {
+            let (wallet_jackpot_share, referral_share) =
+                collect_fee(deps.storage, config, wallet, &denom, challenge.fee)?;
+            jackpot_share += wallet_jackpot_share;
+            referral_shares.extend(referral_share);
+        }
+
+        let flip_id = get_next_flip_id(deps.storage);
+        FLIP_ID.save(deps.storage, &flip_id)?;
+
+        let mut msgs: Vec<CosmosMsg<CustomMsg>> = vec![];
+        let nois_job_id = request_nois_randomness(deps.storage, config, flip_id, &mut msgs)?;
+        let drand_round = get_drand_round(&env, config);
+
+        todo_flips().save(
+            deps.storage,
+            flip_id,
+            &TodoFlip {
+                id: flip_id,
+                wallet: challenge.creator.clone(),
+                amount: challenge.amount,
+                pick: challenge.pick,
+                block: env.block.height,
+                timestamp: env.block.time,
+                commit: None,
+                drand_round,
+                nois_job_id,
+                house_seed_index,
+                fee: challenge.fee,
+                jackpot_share,
+                referral_shares,
+                client_seed: None,
+                opponent: Some(info.sender.clone()),
+                auto_bet: false,
+                voucher: false,
+            },
+        )?;
+
+        let mut accept_event = Event::new("accept_challenge")
+            .add_attribute("id", id.to_string())
+            .add_attribute("flip_id", flip_id.to_string())
+            .add_attribute("creator", challenge.creator)
+            .add_attribute("opponent", info.sender);
+        if let Some(drand_round) = drand_round {
+            accept_event = accept_event.add_attribute("drand_round", drand_round.to_string());
+        }
+
+        Ok(Response::default()
+            .add_event(accept_event)
+            .add_messages(msgs))
+    }
+
+    pub(crate) fn execute_cancel_challenge(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        id: u64,
+    ) -> Result<Response, ContractError> {
+        let challenge = challenges()
+            .may_load(deps.storage, id)?
+            .ok_or(ContractError::ChallengeNotFound(id))?;
+        ensure!(
+            info.sender == challenge.creator || env.block.height >= challenge.expires_at,
+            ContractError::NotChallengeCreator
+        );
+
+        challenges().remove(deps.storage, id)?;
+
+        let refund_amount = challenge.amount.amount.checked_add(challenge.fee)?;
+        CHALLENGE_ESCROW.update(deps.storage, challenge.amount.denom.clone(), |escrow| {
+            Ok::<_, ContractError>(escrow.unwrap_or_default().checked_sub(refund_amount)?)
+        })?;
+
+        Ok(Response::default()
+            .add_message(BankMsg::Send {
+                to_address: challenge.creator.to_string(),
+                amount: vec![coin(refund_amount.u128(), challenge.amount.denom)],
+            })
+            .add_event(
+                Event::new("cancel_challenge")
+                    .add_attribute("id", id.to_string())
+                    .add_attribute("creator", challenge.creator),
+            ))
+    }
+}
+
+mod auto_bet_execute {
+    use cosmwasm_std::{coin, ensure, BankMsg, CosmosMsg, Decimal, Event};
+
+    use crate::chain::CustomMsg;
+    use crate::helpers::{bps_to_decimal, calc_flip_fee, query_denom_balance};
+    use crate::state::{
+        get_held_funds, get_next_flip_id, todo_flips, AUTO_BETS, AUTO_BET_BUDGETS, FLIP_ID,
+    };
+    use crate::types::{AutoBet, AutoBetStrategy, PickRule, PickTypes, TodoFlip};
+    use crate::types::{MAX_WIN_CHANCE, MIN_WIN_CHANCE};
+
+    use super::flip_execute::{
+        collect_fee, ensure_block_limit, get_drand_round, get_house_seed_index,
+        request_nois_randomness,
+    };
+    use super::*;
+
+    pub(crate) fn execute_start_auto_bet(
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        config: &Config,
+        strategy: AutoBetStrategy,
+        sent_funds: &HashMap<String, Uint128>,
+    ) -> Result<Response, ContractError> {
+        // Every commit-reveal flip needs a commitment of the player
+        ensure!(
+            !matches!(config.randomness, RandomnessSource::CommitReveal { .. }),
+            ContractError::AutoBetsNotSupported
+        );
+        ensure!(
+            !AUTO_BETS.has(deps.storage, &info.sender),
+            ContractError::AutoBetExists
+        );
+
+        ensure!(strategy.max_rounds > 0, ContractError::InvalidAutoBetRounds);
+        ensure!(
+            strategy.stop_loss != Some(Uint128::zero())
+                && strategy.take_profit != Some(Uint128::zero()),
+            ContractError::InvalidAutoBetLimits
+        );
+        ensure!(
+            strategy.pick.is_valid(),
+            ContractError::InvalidWinChance {
+                min: MIN_WIN_CHANCE,
+                max: MAX_WIN_CHANCE
+            }
+        );
+        if let PickTypes::RollUnder { .. } = strategy.pick {
+            ensure!(
+                strategy.pick_rule == PickRule::Fixed,
+                ContractError::InvalidAutoBetPickRule
+            );
+        }
+
+        // Verify we only have one coin sent
+        if info.funds.len() != 1 {
+            return Err(ContractError::WrongFundsAmount);
+        }
+
+        let funds = info.funds[0].clone();
+        // Verify the sent funds is in supported denom.
+        let denom = if config.denoms.iter().any(|x| x == &funds.denom) {
+            funds.denom
+        } else {
+            return Err(ContractError::WrongDenom { denom: funds.denom });
+        };
+
+        // Make sure that the base bet is within our limits
+        let Some(bet_limits) = config.denom_limits.get(&denom) else {
+            return Err(ContractError::NoBetLimits { denom });
+        };
+        if let Some(max_payout) = bet_limits.max_payout {
+            ensure!(
+                strategy.pick.payout(strategy.base_bet) <= max_payout,
+                ContractError::OverThePayoutLimit {
+                    max_limit: (max_payout / Uint128::new(1000000)).to_string()
+                }
+            );
+        }
+        ensure!(
+            strategy.base_bet <= bet_limits.max,
+            ContractError::OverTheLimitBet {
+                max_limit: (bet_limits.max / Uint128::new(1000000)).to_string()
+            }
+        );
+        ensure!(
+            strategy.base_bet >= bet_limits.min,
+            ContractError::UnderTheLimitBet {
+                min_limit: (bet_limits.min / Uint128::new(1000000)).to_string()
+            }
+        );
+
+        let base_fee = get_bet_fee(config, strategy.base_bet)?;
+        ensure!(
+            funds.amount >= strategy.base_bet.checked_add(base_fee)?,
+            ContractError::AutoBetBudgetTooLow
+        );
+
+        ensure_block_limit(deps.storage, &env, config, 1)?;
+
+        AUTO_BET_BUDGETS.update(deps.storage, denom.clone(), |budgets| {
+            Ok::<_, ContractError>(budgets.unwrap_or_default().checked_add(funds.amount)?)
+        })?;
+
+        let mut auto_bet = AutoBet {
+            wallet: info.sender.clone(),
+            next_pick: strategy.pick.clone(),
+            next_bet: strategy.base_bet,
+            strategy,
+            denom,
+            deposit: funds.amount,
+            budget: funds.amount,
+            rounds: 0,
+            pending_flip: None,
+        };
+
+        // The flips resolved before are sending funds out of the bank already
+        let paid_out = sent_funds.get(&auto_bet.denom).copied().unwrap_or_default();
+        let mut msgs: Vec<CosmosMsg<CustomMsg>> = vec![];
+        if let Some(reason) = queue_auto_bet(
+            deps.branch(),
+            &env,
+            config,
+            &mut auto_bet,
+            paid_out,
+            &mut msgs,
+        )? {
+            return Err(ContractError::CantQueueAutoBet(reason.to_string()));
+        }
+        AUTO_BETS.save(deps.storage, &info.sender, &auto_bet)?;
+
+        Ok(Response::default()
+            .add_event(
+                Event::new("start_auto_bet")
+                    .add_attribute("wallet", info.sender)
+                    .add_attribute(
+                        "deposit",
+                        coin(auto_bet.deposit.u128(), auto_bet.denom).to_string(),
+                    )
+                    .add_attribute(
+                        "flip_id",
+                        auto_bet.pending_flip.unwrap_or_default().to_string(),
+                    ),
+            )
+            .add_messages(msgs))
+    }
+
+    pub(crate) fn execute_withdraw_auto_bet(
+        deps: DepsMut,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        let auto_bet = AUTO_BETS
+            .may_load(deps.storage, &info.sender)?
+            .ok_or(ContractError::NoAutoBet)?;
+
+        let event = stop_event(&auto_bet, "withdraw");
+        let msgs = stop_auto_bet(deps.storage, auto_bet)?;
+
+        Ok(Response::default().add_event(event).add_messages(msgs))
+    }
+
+    /// The auto-bet waiting for the result of this flip, if any
+    pub(crate) fn get_pending_auto_bet(
+        storage: &dyn Storage,
+        todo_flip: &TodoFlip,
+    ) -> StdResult<Option<AutoBet>> {
+        if !todo_flip.auto_bet {
+            return Ok(None);
+        }
+
+        Ok(AUTO_BETS
+            .may_load(storage, &todo_flip.wallet)?
+            .filter(|auto_bet| auto_bet.pending_flip == Some(todo_flip.id)))
+    }
+
+    /// Add the payout of the done flip to the budget and queue the next round,
+    /// or stop the auto-bet if its strategy says so
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn continue_auto_bet(
+        mut deps: DepsMut,
+        env: &Env,
+        config: &Config,
+        mut auto_bet: AutoBet,
+        todo_flip: &TodoFlip,
+        payout: Uint128,
+        paid_out: Uint128,
+        msgs: &mut Vec<CosmosMsg<CustomMsg>>,
+    ) -> Result<Event, ContractError> {
+        let won = !payout.is_zero();
+        if won {
+            auto_bet.budget = auto_bet.budget.checked_add(payout)?;
+            AUTO_BET_BUDGETS.update(deps.storage, auto_bet.denom.clone(), |budgets| {
+                Ok::<_, ContractError>(budgets.unwrap_or_default().checked_add(payout)?)
+            })?;
+        }
+
+        auto_bet.pending_flip = None;
+        auto_bet.next_bet = auto_bet.strategy.next_bet(todo_flip.amount.amount, won);
+        auto_bet.next_pick = auto_bet.strategy.next_pick(&todo_flip.pick, won);
+
+        if let Some(reason) =
+            queue_auto_bet(deps.branch(), env, config, &mut auto_bet, paid_out, msgs)?
+        {
+            let event = stop_event(&auto_bet, reason);
+            msgs.extend(
+                stop_auto_bet(deps.storage, auto_bet)?
+                    .into_iter()
+                    .map(CosmosMsg::from),
+            );
+            return Ok(event);
+        }
+        AUTO_BETS.save(deps.storage, &auto_bet.wallet, &auto_bet)?;
+
+        Ok(Event::new("auto_bet")
+            .add_attribute("wallet", auto_bet.wallet)
+            .add_attribute("round", auto_bet.rounds.to_string())
+            .add_attribute(
+                "flip_id",
+                auto_bet.pending_flip.unwrap_or_default().to_string(),
+            )
+            .add_attribute("budget", auto_bet.budget))
+    }
+
+    /// Remove the auto-bet and send back what is left of its budget
+    pub(crate) fn stop_auto_bet(
+        storage: &mut dyn Storage,
+        auto_bet: AutoBet,
+    ) -> Result<Option<BankMsg>, ContractError> {
+        AUTO_BETS.remove(storage, &auto_bet.wallet);
+        AUTO_BET_BUDGETS.update(storage, auto_bet.denom.clone(), |budgets| {
+            Ok::<_, ContractError>(budgets.unwrap_or_default().checked_sub(auto_bet.budget)?)
+        })?;
+
+        if auto_bet.budget.is_zero() {
+            return Ok(None);
+        }
+
+        Ok(Some(BankMsg::Send {
+            to_address: auto_bet.wallet.to_string(),
+            amount: vec![coin(auto_bet.budget.u128(), auto_bet.denom)],
+        }))
+    }
+
+    fn stop_event(auto_bet: &AutoBet, reason: &str) -> Event {
+        Event::new("stop_auto_bet")
+            .add_attribute("wallet", auto_bet.wallet.clone())
+            .add_attribute("rounds", auto_bet.rounds.to_string())
+            .add_attribute("budget", auto_bet.budget)
+            .add_attribute("reason", reason)
+    }
+
+    fn get_bet_fee(config: &Config, bet: Uint128) -> Result<Uint128, ContractError> {
+        calc_flip_fee(
+            Decimal::from_atomics(bet, 0)?,
+            bps_to_decimal(config.fees.flip_bps),
+        )
+    }
+
+    /// Take the next bet and its fee out of the budget and start its flip.
+    /// `paid_out` is what the current batch of flips already sends out of the bank.
+    /// Returns why the auto-bet must stop instead, if it must
+    fn queue_auto_bet(
+        deps: DepsMut,
+        env: &Env,
+        config: &Config,
+        auto_bet: &mut AutoBet,
+        paid_out: Uint128,
+        msgs: &mut Vec<CosmosMsg<CustomMsg>>,
+    ) -> Result<Option<&'static str>, ContractError> {
+        let strategy = &auto_bet.strategy;
+        if auto_bet.rounds >= strategy.max_rounds {
+            return Ok(Some("max_rounds"));
+        }
+        if let Some(stop_loss) = strategy.stop_loss {
+            if auto_bet.budget.checked_add(stop_loss)? <= auto_bet.deposit {
+                return Ok(Some("stop_loss"));
+            }
+        }
+        if let Some(take_profit) = strategy.take_profit {
+            if auto_bet.budget >= auto_bet.deposit.checked_add(take_profit)? {
+                return Ok(Some("take_profit"));
+            }
+        }
+
+        // A progression can grow the bet out of the limits
+        let bet = auto_bet.next_bet;
+        let payout = auto_bet.next_pick.payout(bet);
+        let Some(bet_limits) = config
+            .denom_limits
+            .get(&auto_bet.denom)
+            .filter(|_| config.denoms.contains(&auto_bet.denom))
+        else {
+            return Ok(Some("denom_removed"));
+        };
+        if bet > bet_limits.max
+            || bet < bet_limits.min
+            || matches!(bet_limits.max_payout, Some(max_payout) if payout > max_payout)
+        {
+            return Ok(Some("bet_limit"));
+        }
+
+        let fee = get_bet_fee(config, bet)?;
+        let bet_with_fee = bet.checked_add(fee)?;
+        if auto_bet.budget < bet_with_fee {
+            return Ok(Some("budget"));
+        }
+
+        let Ok(house_seed_index) = get_house_seed_index(deps.storage, config) else {
+            return Ok(Some("house_chain"));
+        };
+        if ensure_block_limit(deps.storage, env, config, 1).is_err() {
+            return Ok(Some("block_limit"));
+        }
+
+        // Make sure we have funds to pay for the bet
+        let fees = FEES
+            .may_load(deps.storage, auto_bet.denom.clone())?
+            .unwrap_or_default();
+        let held_funds = get_held_funds(deps.storage, &auto_bet.denom)?;
+        let balance = query_denom_balance(&deps.querier, &env.contract.address, &auto_bet.denom)?;
+        let bank_balance = balance.checked_sub(fees + held_funds + paid_out);
+        if !matches!(bank_balance, Ok(bank_balance) if bank_balance >= payout) {
+            return Ok(Some("bank"));
+        }
+
+        // The bet leaves the budget for the bank, the fee is ours
+        auto_bet.budget -= bet_with_fee;
+        AUTO_BET_BUDGETS.update(deps.storage, auto_bet.denom.clone(), |budgets| {
+            Ok::<_, ContractError>(budgets.unwrap_or_default().checked_sub(bet_with_fee)?)
+        })?;
+        let (jackpot_share, referral_share) =
+            collect_fee(deps.storage, config, &auto_bet.wallet, &auto_bet.denom, fee)?;
+
+        let id = get_next_flip_id(deps.storage);
+        FLIP_ID.save(deps.storage, &id)?;
+
+        let nois_job_id = request_nois_randomness(deps.storage, config, id, msgs)?;
+        let drand_round = get_drand_round(env, config);
+
+        todo_flips().save(
+            deps.storage,
+            id,
+            &TodoFlip {
+                id,
+                wallet: auto_bet.wallet.clone(),
+                amount: coin(bet.u128(), auto_bet.denom.clone()),
+                pick: auto_bet.next_pick.clone(),
+                block: env.block.height,
+                timestamp: env.block.time,
+                commit: None,
+                drand_round,
+                nois_job_id,
+                house_seed_index,
+                fee,
+                jackpot_share,
+                referral_shares: referral_share.into_iter().collect(),
+                client_seed: None,
+                opponent: None,
+                auto_bet: true,
+                voucher: false,
+            },
+        )?;
+
+        auto_bet.rounds += 1;
+        auto_bet.pending_flip = Some(id);
+
+        Ok(None)
+    }
+}
+
+mod voucher_execute {
+    use cosmwasm_std::{ensure, Addr, Coin, Event};
+
+    use crate::state::{get_next_voucher_id, VOUCHERS, VOUCHER_ID, VOUCHER_ISSUERS};
+    use crate::types::Voucher;
+
+    use super::*;
+
+    /// Only the admin and the voucher issuers can grant and revoke vouchers
+    fn ensure_voucher_issuer(
+        storage: &dyn Storage,
+        config: &Config,
+        info: &MessageInfo,
+    ) -> Result<(), ContractError> {
+        let issuers = VOUCHER_ISSUERS.may_load(storage)?.unwrap_or_default();
+        ensure!(
+            config.admin == info.sender || issuers.contains(&info.sender),
+            ContractError::Unauthorized
+        );
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn execute_grant_voucher(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        config: &Config,
+        wallet: String,
+        amount: Coin,
+        flips: u32,
+        expires_in: u64,
+    ) -> Result<Response, ContractError> {
+        ensure_voucher_issuer(deps.storage, config, &info)?;
+        let wallet = deps.api.addr_validate(&wallet)?;
 
-                if nft_pool.is_empty() {
-                    let rewards = STREAK_REWARDS.load(deps.storage)?;
-                    let to_send = rewards[rewards.len() - 1].clone();
+        ensure!(flips > 0, ContractError::InvalidVoucherFlips);
+        ensure!(expires_in > 0, ContractError::InvalidVoucherExpiry);
 
-                    msgs.push(
-                        BankMsg::Send {
-                            to_address: todo_flip.wallet.to_string(),
-                            amount: vec![coin(to_send.reward.u128(), "ustars".to_string())],
-                        }
-                        .into(),
-                    );
+        // Make sure that the free flips are flips we would take
+        ensure!(
+            config.denoms.contains(&amount.denom),
+            ContractError::WrongDenom {
+                denom: amount.denom
+            }
+        );
+        let Some(bet_limits) = config.denom_limits.get(&amount.denom) else {
+            return Err(ContractError::NoBetLimits {
+                denom: amount.denom,
+            });
+        };
+        ensure!(
+            amount.amount <= bet_limits.max,
+            ContractError::OverTheLimitBet {
+                max_limit: (bet_limits.max / Uint128::new(1000000)).to_string()
+            }
+        );
+        ensure!(
+            amount.amount >= bet_limits.min,
+            ContractError::UnderTheLimitBet {
+                min_limit: (bet_limits.min / Uint128::new(1000000)).to_string()
+            }
+        );
 
-                    //add claim amount to event
-                    streak_event = streak_event
-                        .clone()
-                        .add_attribute("claim", format!("{}ustars", to_send.reward.u128()));
-                } else {
-                    // get the random index of the NFT to send
-                    let winning_nft_index = rand % nft_pool.len() as u64;
-                    let nft_to_send = nft_pool[winning_nft_index as usize].clone();
+        let id = get_next_voucher_id(deps.storage)?;
+        VOUCHER_ID.save(deps.storage, &id)?;
 
-                    // Send the NFT to the winner
-                    msgs.push(
-                        WasmMsg::Execute {
-                            contract_addr: nft_to_send.contract_addr.to_string(),
-                            msg: to_json_binary(&cw721::Cw721ExecuteMsg::TransferNft {
-                                recipient: todo_flip.wallet.to_string().clone(),
-                                token_id: nft_to_send.token_id.clone(),
-                            })?,
-                            funds: vec![],
-                        }
-                        .into(),
-                    );
+        let voucher = Voucher {
+            id,
+            amount,
+            flips,
+            expires_at: env.block.height + expires_in,
+        };
+        VOUCHERS.save(deps.storage, (&wallet, id), &voucher)?;
+
+        Ok(Response::default().add_event(
+            Event::new("grant_voucher")
+                .add_attribute("id", id.to_string())
+                .add_attribute("wallet", wallet)
+                .add_attribute("amount", voucher.amount.to_string())
+                .add_attribute("flips", flips.to_string())
+                .add_attribute("expires_at", voucher.expires_at.to_string())
+                .add_attribute("issuer", info.sender),
+        ))
+    }
 
-                    nft_pool.remove(winning_nft_index as usize);
-                    NFT_REWARDS.save(deps.storage, &nft_pool)?;
+    pub(crate) fn execute_revoke_voucher(
+        deps: DepsMut,
+        info: MessageInfo,
+        config: &Config,
+        wallet: String,
+        id: u64,
+    ) -> Result<Response, ContractError> {
+        ensure_voucher_issuer(deps.storage, config, &info)?;
+        let wallet = deps.api.addr_validate(&wallet)?;
 
-                    //add claim amount to event
-                    streak_event = streak_event.clone().add_attribute(
-                        "claim",
-                        format!("{}/{}", nft_to_send.contract_addr, nft_to_send.token_id),
-                    );
-                }
+        ensure!(
+            VOUCHERS.has(deps.storage, (&wallet, id)),
+            ContractError::VoucherNotFound(id)
+        );
+        VOUCHERS.remove(deps.storage, (&wallet, id));
 
-                // Reset the streak score of the flipper
-                score.streak.reset();
+        Ok(Response::default().add_event(
+            Event::new("revoke_voucher")
+                .add_attribute("id", id.to_string())
+                .add_attribute("wallet", wallet),
+        ))
+    }
 
-                // Add event to response
-                response = response.add_event(streak_event);
+    /// Use a free flip of the oldest voucher of the wallet for this bet, denom and amount,
+    /// the expired vouchers of the wallet are removed on the way
+    pub(crate) fn take_voucher(
+        storage: &mut dyn Storage,
+        env: &Env,
+        wallet: &Addr,
+        bet: &Coin,
+    ) -> Result<Voucher, ContractError> {
+        let vouchers = VOUCHERS
+            .prefix(wallet)
+            .range(storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, voucher)| voucher))
+            .collect::<StdResult<Vec<Voucher>>>()?;
+
+        let mut usable_voucher = None;
+        for voucher in vouchers {
+            if env.block.height >= voucher.expires_at {
+                VOUCHERS.remove(storage, (wallet, voucher.id));
+            } else if usable_voucher.is_none() && voucher.amount == *bet {
+                usable_voucher = Some(voucher);
             }
+        }
 
-            // Save the score
-            SCORES.save(deps.storage, &todo_flip.wallet, &score)?;
+        let mut voucher = usable_voucher.ok_or(ContractError::NoVoucher)?;
+        voucher.flips -= 1;
+        if voucher.flips == 0 {
+            VOUCHERS.remove(storage, (wallet, voucher.id));
+        } else {
+            VOUCHERS.save(storage, (wallet, voucher.id), &voucher)?;
+        }
 
-            // Update last flips vector
-            if last_flips.len() >= 5 {
-                last_flips.remove(0);
-            }
-            last_flips.push(flip);
+        Ok(voucher)
+    }
+}
 
-            // Send funds if they won
-            if flip_result {
-                let pay = todo_flip.amount.amount * Uint128::new(2); // double the amount
-                msgs.push(
-                    BankMsg::Send {
-                        to_address: todo_flip.wallet.to_string(),
-                        amount: vec![coin(pay.u128(), todo_flip.amount.denom.clone())],
-                    }
-                    .into(),
-                );
-            }
+mod leaderboard_execute {
+    use cosmwasm_std::{ensure, to_json_binary, Addr, Coin, CosmosMsg, Event, Timestamp, WasmMsg};
 
-            response = response.clone().add_event(
-                Event::new("flip")
-                    .add_attribute("flipper", todo_flip.wallet)
-                    .add_attribute("flip_id", todo_flip.id.to_string())
-                    .add_attribute("flip_amount", todo_flip.amount.to_string())
-                    .add_attribute("flip_pick", format!("{:?}", todo_flip.pick))
-                    .add_attribute("result", if flip_result { "won" } else { "lost" }),
-            );
+    use crate::chain::CustomMsg;
+    use crate::helpers::send_coin_msg;
+    use crate::state::{
+        get_prize_pool, EPOCH_SCORES, LEADERBOARDS, LEADERBOARD_EPOCH, PRIZE_POOLS,
+    };
+    use crate::types::{
+        Leaderboard, LeaderboardConfig, LeaderboardPrize, Standing, LEADERBOARD_SIZE,
+    };
+
+    use super::*;
+
+    /// Leaderboard of the epoch we are in,
+    /// the first flip after the latest epoch ended starts the next one
+    fn get_current_leaderboard(
+        storage: &mut dyn Storage,
+        env: &Env,
+        leaderboard_config: &LeaderboardConfig,
+    ) -> StdResult<Leaderboard> {
+        let latest = match LEADERBOARD_EPOCH.may_load(storage)? {
+            Some(epoch) => Some(LEADERBOARDS.load(storage, epoch)?),
+            None => None,
+        };
+        if let Some(leaderboard) = &latest {
+            if env.block.time < leaderboard.end {
+                return Ok(leaderboard.clone());
+            }
         }
 
-        FLIPS.save(deps.storage, &last_flips)?;
+        // Epochs start at a multiple of their length, unless the latest one ended later
+        let length = leaderboard_config.epoch_length.seconds();
+        let start = env.block.time.seconds() - env.block.time.seconds() % length;
+        let epoch = latest
+            .as_ref()
+            .map_or(0, |leaderboard| leaderboard.epoch + 1);
+        LEADERBOARD_EPOCH.save(storage, &epoch)?;
+
+        Ok(Leaderboard {
+            epoch,
+            start: latest.map_or(Timestamp::from_seconds(start), |leaderboard| {
+                leaderboard.end.max(Timestamp::from_seconds(start))
+            }),
+            end: Timestamp::from_seconds(start + length),
+            volume: vec![],
+            win_streak: vec![],
+            biggest_win: vec![],
+            finalized: false,
+        })
+    }
 
-        Ok(response
-            .add_attribute("flip_action", "do_flips")
-            .add_messages(msgs))
+    /// Put the wallet at its rank on the board, ties keep the wallet that got there first ahead
+    fn rank(board: &mut Vec<Standing>, wallet: &Addr, value: Uint128) {
+        board.retain(|standing| standing.wallet != *wallet);
+        let rank = board
+            .iter()
+            .position(|standing| standing.value < value)
+            .unwrap_or(board.len());
+        board.insert(
+            rank,
+            Standing {
+                wallet: wallet.clone(),
+                value,
+            },
+        );
+        board.truncate(LEADERBOARD_SIZE);
     }
 
-    fn get_random(env: &Env) -> u64 {
-        let tx_index = if let Some(tx) = &env.transaction {
-            tx.index
-        } else {
-            0
+    /// Add a resolved flip to the score of the wallet in the current epoch, and rank it
+    pub(crate) fn score_flip(
+        storage: &mut dyn Storage,
+        env: &Env,
+        config: &Config,
+        wallet: &Addr,
+        amount: &Coin,
+        won: bool,
+        payout: Uint128,
+    ) -> StdResult<()> {
+        let Some(leaderboard_config) = &config.leaderboard else {
+            return Ok(());
         };
+        if amount.denom != leaderboard_config.denom {
+            return Ok(());
+        }
 
-        let sha256 = Sha256Digest::digest(format!(
-            "{}{}{}",
-            tx_index,
-            env.block.height,
-            env.block.time.nanos(),
-        ));
+        let mut leaderboard = get_current_leaderboard(storage, env, leaderboard_config)?;
+        let mut score = EPOCH_SCORES
+            .may_load(storage, (leaderboard.epoch, wallet))?
+            .unwrap_or_default();
+
+        score.volume += amount.amount;
+        rank(&mut leaderboard.volume, wallet, score.volume);
+
+        if won {
+            score.streak += 1;
+            if score.streak > score.best_streak {
+                score.best_streak = score.streak;
+                rank(
+                    &mut leaderboard.win_streak,
+                    wallet,
+                    score.best_streak.into(),
+                );
+            }
+            if payout > score.biggest_win {
+                score.biggest_win = payout;
+                rank(&mut leaderboard.biggest_win, wallet, payout);
+            }
+        } else {
+            score.streak = 0;
+        }
 
-        sha256.as_bytes().iter().fold(0, |acc, x| acc + *x as u64)
+        EPOCH_SCORES.save(storage, (leaderboard.epoch, wallet), &score)?;
+        LEADERBOARDS.save(storage, leaderboard.epoch, &leaderboard)
     }
 
-    fn do_a_flip(todo_flip: &TodoFlip, rand: u64) -> bool {
-        let er = todo_flip
-            .wallet
-            .as_bytes()
-            .iter()
-            .fold(0, |acc, x| acc + *x as u64);
+    pub(crate) fn execute_finalize_epoch(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        config: &Config,
+        epoch: u64,
+    ) -> Result<Response, ContractError> {
+        let Some(leaderboard_config) = &config.leaderboard else {
+            return Err(ContractError::NoLeaderboard);
+        };
+        let mut leaderboard = LEADERBOARDS
+            .may_load(deps.storage, epoch)?
+            .ok_or(ContractError::EpochNotFound(epoch))?;
+        ensure!(
+            env.block.time >= leaderboard.end,
+            ContractError::EpochNotOver(epoch)
+        );
+        ensure!(!leaderboard.finalized, ContractError::EpochFinalized(epoch));
 
-        let flip_result = (rand + er) % 2 == 0;
+        let mut nft_pool = NFT_REWARDS.load(deps.storage)?;
+        let mut msgs: Vec<CosmosMsg<CustomMsg>> = vec![];
+        let mut response = Response::default();
 
-        // if picked heads and flip_result is true, he won
-        let won_heads = todo_flip.pick == PickTypes::Heads && flip_result;
-        // if picked tails and flip_result is false, he won
-        let won_tails = todo_flip.pick == PickTypes::Tails && !flip_result;
+        for (board, standings) in [
+            ("volume", &leaderboard.volume),
+            ("win_streak", &leaderboard.win_streak),
+            ("biggest_win", &leaderboard.biggest_win),
+        ] {
+            for (rank, (standing, prize)) in
+                standings.iter().zip(&leaderboard_config.prizes).enumerate()
+            {
+                let paid_prize = match prize {
+                    LeaderboardPrize::Coin(prize) => {
+                        let prize_pool = get_prize_pool(deps.storage, &prize.denom)?;
+                        if prize_pool < prize.amount {
+                            continue;
+                        }
+                        PRIZE_POOLS.save(
+                            deps.storage,
+                            prize.denom.clone(),
+                            &(prize_pool - prize.amount),
+                        )?;
+                        msgs.push(send_coin_msg(standing.wallet.clone(), prize.clone())?);
+                        prize.to_string()
+                    }
+                    LeaderboardPrize::Nft => {
+                        if nft_pool.is_empty() {
+                            continue;
+                        }
+                        let nft = nft_pool.remove(0);
+                        msgs.push(
+                            WasmMsg::Execute {
+                                contract_addr: nft.contract_addr.to_string(),
+                                msg: to_json_binary(&cw721::Cw721ExecuteMsg::TransferNft {
+                                    recipient: standing.wallet.to_string(),
+                                    token_id: nft.token_id.clone(),
+                                })?,
+                                funds: vec![],
+                            }
+                            .into(),
+                        );
+                        format!("{}/{}", nft.contract_addr, nft.token_id)
+                    }
+                };
 
-        // Return true if one of them is true (won) else return false (lost)
-        won_heads || won_tails
-    }
+                response = response.add_event(
+                    Event::new("leaderboard_prize")
+                        .add_attribute("epoch", epoch.to_string())
+                        .add_attribute("board", board)
+                        .add_attribute("rank", (rank + 1).to_string())
+                        .add_attribute("winner", standing.wallet.to_string())
+                        .add_attribute("prize", paid_prize),
+                );
+            }
+        }
+        NFT_REWARDS.save(deps.storage, &nft_pool)?;
 
-    fn is_streak_nft_winner(config: &Config, score: &FlipScore) -> bool {
-        score.streak.amount == config.streak_nft_winning_amount
+        leaderboard.finalized = true;
+        LEADERBOARDS.save(deps.storage, epoch, &leaderboard)?;
+
+        Ok(response.add_messages(msgs).add_event(
+            Event::new("finalize_epoch")
+                .add_attribute("epoch", epoch.to_string())
+                .add_attribute("caller", info.sender),
+        ))
     }
 }
 
-mod streak_execute {
-    use cosmwasm_std::{coins, BankMsg, Event};
+mod referral_execute {
+    use cosmwasm_std::{ensure, Addr, Coin, CosmosMsg, Event};
 
-    use crate::{
-        state::{ALLOWED_SEND_NFT, NFT_REWARDS, SCORES, STREAK_REWARDS},
-        types::NftReward,
-    };
+    use crate::chain::CustomMsg;
+    use crate::helpers::send_coin_msg;
+    use crate::state::{REFERRAL_REWARDS, REFERRAL_STATS, REFERRERS};
+    use crate::types::ReferralShare;
 
     use super::*;
 
-    pub(crate) fn receive_nft(
+    /// Bind the referrer to the wallet, unless the wallet has one already
+    pub(crate) fn bind_referrer(
         deps: DepsMut,
-        info: MessageInfo,
-        config: &Config,
-        sender: String,
-        token_id: String,
-    ) -> Result<Response, ContractError> {
-        // Verify the sender can send NFTs to the contract
-        let allowed_to_send_nfts = ALLOWED_SEND_NFT.load(deps.storage)?;
+        wallet: &Addr,
+        referrer: Option<String>,
+    ) -> Result<Option<Event>, ContractError> {
+        let Some(referrer) = referrer else {
+            return Ok(None);
+        };
+        let referrer = deps.api.addr_validate(&referrer)?;
+        ensure!(referrer != *wallet, ContractError::SelfReferral);
 
-        if !allowed_to_send_nfts.contains(&deps.api.addr_validate(&sender)?) {
-            return Err(ContractError::UnauthorizedToSendNft);
+        if REFERRERS.has(deps.storage, wallet) {
+            return Ok(None);
         }
+        REFERRERS.save(deps.storage, wallet, &referrer)?;
+        REFERRAL_STATS.update(deps.storage, &referrer, |stats| {
+            let mut stats = stats.unwrap_or_default();
+            stats.referrals += 1;
+            Ok::<_, ContractError>(stats)
+        })?;
+
+        Ok(Some(
+            Event::new("bind_referrer")
+                .add_attribute("wallet", wallet)
+                .add_attribute("referrer", referrer),
+        ))
+    }
 
-        // Verify our NFT rewards pool is not full
-        let mut nft_rewards = NFT_REWARDS.load(deps.storage)?;
+    fn add_coin(coins: &mut Vec<Coin>, to_add: &Coin) {
+        match coins.iter_mut().find(|coin| coin.denom == to_add.denom) {
+            Some(coin) => coin.amount += to_add.amount,
+            None => coins.push(to_add.clone()),
+        }
+    }
 
-        if nft_rewards.len() as u32 >= config.nft_pool_max {
-            return Err(ContractError::MaxNFTStreakRewardsReached);
+    /// Hold the share of the paid fee for the referrer of the wallet,
+    /// it is credited once the flip is resolved and given back if the flip is refunded
+    pub(crate) fn hold_referral_share(
+        storage: &mut dyn Storage,
+        config: &Config,
+        wallet: &Addr,
+        denom: &str,
+        paid_fee: Uint128,
+    ) -> Result<Option<ReferralShare>, ContractError> {
+        let Some(referrer) = REFERRERS.may_load(storage, wallet)? else {
+            return Ok(None);
+        };
+        let amount = config.fees.referral_share(paid_fee);
+        if amount.is_zero() {
+            return Ok(None);
         }
 
-        // info.sender is the NFT contract address, token_id is the NFT id
-        // Add the NFT to the pool of NFTs
-        nft_rewards.push(NftReward::new(info.sender.clone(), token_id.clone()));
-        NFT_REWARDS.save(deps.storage, &nft_rewards)?;
+        REFERRAL_REWARDS.update(storage, denom.to_string(), |rewards| {
+            Ok::<_, ContractError>(rewards.unwrap_or_default().checked_add(amount)?)
+        })?;
+        Ok(Some(ReferralShare { referrer, amount }))
+    }
 
-        Ok(Response::default()
-            .add_attribute("action", "add_nft")
-            .add_attribute("nft_contract", info.sender)
-            .add_attribute("token_id", token_id)
-            .add_attribute("nft_pool_size", nft_rewards.len().to_string()))
+    /// Credit the referrers with their held shares of the fees paid for a resolved flip
+    pub(crate) fn credit_referrers(
+        storage: &mut dyn Storage,
+        denom: &str,
+        shares: &[ReferralShare],
+    ) -> Result<(), ContractError> {
+        for share in shares {
+            let reward = coin(share.amount.u128(), denom);
+            let mut stats = REFERRAL_STATS
+                .may_load(storage, &share.referrer)?
+                .unwrap_or_default();
+            add_coin(&mut stats.earned, &reward);
+            add_coin(&mut stats.claimable, &reward);
+            REFERRAL_STATS.save(storage, &share.referrer, &stats)?;
+        }
+        Ok(())
     }
 
-    pub(crate) fn execute_claim(
+    /// Give back the held shares of the fees paid for a refunded flip, returns their sum
+    pub(crate) fn release_referral_shares(
+        storage: &mut dyn Storage,
+        denom: &str,
+        shares: &[ReferralShare],
+    ) -> Result<Uint128, ContractError> {
+        let amount: Uint128 = shares.iter().map(|share| share.amount).sum();
+        if amount.is_zero() {
+            return Ok(amount);
+        }
+
+        REFERRAL_REWARDS.update(storage, denom.to_string(), |rewards| {
+            Ok::<_, ContractError>(rewards.unwrap_or_default().checked_sub(amount)?)
+        })?;
+        Ok(amount)
+    }
+
+    pub(crate) fn execute_claim_referral_rewards(
         deps: DepsMut,
         info: MessageInfo,
     ) -> Result<Response, ContractError> {
-        let streak_rewards = STREAK_REWARDS.load(deps.storage)?;
-        let mut score = SCORES.load(deps.storage, &info.sender)?;
+        let mut stats = REFERRAL_STATS
+            .may_load(deps.storage, &info.sender)?
+            .unwrap_or_default();
+        ensure!(
+            !stats.claimable.is_empty(),
+            ContractError::NoReferralRewards
+        );
 
-        // make sure score is higher or equal to the lowest streak reward
-        if score.streak.amount < streak_rewards[0].streak {
-            return Err(ContractError::LowStreak(streak_rewards[0].streak));
+        let mut msgs: Vec<CosmosMsg<CustomMsg>> = vec![];
+        for reward in &stats.claimable {
+            REFERRAL_REWARDS.update(deps.storage, reward.denom.clone(), |rewards| {
+                Ok::<_, ContractError>(rewards.unwrap_or_default().checked_sub(reward.amount)?)
+            })?;
+            msgs.push(send_coin_msg(info.sender.clone(), reward.clone())?);
         }
-
-        // find the matching reward to claim
-        match streak_rewards
+        let claimed = stats
+            .claimable
             .iter()
-            .find(|r| r.streak == score.streak.amount)
-        {
-            Some(reward) => {
-                score.streak.reset();
-                SCORES.save(deps.storage, &info.sender, &score)?;
-
-                Ok(Response::default()
-                    .add_event(
-                        Event::new("streak-claim")
-                            .add_attribute("flipper", info.sender.to_string())
-                            .add_attribute("streak", reward.streak.to_string())
-                            .add_attribute("claim", format!("{}ustars", reward.reward)),
-                    )
-                    .add_message(BankMsg::Send {
-                        to_address: info.sender.to_string(),
-                        amount: coins(reward.reward.u128(), "ustars"),
-                    }))
-            }
-            None => Err(ContractError::NotEligibleForStreakReward(
-                score.streak.amount,
-            )),
-        }
+            .map(|reward| reward.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        stats.claimable = vec![];
+        REFERRAL_STATS.save(deps.storage, &info.sender, &stats)?;
+
+        Ok(Response::default().add_messages(msgs).add_event(
+            Event::new("claim_referral_rewards")
+                .add_attribute("referrer", info.sender)
+                .add_attribute("rewards", claimed),
+        ))
     }
 }
 
@@ -539,23 +2998,54 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::GetAllFeesAmount {} => query::get_all_fees(deps),
         QueryMsg::GetScore { address } => query::get_score(deps, address),
         QueryMsg::GetConfig {} => query::get_config(deps),
-        QueryMsg::ShouldDoFlips {} => query::should_do_flips(deps, env),
+        QueryMsg::GetKeeperStatus {} => query::get_keeper_status(deps, env),
         QueryMsg::DryDistribution { denom } => query::dry_distribution(deps, env, denom),
         QueryMsg::GetNftPool {} => query::get_nft_pool(deps),
+        QueryMsg::GetOperatorSeed { round } => query::get_operator_seed(deps, round),
+        QueryMsg::GetFlipProof { id } => query::get_flip_proof(deps, id),
+        QueryMsg::GetHouseChain {} => query::get_house_chain(deps),
+        QueryMsg::GetChallenges {
+            denom,
+            start_after,
+            limit,
+        } => query::get_challenges(deps, denom, start_after, limit),
+        QueryMsg::GetAutoBet { address } => query::get_auto_bet(deps, address),
+        QueryMsg::GetVouchers { address } => query::get_vouchers(deps, address),
+        QueryMsg::GetJackpot { denom } => query::get_jackpot(deps, denom),
+        QueryMsg::GetLeaderboard { epoch } => query::get_leaderboard(deps, epoch),
+        QueryMsg::GetEpochScore { address, epoch } => query::get_epoch_score(deps, address, epoch),
+        QueryMsg::GetPrizePool { denom } => query::get_prize_pool(deps, denom),
+        QueryMsg::GetReferralStats { address } => query::get_referral_stats(deps, address),
     }
 }
 
 mod query {
     use cosmwasm_std::{
-        to_json_binary, Binary, Coin, Decimal, Deps, Env, StdError, StdResult, Uint128,
+        to_json_binary, Binary, Coin, Decimal, Deps, Env, Order, StdError, StdResult, Uint128,
     };
 
+    use std::collections::BTreeSet;
+
     use crate::{
-        msg::DryDistributionResponse,
-        state::{CONFIG, FEES, FLIPS, NFT_REWARDS, SCORES, TODO_FLIPS},
-        sudo::{calculate_fees_to_pay, get_holders_list, verify_contract_balance},
-        types::{FeesToPay, NftReward},
+        error::ContractError,
+        msg::{DryDistributionResponse, KeeperStatusResponse, ReferralStatsResponse},
+        randomness::{get_flip_randomness, FlipRandomness},
+        state::{
+            challenges, flips, AUTO_BETS, CONFIG, EPOCH_SCORES, FEES, FLIP_PROOFS, HOUSE_CHAIN,
+            JACKPOTS, LEADERBOARDS, LEADERBOARD_EPOCH, NFT_REWARDS, OPERATOR_ROUND, OPERATOR_SEEDS,
+            PRIZE_POOLS, REFERRAL_STATS, REFERRERS, SCORES, VOUCHERS,
+        },
+        sudo::{
+            calculate_fees_to_pay, calculate_prize_pool_fees, get_holders_list,
+            verify_contract_balance,
+        },
+        types::{Challenge, FeesToPay, Flip, NftReward, RandomnessSource, TodoFlip, Voucher},
     };
+    use cw_storage_plus::Bound;
+
+    use super::{DEFAULT_CHALLENGES_LIMIT, MAX_CHALLENGES_LIMIT};
+
+    use super::flip_execute::{get_keeper_bounty, get_started_todo_flips};
 
     pub fn get_fees(deps: Deps, denom: String) -> StdResult<Binary> {
         to_json_binary(&FEES.load(deps.storage, denom)?)
@@ -583,19 +3073,58 @@ mod query {
         to_json_binary(&SCORES.load(deps.storage, &address)?)
     }
 
-    pub fn should_do_flips(deps: Deps, env: Env) -> StdResult<Binary> {
-        let todo_flips = TODO_FLIPS.load(deps.storage)?;
+    pub fn get_keeper_status(deps: Deps, env: Env) -> StdResult<Binary> {
+        let config = CONFIG.load(deps.storage)?;
+        let todo_flips =
+            get_started_todo_flips(deps.storage, &env).collect::<StdResult<Vec<_>>>()?;
+
+        // In hash-chain mode, DoFlips reveals the next house seed first,
+        // the flips tied to it are ready too
+        let next_house_seed_index = match config.randomness {
+            RandomnessSource::HashChain => HOUSE_CHAIN
+                .may_load(deps.storage)?
+                .map(|house_chain| house_chain.revealed + 1),
+            _ => None,
+        };
 
-        let res = todo_flips
-            .iter()
-            .any(|todo_flip| env.block.height > todo_flip.block);
-        to_json_binary(&res)
+        let mut ready_flips: u32 = 0;
+        let mut denoms: BTreeSet<String> = BTreeSet::new();
+        let mut resolved_flips: Vec<&TodoFlip> = vec![];
+        for todo_flip in &todo_flips {
+            match get_flip_randomness(deps.storage, &env, &config, todo_flip)? {
+                // Timed out nois flips are refunded, they don't earn a reward
+                Some(FlipRandomness::NoisTimeout) => {}
+                Some(_) => resolved_flips.push(todo_flip),
+                None if next_house_seed_index.is_some()
+                    && todo_flip.house_seed_index == next_house_seed_index =>
+                {
+                    resolved_flips.push(todo_flip)
+                }
+                None => continue,
+            }
+            ready_flips += 1;
+            denoms.insert(todo_flip.amount.denom.clone());
+        }
+
+        to_json_binary(&KeeperStatusResponse {
+            ready_flips,
+            denoms: denoms.into_iter().collect(),
+            bounty: get_keeper_bounty(deps.storage, &config, resolved_flips)?,
+        })
     }
 
     pub fn get_last_5(deps: Deps) -> StdResult<Binary> {
-        let flips = FLIPS.load(deps.storage)?;
-
-        to_json_binary(&flips)
+        let mut last_flips = flips()
+            .idx
+            .block
+            .range(deps.storage, None, None, Order::Descending)
+            .take(5)
+            .map(|item| item.map(|(_, flip)| flip))
+            .collect::<StdResult<Vec<Flip>>>()?;
+        // Oldest flip first
+        last_flips.reverse();
+
+        to_json_binary(&last_flips)
     }
 
     pub fn dry_distribution(deps: Deps, env: Env, denom: String) -> StdResult<Binary> {
@@ -604,7 +3133,14 @@ mod query {
         let bank_limit = config
             .denom_limits
             .get(&denom)
-            .expect("denom limit should exist in the map")
+            .ok_or_else(|| {
+                StdError::generic_err(
+                    ContractError::DenomNotFound {
+                        denom: denom.clone(),
+                    }
+                    .to_string(),
+                )
+            })?
             .bank;
 
         let (
@@ -617,8 +3153,17 @@ mod query {
         ) = calculate_fees_to_pay(&config, total_fees)
             .map_err(|x| StdError::generic_err(x.to_string()))?;
 
-        let reserve_fees_to_send =
-            verify_contract_balance(deps, env, denom, total_fees, reserve_fees, bank_limit)
+        let reserve_fees_to_send = verify_contract_balance(
+            deps,
+            env,
+            denom.clone(),
+            total_fees,
+            reserve_fees,
+            bank_limit,
+        )
+        .map_err(|x| StdError::generic_err(x.to_string()))?;
+        let prize_pool_fees =
+            calculate_prize_pool_fees(deps, &config, &denom, reserve_fees_to_send)
                 .map_err(|x| StdError::generic_err(x.to_string()))?;
 
         let mut paid_to_holders = Uint128::zero();
@@ -649,7 +3194,8 @@ mod query {
         to_json_binary(&DryDistributionResponse {
             total_fees,
             team_total_fee: team_fees_to_send,
-            reserve_total_fee: reserve_fees_to_send,
+            reserve_total_fee: reserve_fees_to_send - prize_pool_fees,
+            prize_pool_total_fee: prize_pool_fees,
             holders_total_fee: holders_fees_to_send,
             holders_total_shares: total_shares,
             fees_per_token,
@@ -663,6 +3209,109 @@ mod query {
 
         to_json_binary(&nft_pool)
     }
+
+    pub fn get_operator_seed(deps: Deps, round: Option<u64>) -> StdResult<Binary> {
+        let round = match round {
+            Some(round) => round,
+            None => OPERATOR_ROUND.load(deps.storage)?,
+        };
+
+        to_json_binary(&OPERATOR_SEEDS.load(deps.storage, round)?)
+    }
+
+    pub fn get_flip_proof(deps: Deps, id: u64) -> StdResult<Binary> {
+        to_json_binary(&FLIP_PROOFS.load(deps.storage, id)?)
+    }
+
+    pub fn get_house_chain(deps: Deps) -> StdResult<Binary> {
+        to_json_binary(&HOUSE_CHAIN.load(deps.storage)?)
+    }
+
+    pub fn get_challenges(
+        deps: Deps,
+        denom: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Binary> {
+        let limit = limit
+            .unwrap_or(DEFAULT_CHALLENGES_LIMIT)
+            .min(MAX_CHALLENGES_LIMIT) as usize;
+
+        let challenges = challenges()
+            .idx
+            .denom
+            .prefix(denom)
+            .range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| item.map(|(_, challenge)| challenge))
+            .collect::<StdResult<Vec<Challenge>>>()?;
+
+        to_json_binary(&challenges)
+    }
+
+    pub fn get_auto_bet(deps: Deps, address: String) -> StdResult<Binary> {
+        let address = deps.api.addr_validate(&address)?;
+        to_json_binary(&AUTO_BETS.may_load(deps.storage, &address)?)
+    }
+
+    pub fn get_vouchers(deps: Deps, address: String) -> StdResult<Binary> {
+        let address = deps.api.addr_validate(&address)?;
+        let vouchers = VOUCHERS
+            .prefix(&address)
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, voucher)| voucher))
+            .collect::<StdResult<Vec<Voucher>>>()?;
+
+        to_json_binary(&vouchers)
+    }
+
+    pub fn get_jackpot(deps: Deps, denom: String) -> StdResult<Binary> {
+        to_json_binary(&JACKPOTS.may_load(deps.storage, denom)?.unwrap_or_default())
+    }
+
+    pub fn get_leaderboard(deps: Deps, epoch: Option<u64>) -> StdResult<Binary> {
+        let leaderboard = match epoch.or(LEADERBOARD_EPOCH.may_load(deps.storage)?) {
+            Some(epoch) => LEADERBOARDS.may_load(deps.storage, epoch)?,
+            None => None,
+        };
+        to_json_binary(&leaderboard)
+    }
+
+    pub fn get_epoch_score(deps: Deps, address: String, epoch: Option<u64>) -> StdResult<Binary> {
+        let address = deps.api.addr_validate(&address)?;
+        let score = match epoch.or(LEADERBOARD_EPOCH.may_load(deps.storage)?) {
+            Some(epoch) => EPOCH_SCORES.may_load(deps.storage, (epoch, &address))?,
+            None => None,
+        };
+        to_json_binary(&score.unwrap_or_default())
+    }
+
+    pub fn get_prize_pool(deps: Deps, denom: String) -> StdResult<Binary> {
+        to_json_binary(
+            &PRIZE_POOLS
+                .may_load(deps.storage, denom)?
+                .unwrap_or_default(),
+        )
+    }
+
+    pub fn get_referral_stats(deps: Deps, address: String) -> StdResult<Binary> {
+        let address = deps.api.addr_validate(&address)?;
+        let stats = REFERRAL_STATS
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default();
+
+        to_json_binary(&ReferralStatsResponse {
+            referrer: REFERRERS.may_load(deps.storage, &address)?,
+            referrals: stats.referrals,
+            earned: stats.earned,
+            claimable: stats.claimable,
+        })
+    }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -671,6 +3320,7 @@ pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, Co
 
     match msg {
         MigrateMsg::Basic {} => {}
+        #[cfg(feature = "stargaze")]
         MigrateMsg::FromV07 {
             nft_pool_max,
             streak_nft_winning_amount,
@@ -702,6 +3352,7 @@ pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, Co
                     min: old_config.min_bet_limit,
                     max: old_config.max_bet_limit,
                     bank: old_config.bank_limit,
+                    max_payout: None,
                 },
             );
 
@@ -721,12 +3372,22 @@ pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, Co
                         holders_bps: old_config.fees.holders_bps,
                         reserve_bps: old_config.fees.reserve_bps,
                         flip_bps: old_config.fees.flip_bps,
+                        jackpot_bps: 0,
+                        referral_bps: 0,
                     },
                     sg721_addr: old_config.sg721_addr,
                     is_paused: old_config.is_paused,
                     // New fields
                     nft_pool_max,
                     streak_nft_winning_amount,
+                    randomness: RandomnessSource::BlockHash,
+                    flip_expiry: DEFAULT_FLIP_EXPIRY,
+                    cancel_fee_policy: CancelFeePolicy::default(),
+                    max_pending_flips: DEFAULT_MAX_PENDING_FLIPS,
+                    keeper_reward: KeeperReward::default(),
+                    challenge_expiry: DEFAULT_CHALLENGE_EXPIRY,
+                    jackpot_odds: 0,
+                    leaderboard: None,
                 },
             )?;
 
@@ -743,7 +3404,60 @@ pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, Co
             ccf_v07::state::FEES.remove(deps.storage);
             FEES.save(deps.storage, old_denom, &old_fees)?;
         }
+        MigrateMsg::FromV08 {} => migrate_v08_flips(deps.storage)?,
     };
 
     Ok(Response::default())
 }
+
+/// Move the waiting and last flips of v0.8 from their vectors to the indexed maps
+fn migrate_v08_flips(storage: &mut dyn Storage) -> StdResult<()> {
+    for old_todo_flip in v08::TODO_FLIPS.may_load(storage)?.unwrap_or_default() {
+        let todo_flip = TodoFlip {
+            id: old_todo_flip.id,
+            wallet: old_todo_flip.wallet,
+            amount: old_todo_flip.amount,
+            pick: old_todo_flip.pick,
+            block: old_todo_flip.block,
+            timestamp: old_todo_flip.timestamp,
+            commit: None,
+            drand_round: None,
+            nois_job_id: None,
+            house_seed_index: None,
+            // v0.8 didn't keep the fee of the flip, a refund only gives the bet back
+            fee: Uint128::zero(),
+            jackpot_share: Uint128::zero(),
+            referral_shares: vec![],
+            client_seed: None,
+            opponent: None,
+            auto_bet: false,
+            voucher: false,
+        };
+        todo_flips().save(storage, todo_flip.id, &todo_flip)?;
+    }
+    v08::TODO_FLIPS.remove(storage);
+
+    // Old flips didn't keep their id and block, they get new ids, oldest first, and an unknown block (0)
+    for old_flip in v08::FLIPS.may_load(storage)?.unwrap_or_default() {
+        let id = get_next_flip_id(storage);
+        FLIP_ID.save(storage, &id)?;
+
+        flips().save(
+            storage,
+            id,
+            &Flip {
+                id,
+                wallet: old_flip.wallet,
+                amount: old_flip.amount,
+                result: old_flip.result,
+                streak: old_flip.streak,
+                timestamp: old_flip.timestamp,
+                block: 0,
+                client_seed: None,
+            },
+        )?;
+    }
+    v08::FLIPS.remove(storage);
+
+    Ok(())
+}
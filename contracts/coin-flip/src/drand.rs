@@ -0,0 +1,58 @@
+use bls12_381::{
+    hash_to_curve::{ExpandMsgXmd, HashToCurve},
+    multi_miller_loop, G1Affine, G1Projective, G2Affine, G2Prepared, Gt,
+};
+use cosmwasm_std::Timestamp;
+use sha2::{Digest, Sha256};
+use sha256::Sha256Digest;
+
+/// Domain separation tag of drand unchained G1 signatures (quicknet)
+const DST: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// Verify the beacon signature of the round against the chain public key (G2, 96 bytes)
+pub fn verify_beacon(pubkey: &[u8], round: u64, signature: &[u8]) -> bool {
+    let (Ok(pubkey), Ok(signature)) = (
+        <[u8; 96]>::try_from(pubkey),
+        <[u8; 48]>::try_from(signature),
+    ) else {
+        return false;
+    };
+    let (Some(pubkey), Some(signature)) = (
+        Option::<G2Affine>::from(G2Affine::from_compressed(&pubkey)),
+        Option::<G1Affine>::from(G1Affine::from_compressed(&signature)),
+    ) else {
+        return false;
+    };
+
+    // Unchained beacons sign sha256(round)
+    let msg = Sha256::digest(&round.to_be_bytes());
+    let msg_on_curve: G1Affine =
+        <G1Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(msg, DST).into();
+
+    // e(signature, g2) == e(msg, pubkey)
+    multi_miller_loop(&[
+        (&signature, &G2Prepared::from(-G2Affine::generator())),
+        (&msg_on_curve, &G2Prepared::from(pubkey)),
+    ])
+    .final_exponentiation()
+        == Gt::identity()
+}
+
+/// The randomness of a beacon is the sha256 of its signature
+pub fn derive_randomness(signature: &[u8]) -> String {
+    Sha256Digest::digest(signature)
+}
+
+/// Time (in seconds) the round is published at
+pub fn round_time(genesis_time: u64, period: u64, round: u64) -> u64 {
+    genesis_time + round.saturating_sub(1) * period
+}
+
+/// First round that is published after the given time
+pub fn next_round(genesis_time: u64, period: u64, time: Timestamp) -> u64 {
+    if time.seconds() < genesis_time {
+        return 1;
+    }
+
+    (time.seconds() - genesis_time) / period + 2
+}
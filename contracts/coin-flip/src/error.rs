@@ -47,8 +47,8 @@ pub enum ContractError {
     #[error("We only support 1 denom at a time.")]
     WrongFundsAmount,
 
-    #[error("You already started a flip, please wait for it to finish.")]
-    AlreadyStartedFlip,
+    #[error("You already have {0} flips waiting, please wait for them to finish.")]
+    TooManyPendingFlips(u32),
 
     #[error("Block limit reached, please try again in few seconds")]
     BlockLimitReached,
@@ -65,6 +65,12 @@ pub enum ContractError {
     #[error("You cannot bet under our limit = {min_limit}")]
     UnderTheLimitBet { min_limit: String },
 
+    #[error("You cannot win above our payout limit = {max_limit}")]
+    OverThePayoutLimit { max_limit: String },
+
+    #[error("Win chance must be between {min}% and {max}%")]
+    InvalidWinChance { min: u8, max: u8 },
+
     #[error("Operation is paused at this moment! Please try again later.")]
     Paused,
 
@@ -74,6 +80,39 @@ pub enum ContractError {
     #[error("There are no flips to do this block")]
     NoFlipsToDoThisBlock,
 
+    #[error("Client seed can't be longer than {max_len} characters")]
+    ClientSeedTooLong { max_len: usize },
+
+    #[error("You don't have flips to refund")]
+    NoFlipsToRefund,
+
+    #[error("Flip can only be refunded from block {0}")]
+    FlipNotExpired(u64),
+
+    #[error("Flip randomness can be known before it's resolved, it can't be refunded")]
+    FlipNotRefundable,
+
+    #[error("Flip expiry must be at least 1 block")]
+    InvalidFlipExpiry,
+
+    #[error("You don't have flips to cancel")]
+    NoFlipsToCancel,
+
+    #[error("Flips can only be cancelled in the block they were started in")]
+    CancelWindowPassed,
+
+    #[error("At least 1 pick is required to flip")]
+    NoPicks,
+
+    #[error("Max pending flips must be at least 1")]
+    InvalidMaxPendingFlips,
+
+    #[error("Keeper reward can't be more than 10000 bps of the flip fee")]
+    InvalidKeeperReward,
+
+    #[error("DoFlips limit must be at least 1")]
+    InvalidDoFlipsLimit,
+
     // Streak errors
     #[error("This address is not allowed to send NFTs to the contract")]
     UnauthorizedToSendNft,
@@ -119,4 +158,154 @@ pub enum ContractError {
 
     #[error("Denom still have fees that are not distributed, denom : {0}")]
     DenomStillHaveFees(String),
+
+    // Commit-reveal errors
+    #[error("A commitment of your secret is required to flip")]
+    MissingCommitment,
+
+    #[error("Commitment is only used in commit-reveal mode")]
+    UnexpectedCommitment,
+
+    #[error("A commitment is required for every pick")]
+    WrongCommitmentsAmount,
+
+    #[error("Commitment must be a hex encoded sha256 hash")]
+    InvalidCommitment,
+
+    #[error("Operator seed is not committed, please try again later")]
+    NoOperatorCommitment,
+
+    #[error("Current operator seed is not revealed yet")]
+    OperatorSeedNotRevealed,
+
+    #[error("Operator seed is already revealed")]
+    OperatorSeedAlreadyRevealed,
+
+    #[error("Revealed value doesn't match the commitment")]
+    InvalidReveal,
+
+    #[error("Flip not found: {0}")]
+    FlipNotFound(u64),
+
+    #[error("Flip secret is already revealed")]
+    AlreadyRevealed,
+
+    #[error("Can't do this while there are flips waiting to be done")]
+    FlipsPending,
+
+    // Drand errors
+    #[error("A drand round and signature are required to do flips")]
+    MissingBeacon,
+
+    #[error("Drand beacon signature is invalid")]
+    InvalidBeacon,
+
+    #[error("Drand round {0} is not published yet")]
+    BeaconFromFuture(u64),
+
+    // Nois errors
+    #[error("Nois job not found: {0}")]
+    NoisJobNotFound(String),
+
+    // Hash-chain errors
+    #[error("House seed chain is not committed")]
+    NoHouseChain,
+
+    #[error("House seed chain is used up, waiting for a new one")]
+    HouseChainUsedUp,
+
+    #[error("House seed chain must have at least 1 seed")]
+    InvalidHouseChainLength,
+
+    #[error("A house seed is required to do flips")]
+    MissingHouseSeed,
+
+    #[error("House seed doesn't hash to the current chain head")]
+    InvalidHouseSeed,
+
+    // Challenge errors
+    #[error("Challenges are not available in commit-reveal mode")]
+    ChallengesNotSupported,
+
+    #[error("Challenges can only pick heads or tails")]
+    InvalidChallengePick,
+
+    #[error("Challenge expiry must be at least 1 block")]
+    InvalidChallengeExpiry,
+
+    #[error("Challenge not found: {0}")]
+    ChallengeNotFound(u64),
+
+    #[error("Challenge {0} expired")]
+    ChallengeExpired(u64),
+
+    #[error("You can't accept your own challenge")]
+    OwnChallenge,
+
+    #[error("Only the creator can cancel a challenge before it expires")]
+    NotChallengeCreator,
+
+    // Auto-bet errors
+    #[error("Auto-bets are not available in commit-reveal mode")]
+    AutoBetsNotSupported,
+
+    #[error("You already have an auto-bet running")]
+    AutoBetExists,
+
+    #[error("You don't have an auto-bet running")]
+    NoAutoBet,
+
+    #[error("Auto-bet max rounds must be at least 1")]
+    InvalidAutoBetRounds,
+
+    #[error("Auto-bet stop-loss and take-profit must be above 0")]
+    InvalidAutoBetLimits,
+
+    #[error("Roll-under picks can only use the fixed pick rule")]
+    InvalidAutoBetPickRule,
+
+    #[error("Auto-bet budget must cover the base bet and its fee")]
+    AutoBetBudgetTooLow,
+
+    #[error("Auto-bet can't queue its first bet: {0}")]
+    CantQueueAutoBet(String),
+
+    // Voucher errors
+    #[error("You don't have a voucher for this amount")]
+    NoVoucher,
+
+    #[error("A voucher pays for a single flip")]
+    VoucherSinglePick,
+
+    #[error("Voucher must grant at least 1 flip")]
+    InvalidVoucherFlips,
+
+    #[error("Voucher expiry must be at least 1 block")]
+    InvalidVoucherExpiry,
+
+    #[error("Voucher not found: {0}")]
+    VoucherNotFound(u64),
+
+    // Leaderboard errors
+    #[error("Leaderboard needs a contract denom and at most 10 non-zero contract denom prizes")]
+    InvalidLeaderboard,
+
+    #[error("Leaderboard is not enabled")]
+    NoLeaderboard,
+
+    #[error("Leaderboard epoch not found: {0}")]
+    EpochNotFound(u64),
+
+    #[error("Epoch {0} is not over yet")]
+    EpochNotOver(u64),
+
+    #[error("Epoch {0} is already finalized")]
+    EpochFinalized(u64),
+
+    // Referral errors
+    #[error("You can't refer yourself")]
+    SelfReferral,
+
+    #[error("You don't have referral rewards to claim")]
+    NoReferralRewards,
 }
@@ -1,6 +1,12 @@
-use cosmwasm_std::{ensure_eq, Decimal, MessageInfo, Uint128};
+use std::collections::HashMap;
 
-use crate::{error::ContractError, types::Config};
+use cosmwasm_std::{
+    ensure_eq, from_json, to_json_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, MessageInfo,
+    QuerierWrapper, StdResult, SubMsg, Uint128, WasmMsg,
+};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
+
+use crate::{chain::CustomMsg, error::ContractError, types::Config};
 
 pub fn ensure_admin(config: &Config, info: &MessageInfo) -> Result<(), ContractError> {
     ensure_eq!(config.admin, info.sender, ContractError::Unauthorized);
@@ -24,27 +30,122 @@ pub fn calc_flip_fee(amount: Decimal, fee: Decimal) -> Result<Uint128, ContractE
     Ok(f)
 }
 
-/// Function to ensure flipper paid the right amount with fees
+/// Function to ensure flipper paid the right amount with fees for all the flips,
+/// returns the fee of each flip
 pub fn ensure_correct_funds(
     funds: Uint128,
-    amount: Uint128,
+    amounts: &[Uint128],
     fee_bps: u64,
-) -> Result<Uint128, ContractError> {
+) -> Result<Vec<Uint128>, ContractError> {
     let fee = bps_to_decimal(fee_bps);
-    let fee_to_pay = calc_flip_fee(Decimal::from_atomics(amount, 0)?, fee)?;
-    let total_amount = amount.checked_add(fee_to_pay)?;
+    let mut fees_to_pay = Vec::with_capacity(amounts.len());
+    let mut total_amount = Uint128::zero();
+
+    for amount in amounts {
+        let fee_to_pay = calc_flip_fee(Decimal::from_atomics(*amount, 0)?, fee)?;
+        total_amount = total_amount.checked_add(*amount)?.checked_add(fee_to_pay)?;
+        fees_to_pay.push(fee_to_pay);
+    }
+
     if funds != total_amount {
         return Err(ContractError::WrongPaidAmount {});
     }
-    Ok(fee_to_pay)
+    Ok(fees_to_pay)
 }
 
 pub fn bps_to_decimal(bps: u64) -> Decimal {
     Decimal::percent(bps) / Uint128::from(100u128)
 }
 
+/// CW20 tokens are handled as the denom "cw20:<token address>"
+pub const CW20_DENOM_PREFIX: &str = "cw20:";
+
+pub fn cw20_denom(token: &Addr) -> String {
+    format!("{CW20_DENOM_PREFIX}{token}")
+}
+
+/// Msg sending the coin to the address, a transfer on the token contract for CW20 denoms
+pub fn send_coin_msg(to_address: impl Into<String>, coin: Coin) -> StdResult<CosmosMsg<CustomMsg>> {
+    match coin.denom.strip_prefix(CW20_DENOM_PREFIX) {
+        Some(token) => Ok(WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: to_address.into(),
+                amount: coin.amount,
+            })?,
+            funds: vec![],
+        }
+        .into()),
+        None => Ok(BankMsg::Send {
+            to_address: to_address.into(),
+            amount: vec![coin],
+        }
+        .into()),
+    }
+}
+
+/// Balance of the address in the denom, queried from the token contract for CW20 denoms
+pub fn query_denom_balance(
+    querier: &QuerierWrapper,
+    address: &Addr,
+    denom: &str,
+) -> StdResult<Uint128> {
+    match denom.strip_prefix(CW20_DENOM_PREFIX) {
+        Some(token) => {
+            let res: BalanceResponse = querier.query_wasm_smart(
+                token,
+                &Cw20QueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )?;
+            Ok(res.balance)
+        }
+        None => Ok(querier.query_balance(address, denom)?.amount),
+    }
+}
+
+/// Funds the messages send out of the contract by denom, CW20 transfers included.
+/// The contract balance only drops once they are executed
+pub fn get_sent_funds(msgs: &[SubMsg<CustomMsg>]) -> StdResult<HashMap<String, Uint128>> {
+    let mut sent_funds: HashMap<String, Uint128> = HashMap::new();
+    for msg in msgs {
+        match &msg.msg {
+            CosmosMsg::Bank(BankMsg::Send { amount, .. }) => {
+                for coin in amount {
+                    *sent_funds.entry(coin.denom.clone()).or_default() += coin.amount;
+                }
+            }
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr,
+                msg,
+                funds,
+            }) => {
+                for coin in funds {
+                    *sent_funds.entry(coin.denom.clone()).or_default() += coin.amount;
+                }
+                if let Ok(Cw20ExecuteMsg::Transfer { amount, .. }) = from_json(msg) {
+                    *sent_funds
+                        .entry(format!("{CW20_DENOM_PREFIX}{contract_addr}"))
+                        .or_default() += amount;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(sent_funds)
+}
+
 #[test]
 fn test() {
-    let res = ensure_correct_funds(Uint128::new(103_500), Uint128::new(100_000), 350).unwrap();
-    assert_eq!(res, Uint128::new(3_500));
+    let res = ensure_correct_funds(Uint128::new(103_500), &[Uint128::new(100_000)], 350).unwrap();
+    assert_eq!(res, vec![Uint128::new(3_500)]);
+
+    let res = ensure_correct_funds(
+        Uint128::new(310_500),
+        &[Uint128::new(100_000), Uint128::new(200_000)],
+        350,
+    )
+    .unwrap();
+    assert_eq!(res, vec![Uint128::new(3_500), Uint128::new(7_000)]);
 }
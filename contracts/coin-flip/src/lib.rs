@@ -1,7 +1,10 @@
+pub mod chain;
 pub mod contract;
+pub mod drand;
 pub mod error;
 pub mod helpers;
 pub mod msg;
+pub mod randomness;
 pub mod state;
 pub mod sudo;
 pub mod types;
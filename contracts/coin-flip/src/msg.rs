@@ -1,10 +1,12 @@
 use std::collections::HashSet;
 
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Coin, Decimal, Uint128};
+use cosmwasm_std::{Addr, Coin, Decimal, HexBinary, Timestamp, Uint128};
 
 use crate::types::{
-    Config, DenomLimit, Fees, Flip, FlipScore, NftReward, PickTypes, StreakReward, Wallets,
+    AutoBet, AutoBetStrategy, CancelFeePolicy, Challenge, Config, DenomLimit, EpochScore, Fees,
+    Flip, FlipProof, FlipScore, HouseChain, KeeperReward, Leaderboard, LeaderboardConfig,
+    NftReward, OperatorSeed, PickTypes, RandomnessSource, StreakReward, Voucher, Wallets,
 };
 
 #[cw_serde]
@@ -16,6 +18,22 @@ pub struct InstantiateMsg {
     pub denom_limits: Vec<(String, Uint128, Uint128, Uint128)>,
     pub flips_per_block_limit: Option<u64>,
     pub sg721_addr: Option<String>,
+    /// Defaults to block hash randomness
+    pub randomness: Option<RandomnessSource>,
+    /// Amount of blocks after which a waiting flip can be refunded, defaults to 600
+    pub flip_expiry: Option<u64>,
+    /// Defaults to a full refund
+    pub cancel_fee_policy: Option<CancelFeePolicy>,
+    /// Amount of flips a wallet can have waiting at the same time, defaults to 1
+    pub max_pending_flips: Option<u32>,
+    /// Defaults to no keeper reward
+    pub keeper_reward: Option<KeeperReward>,
+    /// Amount of blocks a PvP challenge can be accepted for, defaults to 14400
+    pub challenge_expiry: Option<u64>,
+    /// One chance in jackpot_odds for a flip to win the jackpot, defaults to 0, no draws
+    pub jackpot_odds: Option<u64>,
+    /// Defaults to no leaderboard
+    pub leaderboard: Option<LeaderboardConfig>,
 
     // streak
     pub nft_pool_max: u32,
@@ -28,10 +46,24 @@ pub struct InstantiateMsg {
 pub enum ExecuteMsg {
     /// Receive NFTs to add to our NFTs pool of streak mini game
     ReceiveNft(cw721::Cw721ReceiveMsg),
+    /// Receive CW20 tokens to flip with, the msg is a ReceiveMsg
+    Receive(cw20::Cw20ReceiveMsg),
     /// Streak mini game msgs
     Streak(StreakExecuteMsg),
     /// Flip msgs
     Flip(FlipExecuteMsg),
+    /// PvP challenge msgs
+    Challenge(ChallengeExecuteMsg),
+    /// Auto-bet msgs
+    AutoBet(AutoBetExecuteMsg),
+    /// Voucher msgs, only call-able by the admin or a voucher issuer
+    Voucher(VoucherExecuteMsg),
+    /// Leaderboard msgs
+    Leaderboard(LeaderboardExecuteMsg),
+    /// Referral msgs
+    Referral(ReferralExecuteMsg),
+    /// Callback of the nois proxy with the randomness we requested
+    NoisReceive { callback: NoisCallback },
     /// Only call-able by admin (mutlisig)
     Sudo(SudoMsg),
 }
@@ -53,16 +85,53 @@ pub enum QueryMsg {
     /// Get score of wallet
     #[returns(FlipScore)]
     GetScore { address: String },
-    /// let us know if we should execute the do flips msg or not
+    /// let us know if we should execute the do flips msg or not, and what we earn for it
     /// this is to prevent sending unnecessary txs
-    #[returns(bool)]
-    ShouldDoFlips {},
+    #[returns(KeeperStatusResponse)]
+    GetKeeperStatus {},
     /// Do dry ditribution to see results
     #[returns(DryDistributionResponse)]
     DryDistribution { denom: String },
     /// Get the NFT pool
     #[returns(Vec<NftReward>)]
     GetNftPool {},
+    /// Get the operator seed of the commit-reveal mode, defaults to the current round
+    #[returns(OperatorSeed)]
+    GetOperatorSeed { round: Option<u64> },
+    /// Get everything needed to recompute the result of a resolved flip
+    #[returns(FlipProof)]
+    GetFlipProof { id: u64 },
+    /// Get the current head of the house seed chain and how many reveals remain
+    #[returns(HouseChain)]
+    GetHouseChain {},
+    /// Get the open PvP challenges of a denom, expired ones included until they are cancelled
+    #[returns(Vec<Challenge>)]
+    GetChallenges {
+        denom: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Get the running auto-bet of a wallet
+    #[returns(Option<AutoBet>)]
+    GetAutoBet { address: String },
+    /// Get the vouchers of a wallet, expired ones included until the wallet uses a voucher
+    #[returns(Vec<Voucher>)]
+    GetVouchers { address: String },
+    /// Get the jackpot of a denom
+    #[returns(Uint128)]
+    GetJackpot { denom: String },
+    /// Get the standings of an epoch, defaults to the latest one
+    #[returns(Option<Leaderboard>)]
+    GetLeaderboard { epoch: Option<u64> },
+    /// Get what a wallet did in an epoch, defaults to the latest one
+    #[returns(EpochScore)]
+    GetEpochScore { address: String, epoch: Option<u64> },
+    /// Get the coins set aside for the leaderboard prizes of a denom
+    #[returns(Uint128)]
+    GetPrizePool { denom: String },
+    /// Get the referrer of a wallet and what it got as a referrer
+    #[returns(ReferralStatsResponse)]
+    GetReferralStats { address: String },
 }
 
 #[cw_serde]
@@ -80,12 +149,134 @@ pub enum StreakExecuteMsg {
     Claim {},
 }
 
+#[cw_serde]
+pub enum ChallengeExecuteMsg {
+    /// Open a challenge with a stake and pick, paid with the stake and its flip fee.
+    /// There is no max stake, the bank is never at risk
+    Open { pick: PickTypes, amount: Uint128 },
+    /// Accept a challenge with the same stake and its flip fee, the opponent gets the other side.
+    /// The winner takes both stakes once the flip is done, like any other flip
+    Accept { id: u64 },
+    /// Cancel an open challenge, stake and flip fee are sent back to the creator.
+    /// Anyone can cancel it once it expired
+    Cancel { id: u64 },
+}
+
+#[cw_serde]
+pub enum AutoBetExecuteMsg {
+    /// Start an auto-bet with the sent coin as its budget, its first bet is queued right away.
+    /// DoFlips queues the next bet after each result, until the strategy says to stop,
+    /// the budget is sent back then.
+    /// Queued bets count as pending flips of the wallet
+    Start { strategy: AutoBetStrategy },
+    /// Stop the auto-bet and send back what is left of its budget,
+    /// the result of the bet waiting to be done is paid to the wallet
+    Withdraw {},
+}
+
+#[cw_serde]
+pub enum VoucherExecuteMsg {
+    /// Grant a wallet free flips of the amount, usable for expires_in blocks.
+    /// The bank covers their bets and they pay no flip fee
+    Grant {
+        wallet: String,
+        amount: Coin,
+        flips: u32,
+        expires_in: u64,
+    },
+    /// Remove a voucher of a wallet, the free flips it has left are lost
+    Revoke { wallet: String, id: u64 },
+}
+
+#[cw_serde]
+pub enum LeaderboardExecuteMsg {
+    /// Pay the prizes of an epoch that is over to the top wallets of its boards, call-able by anyone.
+    /// Coin prizes the prize pool can't cover and NFT prizes when the NFT pool is empty are skipped
+    FinalizeEpoch { epoch: u64 },
+}
+
+#[cw_serde]
+pub enum ReferralExecuteMsg {
+    /// Send the sender all its referral rewards not claimed yet
+    ClaimReferralRewards {},
+}
+
+/// Msgs embedded in a CW20 send, the tokens sent pay for the flip
+#[cw_serde]
+pub enum ReceiveMsg {
+    /// Register the flip, same as FlipExecuteMsg::StartFlip without vouchers
+    StartFlip {
+        pick: PickTypes,
+        amount: Uint128,
+        commitment: Option<String>,
+        client_seed: Option<String>,
+        referrer: Option<String>,
+    },
+}
+
 #[cw_serde]
 pub enum FlipExecuteMsg {
     /// Register the flip
-    StartFlip { pick: PickTypes, amount: Uint128 },
+    /// In commit-reveal mode, commitment is the sha256 hex of the player secret
+    /// client_seed is mixed into the outcome, so the flipper takes part in the randomness
+    /// With use_voucher, a voucher of the flipper for this amount of voucher_denom (the native denom
+    /// by default) pays the flip and no funds are sent, a win only pays the profit then
+    /// The first referrer sent is bound to the flipper for good, later ones are ignored
+    StartFlip {
+        pick: PickTypes,
+        amount: Uint128,
+        commitment: Option<String>,
+        client_seed: Option<String>,
+        use_voucher: Option<bool>,
+        voucher_denom: Option<String>,
+        referrer: Option<String>,
+    },
+    /// Register a flip for every (pick, amount), paid with a single coin of the sum of
+    /// the amounts and their flip fees
+    /// In commit-reveal mode, commitments has the commitment of every flip, in the order of the picks
+    /// client_seed and referrer work like in StartFlip, for all the flips of the batch
+    StartFlips {
+        picks: Vec<(PickTypes, Uint128)>,
+        commitments: Option<Vec<String>>,
+        client_seed: Option<String>,
+        referrer: Option<String>,
+    },
+    /// Reveal the secret of a commit-reveal flip
+    RevealFlip { id: u64, secret: String },
     /// Does the actual flip
-    DoFlips {},
+    /// In drand mode, round and signature are the beacon the waiting flips are resolved with
+    /// In hash-chain mode, house_seed is the next preimage of the house seed chain, flips resolve
+    /// with the seed of the index they were tied to when started, it can be left out to only do
+    /// the flips whose seed is revealed already
+    /// With a limit, only that many of the oldest waiting flips are looked at, the ready ones are done
+    /// and the rest stay waiting, the response counts the ready flips of the next batch
+    DoFlips {
+        round: Option<u64>,
+        signature: Option<HexBinary>,
+        house_seed: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Refund the waiting flips of the sender that expired, bet and flip fee included.
+    /// Either player of an accepted challenge can refund it, both get their stake back
+    /// Commit-reveal and drand flips are never refunded, their randomness can be known before
+    Refund {},
+    /// Cancel the flips the sender started in this block,
+    /// the flip fee is refunded or kept based on the cancel fee policy
+    CancelFlip {},
+}
+
+/// Randomness published by nois for the job we requested
+#[cw_serde]
+pub struct NoisCallback {
+    pub job_id: String,
+    pub published: Timestamp,
+    pub randomness: HexBinary,
+}
+
+/// Msgs we send to the nois proxy
+#[cw_serde]
+pub enum NoisProxyExecuteMsg {
+    GetNextRandomness { job_id: String },
 }
 
 #[cw_serde]
@@ -122,6 +313,11 @@ pub enum SudoMsg {
         min_bet: Uint128,
         max_bet: Uint128,
     },
+    /// Update the max amount a single flip can pay out, None removes the limit
+    UpdatePayoutLimit {
+        denom: String,
+        max_payout: Option<Uint128>,
+    },
     /// Pause the contract in case of emergency
     UpdatePause(bool),
     /// Update streak related config stuff
@@ -149,17 +345,76 @@ pub enum SudoMsg {
         contract: String,
         token_id: String,
     },
+    /// Update the randomness source, only possible when no flips are waiting
+    UpdateRandomness {
+        randomness: RandomnessSource,
+    },
+    /// Commit to the next operator seed (sha256 hex) of the commit-reveal mode
+    CommitOperatorSeed {
+        commitment: String,
+    },
+    /// Reveal the current operator seed, and optionally commit to the next one
+    RevealOperatorSeed {
+        seed: String,
+        next_commitment: Option<String>,
+    },
+    /// Commit to the final hash (sha256 hex) of a new house seed chain of the hash-chain mode
+    /// length is the amount of seeds that can be revealed from it
+    /// While the current chain has seeds left, the new one is queued and takes over once it is used up
+    /// Otherwise it replaces the current chain right away, only possible when no flips are waiting
+    CommitHouseChain {
+        head: String,
+        length: u64,
+    },
+    /// Update the amount of blocks after which a waiting flip can be refunded
+    UpdateFlipExpiry {
+        blocks: u64,
+    },
+    /// Update what happens to the flip fee when a flip is cancelled
+    UpdateCancelFeePolicy {
+        policy: CancelFeePolicy,
+    },
+    /// Update the amount of flips a wallet can have waiting at the same time
+    UpdateMaxPendingFlips {
+        max: u32,
+    },
+    /// Update what the caller of DoFlips earns for every flip it resolves
+    UpdateKeeperReward {
+        reward: KeeperReward,
+    },
+    /// Update the amount of blocks a PvP challenge can be accepted for
+    UpdateChallengeExpiry {
+        blocks: u64,
+    },
+    /// Update the addresses allowed to grant vouchers, next to the admin
+    UpdateVoucherIssuers {
+        issuers: Vec<String>,
+    },
+    /// Update the odds of a flip to win the jackpot, one chance in odds, 0 stops the draws
+    UpdateJackpotOdds {
+        odds: u64,
+    },
+    /// Update the leaderboard, None stops ranking the flips.
+    /// Epochs already started keep their end, the prize pools of denoms no prize is paid in anymore
+    /// are sent to the reserve
+    UpdateLeaderboard {
+        leaderboard: Option<LeaderboardConfig>,
+    },
 }
 
 #[cw_serde]
 pub enum MigrateMsg {
     Basic {},
+    /// v0.7 was only deployed on stargaze
+    #[cfg(feature = "stargaze")]
     FromV07 {
         nft_pool_max: u32,
         streak_nft_winning_amount: u32,
         streak_rewards: Vec<StreakReward>,
         allowed_to_send_nft: Vec<String>,
     },
+    /// Move the flips of v0.8 storage to the indexed maps
+    FromV08 {},
 }
 
 #[cw_serde]
@@ -167,9 +422,31 @@ pub struct DryDistributionResponse {
     pub total_fees: Uint128,
     pub team_total_fee: Uint128,
     pub reserve_total_fee: Uint128,
+    /// Part of the reserve fees going to the leaderboard prize pool
+    pub prize_pool_total_fee: Uint128,
     pub holders_total_fee: Uint128,
     pub holders_total_shares: Decimal,
     pub fees_per_token: Decimal,
     pub pay_to_holders: Uint128,
     pub number_of_holders: u64,
 }
+
+#[cw_serde]
+pub struct ReferralStatsResponse {
+    /// Referrer bound to the wallet
+    pub referrer: Option<Addr>,
+    /// Amount of wallets the wallet referred
+    pub referrals: u32,
+    pub earned: Vec<Coin>,
+    pub claimable: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct KeeperStatusResponse {
+    /// Amount of flips DoFlips would handle now
+    pub ready_flips: u32,
+    /// Denoms of the ready flips
+    pub denoms: Vec<String>,
+    /// What the caller of DoFlips would earn
+    pub bounty: Vec<Coin>,
+}
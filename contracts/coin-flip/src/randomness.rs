@@ -0,0 +1,372 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Env, HexBinary, StdResult, Storage, Timestamp, Uint128};
+use sha2::{Digest, Sha256};
+use sha256::Sha256Digest;
+
+use crate::drand::{derive_randomness, verify_beacon};
+use crate::state::{DRAND_BEACONS, HOUSE_SEEDS, OPERATOR_SEEDS};
+use crate::types::{Config, FlipProof, PickTypes, RandomnessSource, TodoFlip};
+
+/// Domain of the hash deciding the flip outcome
+const OUTCOME_DOMAIN: &str = "coin_flip_outcome";
+/// Domain of the hash picking the streak prize
+const PRIZE_DOMAIN: &str = "coin_flip_prize";
+/// Domain of the hash drawing the jackpot
+const JACKPOT_DOMAIN: &str = "coin_flip_jackpot";
+
+/// The randomness a flip is going to be resolved with
+#[cw_serde]
+pub enum FlipRandomness {
+    /// Random number of the block the flip is resolved in
+    Block,
+    /// Both the player secret and the operator seed were revealed
+    Revealed {
+        player_secret: String,
+        operator_seed: String,
+    },
+    /// Reveal window is over and the player never revealed his secret, the flip is lost
+    PlayerTimeout,
+    /// Reveal window is over and the operator never revealed his seed, the flip is won
+    OperatorTimeout,
+    /// Beacon of the drand round the flip is tied to
+    Drand { round: u64, randomness: String },
+    /// Randomness the nois proxy called us back with
+    Nois { job_id: String, randomness: String },
+    /// Nois proxy never called us back, the flip is refunded
+    NoisTimeout,
+    /// House seed revealed by the DoFlips of the hash-chain mode
+    HouseSeed { seed: String },
+}
+
+impl FlipRandomness {
+    /// Seed the flip is resolved with, timeouts fall back to the block seed
+    pub fn seed(&self, block_seed: &str) -> String {
+        match self {
+            FlipRandomness::Revealed {
+                player_secret,
+                operator_seed,
+            } => get_commit_reveal_seed(operator_seed, player_secret),
+            FlipRandomness::Drand { randomness, .. } | FlipRandomness::Nois { randomness, .. } => {
+                randomness.clone()
+            }
+            FlipRandomness::HouseSeed { seed } => seed.clone(),
+            FlipRandomness::Block
+            | FlipRandomness::PlayerTimeout
+            | FlipRandomness::OperatorTimeout
+            | FlipRandomness::NoisTimeout => block_seed.to_string(),
+        }
+    }
+}
+
+/// Outcome of a flip rolled with its randomness
+#[derive(Debug, PartialEq)]
+pub struct FlipOutcome {
+    pub seed: String,
+    pub outcome_random: Option<u64>,
+    pub result: bool,
+}
+
+/// Get the randomness we should use to resolve the flip,
+/// returns None if the flip is not ready to be resolved yet.
+pub fn get_flip_randomness(
+    storage: &dyn Storage,
+    env: &Env,
+    config: &Config,
+    todo_flip: &TodoFlip,
+) -> StdResult<Option<FlipRandomness>> {
+    // Flips are never resolved in the block they were started in
+    if todo_flip.block >= env.block.height {
+        return Ok(None);
+    }
+
+    if let Some(round) = todo_flip.drand_round {
+        return Ok(DRAND_BEACONS
+            .may_load(storage, round)?
+            .map(|beacon| FlipRandomness::Drand {
+                round,
+                randomness: beacon.randomness,
+            }));
+    }
+
+    if let Some(index) = todo_flip.house_seed_index {
+        return Ok(HOUSE_SEEDS
+            .may_load(storage, index)?
+            .map(|seed| FlipRandomness::HouseSeed { seed }));
+    }
+
+    // Nois flips are resolved on callback, we only handle the timeout here
+    if todo_flip.nois_job_id.is_some() {
+        let timeout = match config.randomness {
+            RandomnessSource::Nois { timeout, .. } => timeout,
+            _ => 0,
+        };
+
+        if env.block.height > todo_flip.block + timeout {
+            return Ok(Some(FlipRandomness::NoisTimeout));
+        }
+        return Ok(None);
+    }
+
+    let Some(commit) = &todo_flip.commit else {
+        return Ok(Some(FlipRandomness::Block));
+    };
+
+    let operator_seed = OPERATOR_SEEDS.load(storage, commit.operator_round)?.seed;
+
+    if let (Some(player_secret), Some(operator_seed)) = (commit.secret.clone(), operator_seed) {
+        return Ok(Some(FlipRandomness::Revealed {
+            player_secret,
+            operator_seed,
+        }));
+    }
+
+    let reveal_timeout = match config.randomness {
+        RandomnessSource::CommitReveal { reveal_timeout } => reveal_timeout,
+        _ => 0,
+    };
+
+    // Still waiting for reveals
+    if env.block.height <= todo_flip.block + reveal_timeout {
+        return Ok(None);
+    }
+
+    if commit.secret.is_none() {
+        Ok(Some(FlipRandomness::PlayerTimeout))
+    } else {
+        Ok(Some(FlipRandomness::OperatorTimeout))
+    }
+}
+
+/// Index of the transaction in its block, 0 when unknown
+pub fn get_tx_index(env: &Env) -> u32 {
+    if let Some(tx) = &env.transaction {
+        tx.index
+    } else {
+        0
+    }
+}
+
+/// Seed of the flips resolved in this block
+pub fn get_block_seed(env: &Env) -> String {
+    get_block_seed_at(get_tx_index(env), env.block.height, env.block.time)
+}
+
+/// Seed of the flips resolved by the transaction at this index of the block
+pub fn get_block_seed_at(tx_index: u32, height: u64, time: Timestamp) -> String {
+    Sha256Digest::digest(format!("{}{}{}", tx_index, height, time.nanos()))
+}
+
+/// Seed of a commit-reveal flip, made from the operator seed and the player secret
+pub fn get_commit_reveal_seed(operator_seed: &str, player_secret: &str) -> String {
+    Sha256Digest::digest(format!("{operator_seed}{player_secret}"))
+}
+
+/// Random number of a single flip, hashed from the seed, the client seed, the flip id and the wallet.
+/// Flips resolved with the same seed get independent numbers,
+/// and the domain keeps the outcome and the prize pick apart.
+fn get_flip_random(domain: &str, seed: &str, todo_flip: &TodoFlip) -> u64 {
+    let input = match &todo_flip.client_seed {
+        Some(client_seed) => format!(
+            "{domain}:{seed}:{client_seed}:{}:{}",
+            todo_flip.id, todo_flip.wallet
+        ),
+        None => format!("{domain}:{seed}:{}:{}", todo_flip.id, todo_flip.wallet),
+    };
+    let hash = Sha256::digest(input.as_bytes());
+
+    hash[..8].iter().fold(0, |acc, x| (acc << 8) | *x as u64)
+}
+
+/// Random number deciding the flip outcome, heads if it's even
+pub fn get_outcome_random(seed: &str, todo_flip: &TodoFlip) -> u64 {
+    get_flip_random(OUTCOME_DOMAIN, seed, todo_flip)
+}
+
+/// Random number used to pick the streak prize of the flip
+pub fn get_prize_random(seed: &str, todo_flip: &TodoFlip) -> u64 {
+    get_flip_random(PRIZE_DOMAIN, seed, todo_flip)
+}
+
+/// Random number used to draw the jackpot with the flip
+pub fn get_jackpot_random(seed: &str, todo_flip: &TodoFlip) -> u64 {
+    get_flip_random(JACKPOT_DOMAIN, seed, todo_flip)
+}
+
+/// Make sure the commitment is a hex encoded sha256 hash
+pub fn is_valid_commitment(commitment: &str) -> bool {
+    commitment.len() == 64 && commitment.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Verify the revealed secret matches the commitment
+pub fn verify_reveal(commitment: &str, secret: &str) -> bool {
+    Sha256Digest::digest(secret) == commitment.to_lowercase()
+}
+
+/// Commitment of the operator seed the commit-reveal flip is bound to
+pub fn get_operator_commitment(
+    storage: &dyn Storage,
+    todo_flip: &TodoFlip,
+) -> StdResult<Option<String>> {
+    let Some(commit) = &todo_flip.commit else {
+        return Ok(None);
+    };
+
+    Ok(Some(
+        OPERATOR_SEEDS
+            .load(storage, commit.operator_round)?
+            .commitment,
+    ))
+}
+
+/// Signature of the drand beacon the flip is tied to
+pub fn get_beacon_signature(
+    storage: &dyn Storage,
+    todo_flip: &TodoFlip,
+) -> StdResult<Option<HexBinary>> {
+    let Some(round) = todo_flip.drand_round else {
+        return Ok(None);
+    };
+
+    Ok(DRAND_BEACONS
+        .may_load(storage, round)?
+        .map(|beacon| beacon.signature))
+}
+
+/// Check the randomness of a proof against the commitments and the drand beacon it holds,
+/// returns everything that doesn't match. Drand signatures need the public key of the chain.
+pub fn check_proof_randomness(proof: &FlipProof, drand_pubkey: Option<&[u8]>) -> Vec<String> {
+    let mut mismatches = vec![];
+
+    match &proof.randomness {
+        FlipRandomness::Revealed {
+            player_secret,
+            operator_seed,
+        } => {
+            if !proof
+                .commitment
+                .as_ref()
+                .is_some_and(|commitment| verify_reveal(commitment, player_secret))
+            {
+                mismatches.push("player secret doesn't match the commitment".to_string());
+            }
+            if !proof
+                .operator_commitment
+                .as_ref()
+                .is_some_and(|commitment| verify_reveal(commitment, operator_seed))
+            {
+                mismatches.push("operator seed doesn't match the commitment".to_string());
+            }
+        }
+        FlipRandomness::PlayerTimeout | FlipRandomness::OperatorTimeout => {
+            if proof.commitment.is_none() {
+                mismatches.push("reveal timeout of a flip without commitment".to_string());
+            }
+        }
+        FlipRandomness::Drand { round, randomness } => {
+            if proof.drand_round != Some(*round) {
+                mismatches.push(format!(
+                    "drand round is {round}, the flip was tied to {:?}",
+                    proof.drand_round
+                ));
+            }
+            match &proof.signature {
+                Some(signature) => {
+                    if derive_randomness(signature.as_slice()) != *randomness {
+                        mismatches.push("drand randomness doesn't match the signature".to_string());
+                    }
+                    match drand_pubkey {
+                        Some(pubkey) if !verify_beacon(pubkey, *round, signature.as_slice()) => {
+                            mismatches
+                                .push(format!("drand signature is not valid for round {round}"))
+                        }
+                        Some(_) => {}
+                        None => mismatches.push(
+                            "drand signature not checked, the drand public key is missing"
+                                .to_string(),
+                        ),
+                    }
+                }
+                None => mismatches.push("drand signature is missing".to_string()),
+            }
+        }
+        FlipRandomness::Block => {
+            if proof.commitment.is_some() || proof.drand_round.is_some() {
+                mismatches.push(
+                    "block randomness for a flip tied to a commitment or a drand round".to_string(),
+                );
+            }
+        }
+        FlipRandomness::Nois { .. }
+        | FlipRandomness::NoisTimeout
+        | FlipRandomness::HouseSeed { .. } => {}
+    }
+
+    mismatches
+}
+
+/// Roll the flip with its randomness, timeouts decide the result without rolling
+pub fn roll_flip(
+    todo_flip: &TodoFlip,
+    flip_randomness: &FlipRandomness,
+    block_seed: &str,
+) -> FlipOutcome {
+    let seed = flip_randomness.seed(block_seed);
+
+    let (outcome_random, result) = match flip_randomness {
+        FlipRandomness::PlayerTimeout => (None, false),
+        FlipRandomness::OperatorTimeout => (None, true),
+        _ => (
+            Some(get_outcome_random(&seed, todo_flip)),
+            do_a_flip(todo_flip, &seed),
+        ),
+    };
+
+    FlipOutcome {
+        seed,
+        outcome_random,
+        result,
+    }
+}
+
+/// Recompute a resolved flip from its proof,
+/// the block seed is only used by block flips and reveal timeouts.
+pub fn replay_flip(proof: &FlipProof, block_seed: &str) -> FlipOutcome {
+    let todo_flip = TodoFlip {
+        id: proof.id,
+        wallet: proof.wallet.clone(),
+        amount: proof.amount.clone(),
+        pick: proof.pick.clone(),
+        block: proof.start_block,
+        timestamp: proof.timestamp,
+        commit: None,
+        drand_round: None,
+        nois_job_id: None,
+        house_seed_index: None,
+        fee: proof.fee,
+        jackpot_share: Uint128::zero(),
+        referral_shares: vec![],
+        client_seed: proof.client_seed.clone(),
+        opponent: proof.opponent.clone(),
+        auto_bet: false,
+        voucher: proof.voucher,
+    };
+
+    roll_flip(&todo_flip, &proof.randomness, block_seed)
+}
+
+pub fn do_a_flip(todo_flip: &TodoFlip, seed: &str) -> bool {
+    let outcome_random = get_outcome_random(seed, todo_flip);
+    if let PickTypes::RollUnder { win_chance } = todo_flip.pick {
+        return outcome_random % 100 < win_chance as u64;
+    }
+
+    let flip_result = outcome_random.is_multiple_of(2);
+
+    // if picked heads and flip_result is true, he won
+    let won_heads = todo_flip.pick == PickTypes::Heads && flip_result;
+    // if picked tails and flip_result is false, he won
+    let won_tails = todo_flip.pick == PickTypes::Tails && !flip_result;
+
+    // Return true if one of them is true (won) else return false (lost)
+    won_heads || won_tails
+}
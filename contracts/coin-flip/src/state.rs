@@ -1,7 +1,10 @@
-use cosmwasm_std::{Addr, StdError, Storage, Uint128};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Order, StdError, StdResult, Storage, Uint128};
+use cw_storage_plus::{Bound, Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 
-use crate::types::{Config, Flip, FlipScore, NftReward, StreakReward, TodoFlip};
+use crate::types::{
+    AutoBet, Challenge, Config, DrandBeacon, EpochScore, Flip, FlipProof, FlipScore, HouseChain,
+    Leaderboard, NftReward, OperatorSeed, ReferralStats, StreakReward, TodoFlip, Voucher,
+};
 
 /// Our config holds admin and fees %
 pub const CONFIG: Item<Config> = Item::new("config");
@@ -12,9 +15,178 @@ pub const FEES: Map<String, Uint128> = Map::new("total_fees");
 pub const SCORES: Map<&Addr, FlipScore> = Map::new("scores");
 /// Last Flip id
 pub const FLIP_ID: Item<u64> = Item::new("flip_id");
+/// Amount of latest flip ids the resolved flips are kept for, older ones are pruned
+pub const FLIPS_KEPT: u64 = 1000;
+/// Max amount of old flips pruned every time a flip is resolved
+const FLIPS_PRUNE_LIMIT: usize = 2;
+
+/// Indexes of the resolved flips
+pub struct FlipIndexes<'a> {
+    pub wallet: MultiIndex<'a, Addr, Flip, u64>,
+    /// Block the flip was resolved in
+    pub block: MultiIndex<'a, u64, Flip, u64>,
+}
+
+impl<'a> IndexList<Flip> for FlipIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Flip>> + '_> {
+        let v: Vec<&dyn Index<Flip>> = vec![&self.wallet, &self.block];
+        Box::new(v.into_iter())
+    }
+}
+
 /// Flips tracker so we can easily get stats later
-pub const FLIPS: Item<Vec<Flip>> = Item::new("last_flips");
-pub const TODO_FLIPS: Item<Vec<TodoFlip>> = Item::new("todo_flips");
+/// A map of flip id -> flip
+pub fn flips<'a>() -> IndexedMap<'a, u64, Flip, FlipIndexes<'a>> {
+    let indexes = FlipIndexes {
+        wallet: MultiIndex::new(|_pk, flip| flip.wallet.clone(), "flips", "flips__wallet"),
+        block: MultiIndex::new(|_pk, flip| flip.block, "flips", "flips__block"),
+    };
+    IndexedMap::new("flips", indexes)
+}
+
+/// Indexes of the flips waiting to be done
+pub struct TodoFlipIndexes<'a> {
+    pub wallet: MultiIndex<'a, Addr, TodoFlip, u64>,
+    /// Block the flip was started in
+    pub block: MultiIndex<'a, u64, TodoFlip, u64>,
+    /// Opponent of an accepted challenge, empty for the other flips
+    pub opponent: MultiIndex<'a, Addr, TodoFlip, u64>,
+}
+
+impl<'a> IndexList<TodoFlip> for TodoFlipIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<TodoFlip>> + '_> {
+        let v: Vec<&dyn Index<TodoFlip>> = vec![&self.wallet, &self.block, &self.opponent];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Flips waiting to be done
+/// A map of flip id -> todo flip
+pub fn todo_flips<'a>() -> IndexedMap<'a, u64, TodoFlip, TodoFlipIndexes<'a>> {
+    let indexes = TodoFlipIndexes {
+        wallet: MultiIndex::new(
+            |_pk, todo_flip| todo_flip.wallet.clone(),
+            "todo_flips_by_id",
+            "todo_flips_by_id__wallet",
+        ),
+        block: MultiIndex::new(
+            |_pk, todo_flip| todo_flip.block,
+            "todo_flips_by_id",
+            "todo_flips_by_id__block",
+        ),
+        opponent: MultiIndex::new(
+            |_pk, todo_flip| {
+                todo_flip
+                    .opponent
+                    .clone()
+                    .unwrap_or_else(|| Addr::unchecked(""))
+            },
+            "todo_flips_by_id",
+            "todo_flips_by_id__opponent",
+        ),
+    };
+    IndexedMap::new("todo_flips_by_id", indexes)
+}
+
+/// Proofs of all resolved flips
+/// A map of flip id -> proof
+pub const FLIP_PROOFS: Map<u64, FlipProof> = Map::new("flip_proofs");
+
+/// Operator seeds of the commit-reveal mode
+/// A map of round -> operator seed
+pub const OPERATOR_SEEDS: Map<u64, OperatorSeed> = Map::new("operator_seeds");
+/// Current operator seed round
+pub const OPERATOR_ROUND: Item<u64> = Item::new("operator_round");
+
+/// Verified drand beacons
+/// A map of round -> beacon
+pub const DRAND_BEACONS: Map<u64, DrandBeacon> = Map::new("drand_beacons");
+
+/// House seed chain of the hash-chain mode
+pub const HOUSE_CHAIN: Item<HouseChain> = Item::new("house_chain");
+
+/// House seeds revealed by DoFlips
+/// A map of index -> seed
+pub const HOUSE_SEEDS: Map<u64, String> = Map::new("house_seeds");
+
+/// Randomness requests sent to the nois proxy
+/// A map of job id -> flip id
+pub const NOIS_JOBS: Map<String, u64> = Map::new("nois_jobs");
+
+/// Indexes of the open PvP challenges
+pub struct ChallengeIndexes<'a> {
+    pub denom: MultiIndex<'a, String, Challenge, u64>,
+}
+
+impl<'a> IndexList<Challenge> for ChallengeIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Challenge>> + '_> {
+        let v: Vec<&dyn Index<Challenge>> = vec![&self.denom];
+        Box::new(v.into_iter())
+    }
+}
+
+/// PvP challenges waiting for a second player
+/// A map of challenge id -> challenge
+pub fn challenges<'a>() -> IndexedMap<'a, u64, Challenge, ChallengeIndexes<'a>> {
+    let indexes = ChallengeIndexes {
+        denom: MultiIndex::new(
+            |_pk, challenge| challenge.amount.denom.clone(),
+            "challenges",
+            "challenges__denom",
+        ),
+    };
+    IndexedMap::new("challenges", indexes)
+}
+/// Last challenge id
+pub const CHALLENGE_ID: Item<u64> = Item::new("challenge_id");
+/// Funds of PvP challenges held by the contract, stakes and fees of open challenges
+/// and stakes of accepted ones. They are owed to the players and never part of the bank.
+/// A map of denom -> amount
+pub const CHALLENGE_ESCROW: Map<String, Uint128> = Map::new("challenge_escrow");
+
+/// Auto-bets of the wallets, a single one per wallet
+/// A map of wallet -> auto-bet
+pub const AUTO_BETS: Map<&Addr, AutoBet> = Map::new("auto_bets");
+/// Sum of the auto-bet budgets, owed to the players and never part of the bank.
+/// A map of denom -> amount
+pub const AUTO_BET_BUDGETS: Map<String, Uint128> = Map::new("auto_bet_budgets");
+
+/// Jackpots funded by a share of the flip fees, won whole by a lucky flip.
+/// They are not part of the fees nor of the bank.
+/// A map of denom -> amount
+pub const JACKPOTS: Map<String, Uint128> = Map::new("jackpots");
+
+/// Id of the current leaderboard epoch
+pub const LEADERBOARD_EPOCH: Item<u64> = Item::new("leaderboard_epoch");
+
+/// A map of epoch -> leaderboard
+pub const LEADERBOARDS: Map<u64, Leaderboard> = Map::new("leaderboards");
+
+/// A map of (epoch, wallet) -> score of the wallet in the epoch
+pub const EPOCH_SCORES: Map<(u64, &Addr), EpochScore> = Map::new("epoch_scores");
+
+/// Coins set aside out of the reserve fees to pay the leaderboard prizes.
+/// A map of denom -> amount
+pub const PRIZE_POOLS: Map<String, Uint128> = Map::new("prize_pools");
+
+/// Referrer bound to a wallet by its first referred flip, it never changes
+/// A map of wallet -> referrer
+pub const REFERRERS: Map<&Addr, Addr> = Map::new("referrers");
+
+/// A map of referrer -> referral stats
+pub const REFERRAL_STATS: Map<&Addr, ReferralStats> = Map::new("referral_stats");
+
+/// Referral rewards held for unresolved flips or credited and not claimed yet, of all the referrers
+/// A map of denom -> amount
+pub const REFERRAL_REWARDS: Map<String, Uint128> = Map::new("referral_rewards");
+
+/// Free flips granted to the wallets
+/// A map of (wallet, voucher id) -> voucher
+pub const VOUCHERS: Map<(&Addr, u64), Voucher> = Map::new("vouchers");
+/// Last voucher id
+pub const VOUCHER_ID: Item<u64> = Item::new("voucher_id");
+/// Addresses allowed to grant vouchers, next to the admin
+pub const VOUCHER_ISSUERS: Item<Vec<Addr>> = Item::new("voucher_issuers");
 
 /// streak rewards list
 pub const STREAK_REWARDS: Item<Vec<StreakReward>> = Item::new("streak_rewards");
@@ -27,6 +199,13 @@ pub const NFT_REWARDS: Item<Vec<NftReward>> = Item::new("nft_rewards");
 pub fn get_flip_id(store: &dyn Storage) -> Result<u64, StdError> {
     FLIP_ID.load(store)
 }
+/// Are there flips waiting to be done
+pub fn has_todo_flips(store: &dyn Storage) -> bool {
+    todo_flips()
+        .keys(store, None, None, Order::Ascending)
+        .next()
+        .is_some()
+}
 /// helper function to get the next flip id.
 pub fn get_next_flip_id(store: &dyn Storage) -> u64 {
     match get_flip_id(store) {
@@ -35,11 +214,158 @@ pub fn get_next_flip_id(store: &dyn Storage) -> u64 {
     }
 }
 
+pub fn get_next_challenge_id(store: &dyn Storage) -> StdResult<u64> {
+    Ok(CHALLENGE_ID.may_load(store)?.map_or(0, |id| id + 1))
+}
+
+pub fn get_next_voucher_id(store: &dyn Storage) -> StdResult<u64> {
+    Ok(VOUCHER_ID.may_load(store)?.map_or(0, |id| id + 1))
+}
+
+/// Amount of a denom held for PvP challenges
+pub fn get_challenge_escrow(store: &dyn Storage, denom: &str) -> StdResult<Uint128> {
+    Ok(CHALLENGE_ESCROW
+        .may_load(store, denom.to_string())?
+        .unwrap_or_default())
+}
+
+pub fn get_jackpot(store: &dyn Storage, denom: &str) -> StdResult<Uint128> {
+    Ok(JACKPOTS
+        .may_load(store, denom.to_string())?
+        .unwrap_or_default())
+}
+
+pub fn get_prize_pool(store: &dyn Storage, denom: &str) -> StdResult<Uint128> {
+    Ok(PRIZE_POOLS
+        .may_load(store, denom.to_string())?
+        .unwrap_or_default())
+}
+
+/// Amount of a denom owed to the players, challenge stakes, auto-bet budgets, the jackpot,
+/// the leaderboard prizes and the referral rewards
+pub fn get_held_funds(store: &dyn Storage, denom: &str) -> StdResult<Uint128> {
+    let auto_bet_budgets = AUTO_BET_BUDGETS
+        .may_load(store, denom.to_string())?
+        .unwrap_or_default();
+    let referral_rewards = REFERRAL_REWARDS
+        .may_load(store, denom.to_string())?
+        .unwrap_or_default();
+    Ok(get_challenge_escrow(store, denom)?
+        + auto_bet_budgets
+        + get_jackpot(store, denom)?
+        + get_prize_pool(store, denom)?
+        + referral_rewards)
+}
+
+/// Remove the resolved flips older than the kept ones, a few at a time
+pub fn prune_flips(store: &mut dyn Storage, last_id: u64) -> StdResult<()> {
+    let Some(oldest_kept_id) = (last_id + 1).checked_sub(FLIPS_KEPT) else {
+        return Ok(());
+    };
+    let old_ids = flips()
+        .keys(
+            store,
+            None,
+            Some(Bound::exclusive(oldest_kept_id)),
+            Order::Ascending,
+        )
+        .take(FLIPS_PRUNE_LIMIT)
+        .collect::<StdResult<Vec<_>>>()?;
+    for id in old_ids {
+        flips().remove(store, id)?;
+    }
+    Ok(())
+}
+
+/// Storage of v0.8, before flips moved to indexed maps
+pub mod v08 {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{Addr, Coin, Timestamp};
+    use cw_storage_plus::Item;
+
+    use crate::types::{PickTypes, Streak};
+
+    #[cw_serde]
+    pub struct Flip {
+        pub wallet: Addr,
+        pub amount: Coin,
+        pub result: bool,
+        pub streak: Streak,
+        pub timestamp: Timestamp,
+    }
+
+    #[cw_serde]
+    pub struct TodoFlip {
+        pub id: u64,
+        pub wallet: Addr,
+        pub amount: Coin,
+        pub pick: PickTypes,
+        pub block: u64,
+        pub timestamp: Timestamp,
+    }
+
+    /// Last 5 flips, oldest first
+    pub const FLIPS: Item<Vec<Flip>> = Item::new("last_flips");
+    pub const TODO_FLIPS: Item<Vec<TodoFlip>> = Item::new("todo_flips");
+}
+
 #[cfg(test)]
 mod test {
-    use cosmwasm_std::Uint128;
+    use cosmwasm_std::{
+        coin, testing::mock_dependencies, Addr, Order, Storage, Timestamp, Uint128,
+    };
 
-    use crate::types::{Fees, FeesToPay};
+    use crate::types::{Fees, FeesToPay, Flip, Streak};
+
+    use super::{flips, prune_flips, FLIPS_KEPT};
+
+    #[test]
+    fn test_prune_flips() {
+        let mut deps = mock_dependencies();
+        let flip = |id: u64| Flip {
+            id,
+            wallet: Addr::unchecked("flipper"),
+            amount: coin(1, "ustars"),
+            result: true,
+            streak: Streak {
+                amount: 1,
+                result: true,
+            },
+            timestamp: Timestamp::from_seconds(id),
+            block: id,
+            client_seed: None,
+        };
+        let stored_ids = |store: &dyn Storage| {
+            flips()
+                .keys(store, None, None, Order::Ascending)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        };
+
+        // Nothing is pruned while there are less flips than kept
+        for id in 0..FLIPS_KEPT {
+            flips().save(deps.as_mut().storage, id, &flip(id)).unwrap();
+        }
+        prune_flips(deps.as_mut().storage, FLIPS_KEPT - 1).unwrap();
+        assert_eq!(stored_ids(deps.as_ref().storage).len() as u64, FLIPS_KEPT);
+
+        // A new flip prunes the oldest one, and a backlog is pruned a few at a time
+        let last_id = FLIPS_KEPT + 2;
+        flips()
+            .save(deps.as_mut().storage, last_id, &flip(last_id))
+            .unwrap();
+        prune_flips(deps.as_mut().storage, last_id).unwrap();
+        assert_eq!(stored_ids(deps.as_ref().storage)[..2], [2, 3]);
+        prune_flips(deps.as_mut().storage, last_id).unwrap();
+        assert_eq!(stored_ids(deps.as_ref().storage)[0], 3);
+        assert!(flips()
+            .idx
+            .block
+            .prefix(0)
+            .keys(deps.as_ref().storage, None, None, Order::Ascending)
+            .next()
+            .is_none());
+    }
 
     #[test]
     fn test_fee_calc() {
@@ -48,6 +374,8 @@ mod test {
             holders_bps: 7000,
             reserve_bps: 1500,
             flip_bps: 350,
+            jackpot_bps: 0,
+            referral_bps: 0,
         };
 
         let total_fees = Uint128::new(100);
@@ -1,16 +1,23 @@
 use std::collections::{HashMap, HashSet};
 
 use cosmwasm_std::{
-    coins, to_json_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env,
-    MessageInfo, Uint128, WasmMsg,
+    coin, ensure, to_json_binary, Addr, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Order,
+    StdResult, Uint128, WasmMsg,
 };
-use sg_std::{Response, StargazeMsgWrapper};
 
+use crate::chain::{CustomMsg, Response};
 use crate::error::ContractError;
-use crate::helpers::ensure_admin;
+use crate::helpers::{ensure_admin, query_denom_balance, send_coin_msg, CW20_DENOM_PREFIX};
 use crate::msg::SudoMsg;
-use crate::state::{ALLOWED_SEND_NFT, CONFIG, FEES, NFT_REWARDS, STREAK_REWARDS};
-use crate::types::{Config, DenomLimit, Fees, FeesToPay, StreakReward};
+use crate::randomness::{is_valid_commitment, verify_reveal};
+use crate::state::{
+    get_held_funds, get_prize_pool, has_todo_flips, ALLOWED_SEND_NFT, CONFIG, FEES, HOUSE_CHAIN,
+    NFT_REWARDS, OPERATOR_ROUND, OPERATOR_SEEDS, PRIZE_POOLS, STREAK_REWARDS, VOUCHER_ISSUERS,
+};
+use crate::types::{
+    CancelFeePolicy, Config, DenomLimit, Fees, FeesToPay, HouseChain, KeeperReward,
+    LeaderboardConfig, NextHouseChain, OperatorSeed, RandomnessSource, StreakReward,
+};
 
 pub fn handle_sudo_msg(
     deps: DepsMut,
@@ -47,6 +54,9 @@ pub fn handle_sudo_msg(
             min_bet,
             max_bet,
         } => update_bet_limit(deps, config, denom, min_bet, max_bet),
+        SudoMsg::UpdatePayoutLimit { denom, max_payout } => {
+            update_payout_limit(deps, config, denom, max_payout)
+        }
         SudoMsg::WithdrawNftFromPool { index, all } => {
             withdraw_nft_from_pool(deps, &config, index, all)
         }
@@ -54,6 +64,21 @@ pub fn handle_sudo_msg(
         SudoMsg::TransferNft { contract, token_id } => {
             transfer_nft(deps, &config, contract, token_id)
         }
+        SudoMsg::UpdateRandomness { randomness } => update_randomness(deps, config, randomness),
+        SudoMsg::CommitOperatorSeed { commitment } => commit_operator_seed(deps, env, commitment),
+        SudoMsg::RevealOperatorSeed {
+            seed,
+            next_commitment,
+        } => reveal_operator_seed(deps, env, seed, next_commitment),
+        SudoMsg::CommitHouseChain { head, length } => commit_house_chain(deps, head, length),
+        SudoMsg::UpdateFlipExpiry { blocks } => update_flip_expiry(deps, config, blocks),
+        SudoMsg::UpdateCancelFeePolicy { policy } => update_cancel_fee_policy(deps, config, policy),
+        SudoMsg::UpdateMaxPendingFlips { max } => update_max_pending_flips(deps, config, max),
+        SudoMsg::UpdateKeeperReward { reward } => update_keeper_reward(deps, config, reward),
+        SudoMsg::UpdateChallengeExpiry { blocks } => update_challenge_expiry(deps, config, blocks),
+        SudoMsg::UpdateVoucherIssuers { issuers } => update_voucher_issuers(deps, issuers),
+        SudoMsg::UpdateJackpotOdds { odds } => update_jackpot_odds(deps, config, odds),
+        SudoMsg::UpdateLeaderboard { leaderboard } => update_leaderboard(deps, config, leaderboard),
     }
 }
 
@@ -67,7 +92,9 @@ pub fn update_bank_limit(
     let mut bank_limit = config
         .denom_limits
         .get(&denom)
-        .expect("denom limit should exist in the map")
+        .ok_or(ContractError::DenomNotFound {
+            denom: denom.clone(),
+        })?
         .clone();
 
     bank_limit.bank = limit;
@@ -110,7 +137,9 @@ pub fn update_bet_limit(
     let mut denom_limit = config
         .denom_limits
         .get(&denom)
-        .expect("denom limit should exist in the map")
+        .ok_or(ContractError::DenomNotFound {
+            denom: denom.clone(),
+        })?
         .clone();
 
     denom_limit.min = min;
@@ -122,6 +151,28 @@ pub fn update_bet_limit(
     Ok(Response::default().add_attribute("method", "update_bet_limit"))
 }
 
+pub fn update_payout_limit(
+    deps: DepsMut,
+    mut config: Config,
+    denom: String,
+    max_payout: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let mut denom_limit = config
+        .denom_limits
+        .get(&denom)
+        .ok_or(ContractError::DenomNotFound {
+            denom: denom.clone(),
+        })?
+        .clone();
+
+    denom_limit.max_payout = max_payout;
+    config.denom_limits.insert(denom, denom_limit);
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::default().add_attribute("method", "update_payout_limit"))
+}
+
 pub fn update_pause(
     deps: DepsMut,
     mut config: Config,
@@ -172,6 +223,11 @@ pub fn add_new_denom(
     denom: String,
     limits: DenomLimit,
 ) -> Result<Response, ContractError> {
+    // CW20 denoms must point to a token contract
+    if let Some(token) = denom.strip_prefix(CW20_DENOM_PREFIX) {
+        deps.api.addr_validate(token)?;
+    }
+
     FEES.update(deps.storage, denom.clone(), |denom| match denom {
         Some(_) => Err(ContractError::DenomAlreadyExists),
         None => Ok(Uint128::zero()),
@@ -219,7 +275,9 @@ pub fn distribute(
     let bank_limit = config
         .denom_limits
         .get(&denom)
-        .expect("denom limit should exist in the map")
+        .ok_or(ContractError::DenomNotFound {
+            denom: denom.clone(),
+        })?
         .bank;
 
     // We do distribution per a single denom
@@ -232,7 +290,7 @@ pub fn distribute(
         },
     ) = calculate_fees_to_pay(config, total_fees)?;
 
-    let reserve_fees_to_send = verify_contract_balance(
+    let mut reserve_fees_to_send = verify_contract_balance(
         deps.as_ref(),
         env,
         denom.clone(),
@@ -241,8 +299,17 @@ pub fn distribute(
         bank_limit,
     )?;
 
+    let paid_to_prize_pool =
+        calculate_prize_pool_fees(deps.as_ref(), config, &denom, reserve_fees_to_send)?;
+    if !paid_to_prize_pool.is_zero() {
+        PRIZE_POOLS.update(deps.storage, denom.clone(), |prize_pool| {
+            Ok::<_, ContractError>(prize_pool.unwrap_or_default() + paid_to_prize_pool)
+        })?;
+        reserve_fees_to_send -= paid_to_prize_pool;
+    }
+
     // Handle holders fees
-    let mut msgs: Vec<BankMsg> = vec![];
+    let mut msgs: Vec<CosmosMsg<CustomMsg>> = vec![];
     let mut paid_to_holders = Uint128::zero();
     let mut total_shares = Decimal::zero();
     let mut fees_per_token = Decimal::zero();
@@ -258,10 +325,7 @@ pub fn distribute(
             let amount = fees_per_token.checked_mul(num)?.to_uint_floor();
 
             if !amount.is_zero() {
-                msgs.push(BankMsg::Send {
-                    to_address: addr,
-                    amount: coins(amount.into(), denom.clone()),
-                });
+                msgs.push(send_coin_msg(addr, coin(amount.into(), denom.clone()))?);
             }
 
             paid_to_holders = paid_to_holders.checked_add(amount)?;
@@ -269,17 +333,17 @@ pub fn distribute(
     }
 
     // create subMsg send to team wallet
-    msgs.push(BankMsg::Send {
-        to_address: config.wallets.team.clone(),
-        amount: coins(team_fees_to_send.into(), denom.clone()),
-    });
+    msgs.push(send_coin_msg(
+        config.wallets.team.clone(),
+        coin(team_fees_to_send.into(), denom.clone()),
+    )?);
 
     // Send to reserve
     if !reserve_fees_to_send.is_zero() {
-        msgs.push(BankMsg::Send {
-            to_address: config.wallets.reserve.clone(),
-            amount: coins(reserve_fees_to_send.into(), denom.clone()),
-        });
+        msgs.push(send_coin_msg(
+            config.wallets.reserve.clone(),
+            coin(reserve_fees_to_send.into(), denom.clone()),
+        )?);
     }
 
     // calculate remaining fees and save them to state
@@ -295,6 +359,7 @@ pub fn distribute(
         .add_attribute("reserve_paid", reserve_fees)
         .add_attribute("team_paid", team_fees_to_send)
         .add_attribute("holders_paid", paid_to_holders)
+        .add_attribute("prize_pool_paid", paid_to_prize_pool)
         .add_attribute("fees_per_token", fees_per_token.to_string())
         .add_attribute("total_shares", total_shares.to_string()))
 }
@@ -306,7 +371,7 @@ pub fn withdraw_nft_from_pool(
     all: Option<bool>,
 ) -> Result<Response, ContractError> {
     let nft_attr: String;
-    let mut msgs: Vec<CosmosMsg<StargazeMsgWrapper>> = vec![];
+    let mut msgs: Vec<CosmosMsg<CustomMsg>> = vec![];
 
     if all.is_some() {
         // loop over all NFTs in pool, and send them to team wallet.
@@ -376,26 +441,29 @@ pub fn send_excess_funds(
     config: &Config,
     denom: String,
 ) -> Result<Response, ContractError> {
-    let Coin {
-        amount: bank_amount,
-        denom,
-    } = deps.querier.query_balance(env.contract.address, denom)?;
-
-    let fees_amount = FEES.load(deps.storage, denom.clone())?;
-    let bank_balance = bank_amount - fees_amount;
     let bank_limit = config
         .denom_limits
         .get(&denom)
-        .expect("denom limit should exist in the map")
+        .ok_or(ContractError::DenomNotFound {
+            denom: denom.clone(),
+        })?
         .bank;
+    let bank_amount = query_denom_balance(&deps.querier, &env.contract.address, &denom)?;
+
+    let fees_amount = FEES.load(deps.storage, denom.clone())?;
+    // Funds held for the players and the leaderboard prizes are not part of the bank
+    let held_funds = get_held_funds(deps.storage, &denom)?;
+    let bank_balance = bank_amount
+        .checked_sub(fees_amount + held_funds)
+        .map_err(|_| ContractError::ContractMissingFunds(denom.clone()))?;
 
     if bank_balance > bank_limit {
         let to_send = bank_balance - bank_limit;
 
-        let msg = BankMsg::Send {
-            to_address: config.wallets.reserve.clone(),
-            amount: coins(to_send.into(), denom.clone()),
-        };
+        let msg = send_coin_msg(
+            config.wallets.reserve.clone(),
+            coin(to_send.into(), denom.clone()),
+        )?;
         Ok(Response::default()
             .add_message(msg)
             .add_attribute("action", "send_excess_funds")
@@ -431,6 +499,169 @@ pub fn transfer_nft(
     Ok(Response::default().add_message(msg))
 }
 
+pub fn update_randomness(
+    deps: DepsMut,
+    mut config: Config,
+    randomness: RandomnessSource,
+) -> Result<Response, ContractError> {
+    // Waiting flips are resolved based on the mode they were started in
+    ensure!(!has_todo_flips(deps.storage), ContractError::FlipsPending);
+
+    // Make sure the nois proxy is a valid address
+    if let RandomnessSource::Nois { proxy, .. } = &randomness {
+        deps.api.addr_validate(proxy.as_str())?;
+    }
+
+    config.randomness = randomness;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::default().add_attribute("method", "update_randomness"))
+}
+
+pub fn commit_operator_seed(
+    deps: DepsMut,
+    env: Env,
+    commitment: String,
+) -> Result<Response, ContractError> {
+    ensure!(
+        is_valid_commitment(&commitment),
+        ContractError::InvalidCommitment
+    );
+
+    // We can only commit to a new seed once the current one is revealed
+    let round = match OPERATOR_ROUND.may_load(deps.storage)? {
+        Some(round) => {
+            let operator_seed = OPERATOR_SEEDS.load(deps.storage, round)?;
+            ensure!(
+                operator_seed.seed.is_some(),
+                ContractError::OperatorSeedNotRevealed
+            );
+            round + 1
+        }
+        None => 0,
+    };
+
+    OPERATOR_SEEDS.save(
+        deps.storage,
+        round,
+        &OperatorSeed {
+            commitment: commitment.to_lowercase(),
+            seed: None,
+            block: env.block.height,
+        },
+    )?;
+    OPERATOR_ROUND.save(deps.storage, &round)?;
+
+    Ok(Response::default()
+        .add_attribute("method", "commit_operator_seed")
+        .add_attribute("operator_round", round.to_string())
+        .add_attribute("commitment", commitment))
+}
+
+pub fn reveal_operator_seed(
+    deps: DepsMut,
+    env: Env,
+    seed: String,
+    next_commitment: Option<String>,
+) -> Result<Response, ContractError> {
+    let round = OPERATOR_ROUND
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoOperatorCommitment)?;
+    let mut operator_seed = OPERATOR_SEEDS.load(deps.storage, round)?;
+
+    ensure!(
+        operator_seed.seed.is_none(),
+        ContractError::OperatorSeedAlreadyRevealed
+    );
+    ensure!(
+        verify_reveal(&operator_seed.commitment, &seed),
+        ContractError::InvalidReveal
+    );
+
+    operator_seed.seed = Some(seed.clone());
+    OPERATOR_SEEDS.save(deps.storage, round, &operator_seed)?;
+
+    let mut res = Response::default()
+        .add_attribute("method", "reveal_operator_seed")
+        .add_attribute("operator_round", round.to_string())
+        .add_attribute("operator_seed", seed);
+
+    if let Some(next_commitment) = next_commitment {
+        commit_operator_seed(deps, env, next_commitment.clone())?;
+        res = res.add_attribute("next_commitment", next_commitment);
+    }
+
+    Ok(res)
+}
+
+pub fn commit_house_chain(
+    deps: DepsMut,
+    head: String,
+    length: u64,
+) -> Result<Response, ContractError> {
+    ensure!(is_valid_commitment(&head), ContractError::InvalidCommitment);
+    ensure!(length > 0, ContractError::InvalidHouseChainLength);
+
+    let head = head.to_lowercase();
+    let (house_chain, queued) = match HOUSE_CHAIN.may_load(deps.storage)? {
+        // The current chain still has seeds to reveal, so the new one is queued behind it
+        Some(mut house_chain) if house_chain.remaining > 0 => {
+            house_chain.next = Some(NextHouseChain {
+                head: head.clone(),
+                length,
+            });
+            (house_chain, true)
+        }
+        current => {
+            // Waiting flips must be resolved with the chain that was committed when they started
+            ensure!(!has_todo_flips(deps.storage), ContractError::FlipsPending);
+            // Seed indexes keep counting, so flips never get a seed of the previous chain
+            let house_chain = HouseChain {
+                head: head.clone(),
+                remaining: length,
+                revealed: current
+                    .map(|house_chain| house_chain.revealed)
+                    .unwrap_or_default(),
+                next: None,
+            };
+            (house_chain, false)
+        }
+    };
+    HOUSE_CHAIN.save(deps.storage, &house_chain)?;
+
+    Ok(Response::default()
+        .add_attribute("method", "commit_house_chain")
+        .add_attribute("head", head)
+        .add_attribute("length", length.to_string())
+        .add_attribute("queued", queued.to_string()))
+}
+
+pub fn update_flip_expiry(
+    deps: DepsMut,
+    mut config: Config,
+    blocks: u64,
+) -> Result<Response, ContractError> {
+    ensure!(blocks > 0, ContractError::InvalidFlipExpiry);
+
+    config.flip_expiry = blocks;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::default()
+        .add_attribute("method", "update_flip_expiry")
+        .add_attribute("blocks", blocks.to_string()))
+}
+
+pub fn update_cancel_fee_policy(
+    deps: DepsMut,
+    mut config: Config,
+    policy: CancelFeePolicy,
+) -> Result<Response, ContractError> {
+    config.cancel_fee_policy = policy;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::default().add_attribute("method", "update_cancel_fee_policy"))
+}
+
 pub fn calculate_fees_to_pay(
     config: &Config,
     total_fees: Uint128,
@@ -457,6 +688,24 @@ pub fn calculate_fees_to_pay(
     }
 }
 
+/// Part of the reserve fees filling the leaderboard prize pool, up to the prizes of an epoch
+pub fn calculate_prize_pool_fees(
+    deps: Deps,
+    config: &Config,
+    denom: &str,
+    reserve_fees_to_send: Uint128,
+) -> Result<Uint128, ContractError> {
+    let Some(leaderboard) = &config.leaderboard else {
+        return Ok(Uint128::zero());
+    };
+    let prize_pool = get_prize_pool(deps.storage, denom)?;
+
+    Ok(leaderboard
+        .prizes_amount(denom)
+        .saturating_sub(prize_pool)
+        .min(reserve_fees_to_send))
+}
+
 pub fn verify_contract_balance(
     deps: Deps,
     env: Env,
@@ -466,10 +715,10 @@ pub fn verify_contract_balance(
     bank_limit: Uint128,
 ) -> Result<Uint128, ContractError> {
     let mut reserve_fees_to_send = reserve_fees;
-    let contract_balance = deps.querier.query_balance(env.contract.address, denom)?;
+    let held_funds = get_held_funds(deps.storage, &denom)?;
+    let contract_balance = query_denom_balance(&deps.querier, &env.contract.address, &denom)?;
     let bank_balance = contract_balance
-        .amount
-        .checked_sub(total_fees)
+        .checked_sub(total_fees + held_funds)
         .map_err(|_| ContractError::NotEnoughFundsToPayFees)?;
 
     if bank_balance < bank_limit {
@@ -530,3 +779,114 @@ pub fn get_share(num: u32) -> Result<Decimal, ContractError> {
         Ok(Decimal::one()) // 1
     }
 }
+
+pub fn update_max_pending_flips(
+    deps: DepsMut,
+    mut config: Config,
+    max: u32,
+) -> Result<Response, ContractError> {
+    ensure!(max > 0, ContractError::InvalidMaxPendingFlips);
+
+    config.max_pending_flips = max;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::default()
+        .add_attribute("method", "update_max_pending_flips")
+        .add_attribute("max", max.to_string()))
+}
+
+pub fn update_keeper_reward(
+    deps: DepsMut,
+    mut config: Config,
+    reward: KeeperReward,
+) -> Result<Response, ContractError> {
+    ensure!(reward.is_valid(), ContractError::InvalidKeeperReward);
+
+    config.keeper_reward = reward;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::default().add_attribute("method", "update_keeper_reward"))
+}
+
+pub fn update_challenge_expiry(
+    deps: DepsMut,
+    mut config: Config,
+    blocks: u64,
+) -> Result<Response, ContractError> {
+    ensure!(blocks > 0, ContractError::InvalidChallengeExpiry);
+
+    config.challenge_expiry = blocks;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::default()
+        .add_attribute("method", "update_challenge_expiry")
+        .add_attribute("blocks", blocks.to_string()))
+}
+
+pub fn update_voucher_issuers(
+    deps: DepsMut,
+    issuers: Vec<String>,
+) -> Result<Response, ContractError> {
+    let addrs = issuers
+        .iter()
+        .map(|addr| deps.api.addr_validate(addr))
+        .collect::<Result<Vec<Addr>, _>>()?;
+    VOUCHER_ISSUERS.save(deps.storage, &addrs)?;
+
+    Ok(Response::default().add_attribute("method", "update_voucher_issuers"))
+}
+
+pub fn update_jackpot_odds(
+    deps: DepsMut,
+    mut config: Config,
+    odds: u64,
+) -> Result<Response, ContractError> {
+    config.jackpot_odds = odds;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::default()
+        .add_attribute("method", "update_jackpot_odds")
+        .add_attribute("odds", odds.to_string()))
+}
+
+pub fn update_leaderboard(
+    deps: DepsMut,
+    mut config: Config,
+    leaderboard: Option<LeaderboardConfig>,
+) -> Result<Response, ContractError> {
+    if let Some(leaderboard) = &leaderboard {
+        ensure!(
+            leaderboard.is_valid(&config.denoms),
+            ContractError::InvalidLeaderboard
+        );
+    }
+
+    // Prize pools no prize is paid from anymore go back to the reserve
+    let prize_pools = PRIZE_POOLS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut msgs: Vec<CosmosMsg<CustomMsg>> = vec![];
+    for (denom, prize_pool) in prize_pools {
+        if leaderboard
+            .as_ref()
+            .is_some_and(|leaderboard| !leaderboard.prizes_amount(&denom).is_zero())
+        {
+            continue;
+        }
+
+        PRIZE_POOLS.remove(deps.storage, denom.clone());
+        if !prize_pool.is_zero() {
+            msgs.push(send_coin_msg(
+                config.wallets.reserve.clone(),
+                coin(prize_pool.u128(), denom),
+            )?);
+        }
+    }
+
+    config.leaderboard = leaderboard;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::default()
+        .add_messages(msgs)
+        .add_attribute("method", "update_leaderboard"))
+}
@@ -1,10 +1,30 @@
 mod utils;
 
+mod test_auto_bet;
+mod test_cancel;
+mod test_challenge;
+mod test_commit_reveal;
 mod test_contract;
+mod test_cw20;
+mod test_dice;
 mod test_distribute;
+mod test_drand;
+mod test_hash_chain;
+mod test_jackpot;
+mod test_keeper;
+mod test_lazy_flips;
+mod test_leaderboard;
 mod test_migration;
+// v0.7 was only deployed on stargaze, the migrations from v0.8 are tested with both bindings
+#[cfg(feature = "stargaze")]
+mod test_migration_v07;
 mod test_multiple_denoms;
+mod test_nois;
 mod test_queries;
+mod test_randomness;
+mod test_referral;
+mod test_refund;
 mod test_streak;
 mod test_sudo;
 mod test_types;
+mod test_voucher;
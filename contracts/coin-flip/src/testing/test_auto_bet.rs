@@ -0,0 +1,326 @@
+use cosmwasm_std::{Addr, Uint128};
+use cw_multi_test::AppResponse;
+
+use crate::{
+    error::ContractError,
+    testing::utils::{
+        executes::{
+            execute_do_flips, execute_open_challenge, execute_start_auto_bet, execute_start_flips,
+            execute_withdraw_auto_bet, sudo_update_max_pending_flips,
+        },
+        queries::{query_auto_bet, query_flip_proof},
+    },
+    types::{AutoBetStrategy, BetProgression, PickRule, PickTypes},
+};
+
+use super::utils::{
+    helpers::{MIN_FEES, MIN_FUNDS},
+    setup::{
+        next_block, setup_base_contract, BaseApp, FLIPPER_ADDR, FLIPPER_ADDR2, MIN_BET,
+        NATIVE_DENOM,
+    },
+};
+
+const DEPOSIT: Uint128 = Uint128::new(100_000_000);
+
+fn strategy(progression: BetProgression, pick_rule: PickRule) -> AutoBetStrategy {
+    AutoBetStrategy {
+        pick: PickTypes::Heads,
+        pick_rule,
+        base_bet: MIN_BET,
+        progression,
+        stop_loss: None,
+        take_profit: None,
+        max_rounds: 3,
+    }
+}
+
+fn query_balance(app: &BaseApp, addr: &str) -> Uint128 {
+    app.wrap().query_balance(addr, NATIVE_DENOM).unwrap().amount
+}
+
+fn get_stop_reason(res: &AppResponse) -> Option<String> {
+    res.events
+        .iter()
+        .find(|event| event.ty == "wasm-stop_auto_bet")
+        .and_then(|event| event.attributes.iter().find(|attr| attr.key == "reason"))
+        .map(|attr| attr.value.clone())
+}
+
+#[test]
+fn test_auto_bet_rounds() {
+    let (mut app, contract_addr) = setup_base_contract();
+    let old_balance = query_balance(&app, FLIPPER_ADDR);
+
+    execute_start_auto_bet(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        strategy(BetProgression::Fixed, PickRule::Fixed),
+        DEPOSIT,
+    )
+    .unwrap();
+
+    // The first bet is queued right away
+    let auto_bet = query_auto_bet(&app, contract_addr.clone(), FLIPPER_ADDR)
+        .unwrap()
+        .unwrap();
+    assert_eq!(auto_bet.rounds, 1);
+    assert_eq!(auto_bet.pending_flip, Some(0));
+    assert_eq!(auto_bet.budget, DEPOSIT - MIN_FUNDS);
+
+    // Every DoFlips queues the next bet, until the max rounds
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    let res = execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    assert_eq!(get_stop_reason(&res), Some("max_rounds".to_string()));
+
+    let auto_bet = query_auto_bet(&app, contract_addr.clone(), FLIPPER_ADDR).unwrap();
+    assert_eq!(auto_bet, None);
+
+    // The budget is sent back with the winnings
+    let mut payouts = Uint128::zero();
+    for id in 0..3 {
+        let proof = query_flip_proof(&app, contract_addr.clone(), id).unwrap();
+        assert_eq!(proof.amount.amount, MIN_BET);
+        assert_eq!(proof.pick, PickTypes::Heads);
+        payouts += proof.payout;
+    }
+    assert_eq!(
+        query_balance(&app, FLIPPER_ADDR),
+        old_balance - MIN_FUNDS * Uint128::new(3) + payouts
+    );
+}
+
+#[test]
+fn test_auto_bet_martingale() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    execute_start_auto_bet(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        strategy(BetProgression::Martingale, PickRule::Alternate),
+        DEPOSIT,
+    )
+    .unwrap();
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    // The bet doubles after a loss, and the pick switches every round
+    let proof = query_flip_proof(&app, contract_addr.clone(), 0).unwrap();
+    let auto_bet = query_auto_bet(&app, contract_addr.clone(), FLIPPER_ADDR)
+        .unwrap()
+        .unwrap();
+    let next_bet = if proof.result {
+        MIN_BET
+    } else {
+        MIN_BET * Uint128::new(2)
+    };
+    assert_eq!(auto_bet.rounds, 2);
+    assert_eq!(auto_bet.pending_flip, Some(1));
+    assert_eq!(auto_bet.next_bet, next_bet);
+    assert_eq!(auto_bet.next_pick, PickTypes::Tails);
+
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    let proof = query_flip_proof(&app, contract_addr, 1).unwrap();
+    assert_eq!(proof.amount.amount, next_bet);
+    assert_eq!(proof.pick, PickTypes::Tails);
+}
+
+#[test]
+fn test_auto_bet_stop_loss_and_take_profit() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    // Any result of the first round stops the auto-bet
+    execute_start_auto_bet(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        AutoBetStrategy {
+            stop_loss: Some(MIN_FUNDS),
+            take_profit: Some(MIN_BET - MIN_FEES),
+            ..strategy(BetProgression::Fixed, PickRule::Fixed)
+        },
+        DEPOSIT,
+    )
+    .unwrap();
+    let res = execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    let proof = query_flip_proof(&app, contract_addr.clone(), 0).unwrap();
+    let reason = if proof.result {
+        "take_profit"
+    } else {
+        "stop_loss"
+    };
+    assert_eq!(get_stop_reason(&res), Some(reason.to_string()));
+
+    let auto_bet = query_auto_bet(&app, contract_addr, FLIPPER_ADDR).unwrap();
+    assert_eq!(auto_bet, None);
+}
+
+#[test]
+fn test_auto_bet_withdraw() {
+    let (mut app, contract_addr) = setup_base_contract();
+    let old_balance = query_balance(&app, FLIPPER_ADDR);
+
+    execute_start_auto_bet(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        strategy(BetProgression::Fixed, PickRule::Fixed),
+        DEPOSIT,
+    )
+    .unwrap();
+
+    // The budget is sent back, the waiting bet stays
+    execute_withdraw_auto_bet(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+    )
+    .unwrap();
+    assert_eq!(query_balance(&app, FLIPPER_ADDR), old_balance - MIN_FUNDS);
+
+    let err = execute_withdraw_auto_bet(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoAutoBet);
+
+    // Its result is paid to the wallet, and no new bet is queued
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    let proof = query_flip_proof(&app, contract_addr.clone(), 0).unwrap();
+    assert_eq!(
+        query_balance(&app, FLIPPER_ADDR),
+        old_balance - MIN_FUNDS + proof.payout
+    );
+
+    let err = execute_do_flips(&mut app, contract_addr).unwrap_err();
+    assert_eq!(err, ContractError::NoFlipsToDo);
+}
+
+#[test]
+fn test_auto_bet_block_limit() {
+    let (mut app, contract_addr) = setup_base_contract();
+    sudo_update_max_pending_flips(&mut app, contract_addr.clone(), 10).unwrap();
+
+    execute_start_flips(
+        &mut app,
+        contract_addr.clone(),
+        vec![(PickTypes::Heads, MIN_BET); 5],
+        Addr::unchecked(FLIPPER_ADDR2),
+        NATIVE_DENOM,
+        MIN_FUNDS * Uint128::new(5),
+    )
+    .unwrap();
+    execute_start_auto_bet(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        strategy(BetProgression::Fixed, PickRule::Fixed),
+        DEPOSIT,
+    )
+    .unwrap();
+
+    // Another player fills the next block, the lazy flips only get to its older flips
+    app.update_block(next_block);
+    let res = execute_start_flips(
+        &mut app,
+        contract_addr.clone(),
+        vec![(PickTypes::Heads, MIN_BET); 10],
+        Addr::unchecked(FLIPPER_ADDR2),
+        NATIVE_DENOM,
+        MIN_FUNDS * Uint128::new(10),
+    )
+    .unwrap();
+    assert_eq!(get_stop_reason(&res), None);
+
+    // The auto-bet flip is resolved in the full block by the next execute
+    let res = execute_open_challenge(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR2),
+        PickTypes::Heads,
+        MIN_BET,
+        MIN_FUNDS,
+    )
+    .unwrap();
+    assert_eq!(get_stop_reason(&res), Some("block_limit".to_string()));
+
+    let auto_bet = query_auto_bet(&app, contract_addr, FLIPPER_ADDR).unwrap();
+    assert_eq!(auto_bet, None);
+}
+
+#[test]
+fn test_auto_bet_errors() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    let err = execute_start_auto_bet(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        AutoBetStrategy {
+            max_rounds: 0,
+            ..strategy(BetProgression::Fixed, PickRule::Fixed)
+        },
+        DEPOSIT,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidAutoBetRounds);
+
+    let err = execute_start_auto_bet(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        AutoBetStrategy {
+            stop_loss: Some(Uint128::zero()),
+            ..strategy(BetProgression::Fixed, PickRule::Fixed)
+        },
+        DEPOSIT,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidAutoBetLimits);
+
+    let err = execute_start_auto_bet(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        AutoBetStrategy {
+            pick: PickTypes::RollUnder { win_chance: 50 },
+            ..strategy(BetProgression::Fixed, PickRule::Alternate)
+        },
+        DEPOSIT,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidAutoBetPickRule);
+
+    let err = execute_start_auto_bet(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        strategy(BetProgression::Fixed, PickRule::Fixed),
+        MIN_FUNDS - Uint128::one(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AutoBetBudgetTooLow);
+
+    execute_start_auto_bet(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        strategy(BetProgression::Fixed, PickRule::Fixed),
+        DEPOSIT,
+    )
+    .unwrap();
+    let err = execute_start_auto_bet(
+        &mut app,
+        contract_addr,
+        Addr::unchecked(FLIPPER_ADDR),
+        strategy(BetProgression::Fixed, PickRule::Fixed),
+        DEPOSIT,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AutoBetExists);
+}
@@ -0,0 +1,149 @@
+use cosmwasm_std::{Addr, Uint128};
+use cw_multi_test::AppResponse;
+
+use crate::{
+    error::ContractError,
+    testing::utils::{
+        executes::{execute_cancel_flip, sudo_update_cancel_fee_policy},
+        queries::query_fees,
+        setup::next_block,
+    },
+    types::{CancelFeePolicy, PickTypes},
+};
+
+use super::utils::{
+    executes::{execute_do_flips, execute_start_flip},
+    helpers::{MIN_FEES, MIN_FUNDS},
+    setup::{setup_base_contract, BaseApp, FLIPPER_ADDR, MIN_BET, NATIVE_DENOM},
+};
+
+fn start_flip(app: &mut BaseApp, contract_addr: Addr) -> AppResponse {
+    execute_start_flip(
+        app,
+        contract_addr,
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap()
+}
+
+fn get_event_attr(res: &AppResponse, ty: &str, key: &str) -> String {
+    res.events
+        .iter()
+        .find(|event| event.ty == ty)
+        .unwrap()
+        .attributes
+        .iter()
+        .find(|attr| attr.key == key)
+        .unwrap()
+        .value
+        .clone()
+}
+
+#[test]
+fn test_cancel_flip_full_refund() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    let old_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+
+    let start_res = start_flip(&mut app, contract_addr.clone());
+    let cancel_res = execute_cancel_flip(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+    )
+    .unwrap();
+
+    // Indexers pair the cancel with the start by flip id and flipper
+    for key in ["id", "flipper"] {
+        assert_eq!(
+            get_event_attr(&start_res, "wasm-start_flip", key),
+            get_event_attr(&cancel_res, "wasm-cancel_flip", key)
+        );
+    }
+    assert_eq!(
+        get_event_attr(&cancel_res, "wasm-cancel_flip", "kept_fee"),
+        "0"
+    );
+
+    let new_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(new_balance.amount, old_balance.amount);
+    assert_eq!(
+        query_fees(&app, contract_addr.clone(), NATIVE_DENOM).unwrap(),
+        Uint128::zero()
+    );
+
+    // Nothing left to flip
+    let err = execute_do_flips(&mut app, contract_addr).unwrap_err();
+    assert_eq!(err, ContractError::NoFlipsToDo);
+}
+
+#[test]
+fn test_cancel_flip_keep_fee() {
+    let (mut app, contract_addr) = setup_base_contract();
+    sudo_update_cancel_fee_policy(&mut app, contract_addr.clone(), CancelFeePolicy::KeepFee)
+        .unwrap();
+
+    let old_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+
+    start_flip(&mut app, contract_addr.clone());
+    let res = execute_cancel_flip(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+    )
+    .unwrap();
+    assert_eq!(
+        get_event_attr(&res, "wasm-cancel_flip", "kept_fee"),
+        MIN_FEES.to_string()
+    );
+
+    let new_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(new_balance.amount, old_balance.amount - MIN_FEES);
+    assert_eq!(
+        query_fees(&app, contract_addr, NATIVE_DENOM).unwrap(),
+        MIN_FEES
+    );
+}
+
+#[test]
+fn test_cancel_flip_after_start_block() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    let err = execute_cancel_flip(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoFlipsToCancel);
+
+    start_flip(&mut app, contract_addr.clone());
+    app.update_block(next_block);
+
+    // DoFlips can resolve the flip already
+    let err = execute_cancel_flip(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::CancelWindowPassed);
+
+    execute_do_flips(&mut app, contract_addr).unwrap();
+}
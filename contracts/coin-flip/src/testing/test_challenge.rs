@@ -0,0 +1,233 @@
+use cosmwasm_std::{Addr, Uint128};
+
+use crate::{
+    error::ContractError,
+    testing::utils::{
+        executes::{
+            execute_accept_challenge, execute_cancel_challenge, execute_do_flips,
+            execute_open_challenge, sudo_update_challenge_expiry, sudo_withdraw_excess,
+        },
+        queries::{query_challenges, query_fees, query_flip_proof},
+    },
+    types::PickTypes,
+};
+
+use super::utils::setup::{
+    next_block, setup_base_contract, BaseApp, FLIPPER_ADDR, FLIPPER_ADDR2, MIN_BET, NATIVE_DENOM,
+};
+
+/// Above the max bet, challenges have no max stake
+const STAKE: Uint128 = Uint128::new(100_000_000);
+const STAKE_FEE: Uint128 = Uint128::new(3_500_000);
+const STAKE_FUNDS: Uint128 = Uint128::new(103_500_000);
+
+fn query_balance(app: &BaseApp, addr: &str) -> Uint128 {
+    app.wrap().query_balance(addr, NATIVE_DENOM).unwrap().amount
+}
+
+#[test]
+fn test_challenge_flip() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    execute_open_challenge(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        PickTypes::Heads,
+        STAKE,
+        STAKE_FUNDS,
+    )
+    .unwrap();
+
+    let challenges = query_challenges(&app, contract_addr.clone(), NATIVE_DENOM, None).unwrap();
+    assert_eq!(challenges.len(), 1);
+    assert_eq!(challenges[0].id, 0);
+    assert_eq!(challenges[0].creator, Addr::unchecked(FLIPPER_ADDR));
+    assert_eq!(challenges[0].amount.amount, STAKE);
+    assert_eq!(challenges[0].fee, STAKE_FEE);
+
+    // Held funds are not part of the bank
+    let err = sudo_withdraw_excess(&mut app, contract_addr.clone(), NATIVE_DENOM).unwrap_err();
+    assert_eq!(err, ContractError::NoExcessFunds);
+
+    execute_accept_challenge(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR2),
+        0,
+        STAKE_FUNDS,
+    )
+    .unwrap();
+
+    let challenges = query_challenges(&app, contract_addr.clone(), NATIVE_DENOM, None).unwrap();
+    assert!(challenges.is_empty());
+    let fees = query_fees(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert_eq!(fees, STAKE_FEE * Uint128::new(2));
+
+    let creator_balance = query_balance(&app, FLIPPER_ADDR);
+    let opponent_balance = query_balance(&app, FLIPPER_ADDR2);
+
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    // The winner takes both stakes
+    let proof = query_flip_proof(&app, contract_addr.clone(), 0).unwrap();
+    assert_eq!(proof.opponent, Some(Addr::unchecked(FLIPPER_ADDR2)));
+    if proof.result {
+        assert_eq!(proof.payout, STAKE * Uint128::new(2));
+        assert_eq!(
+            query_balance(&app, FLIPPER_ADDR),
+            creator_balance + STAKE * Uint128::new(2)
+        );
+        assert_eq!(query_balance(&app, FLIPPER_ADDR2), opponent_balance);
+    } else {
+        assert_eq!(proof.payout, Uint128::zero());
+        assert_eq!(query_balance(&app, FLIPPER_ADDR), creator_balance);
+        assert_eq!(
+            query_balance(&app, FLIPPER_ADDR2),
+            opponent_balance + STAKE * Uint128::new(2)
+        );
+    }
+
+    // The bank didn't win or lose anything
+    let err = sudo_withdraw_excess(&mut app, contract_addr, NATIVE_DENOM).unwrap_err();
+    assert_eq!(err, ContractError::NoExcessFunds);
+}
+
+#[test]
+fn test_cancel_challenge() {
+    let (mut app, contract_addr) = setup_base_contract();
+    sudo_update_challenge_expiry(&mut app, contract_addr.clone(), 5).unwrap();
+    let creator_balance = query_balance(&app, FLIPPER_ADDR);
+
+    execute_open_challenge(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        PickTypes::Tails,
+        STAKE,
+        STAKE_FUNDS,
+    )
+    .unwrap();
+
+    // Only the creator can cancel before it expired
+    let err = execute_cancel_challenge(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR2),
+        0,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotChallengeCreator);
+
+    for _ in 0..5 {
+        app.update_block(next_block);
+    }
+
+    let err = execute_accept_challenge(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR2),
+        0,
+        STAKE_FUNDS,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ChallengeExpired(0));
+
+    // Anyone can cancel it now, stake and fee go back to the creator
+    execute_cancel_challenge(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR2),
+        0,
+    )
+    .unwrap();
+    assert_eq!(query_balance(&app, FLIPPER_ADDR), creator_balance);
+
+    let challenges = query_challenges(&app, contract_addr.clone(), NATIVE_DENOM, None).unwrap();
+    assert!(challenges.is_empty());
+
+    let err = execute_cancel_challenge(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        0,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ChallengeNotFound(0));
+
+    let err = sudo_withdraw_excess(&mut app, contract_addr, NATIVE_DENOM).unwrap_err();
+    assert_eq!(err, ContractError::NoExcessFunds);
+}
+
+#[test]
+fn test_challenge_errors() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    let err = execute_open_challenge(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        PickTypes::RollUnder { win_chance: 50 },
+        STAKE,
+        STAKE_FUNDS,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidChallengePick);
+
+    let err = execute_open_challenge(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        PickTypes::Heads,
+        MIN_BET - Uint128::one(),
+        STAKE_FUNDS,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnderTheLimitBet {
+            min_limit: "5".to_string()
+        }
+    );
+
+    execute_open_challenge(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        PickTypes::Heads,
+        STAKE,
+        STAKE_FUNDS,
+    )
+    .unwrap();
+
+    let err = execute_accept_challenge(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        0,
+        STAKE_FUNDS,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::OwnChallenge);
+
+    // The opponent pays the same stake and fee
+    let err = execute_accept_challenge(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR2),
+        0,
+        STAKE,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WrongPaidAmount);
+
+    let err = execute_accept_challenge(
+        &mut app,
+        contract_addr,
+        Addr::unchecked(FLIPPER_ADDR2),
+        1,
+        STAKE_FUNDS,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ChallengeNotFound(1));
+}
@@ -0,0 +1,331 @@
+use cosmwasm_std::{Addr, Uint128};
+use cw_multi_test::AppResponse;
+
+use crate::{
+    error::ContractError,
+    randomness::{check_proof_randomness, FlipRandomness},
+    testing::utils::{
+        executes::{
+            execute_reveal_flip, execute_start_flip_with_commitment,
+            execute_start_flips_with_commitments, sudo_commit_operator_seed,
+            sudo_reveal_operator_seed, sudo_update_max_pending_flips, sudo_update_randomness,
+        },
+        queries::{query_flip_proof, query_operator_seed},
+        setup::{setup_commit_reveal_contract, OPERATOR_SEED, PLAYER_SECRET, REVEAL_TIMEOUT},
+    },
+    types::{PickTypes, RandomnessSource},
+};
+
+use super::utils::{
+    executes::{execute_do_flips, execute_start_flip},
+    helpers::MIN_FUNDS,
+    queries::query_last_flips,
+    setup::{FLIPPER_ADDR, MIN_BET, NATIVE_DENOM},
+};
+
+fn get_flip_attr(res: &AppResponse, key: &str) -> Option<String> {
+    res.events
+        .iter()
+        .find(|event| event.ty == "wasm-flip")
+        .unwrap()
+        .attributes
+        .iter()
+        .find(|attr| attr.key == key)
+        .map(|attr| attr.value.clone())
+}
+
+#[test]
+fn test_commit_reveal_flip() {
+    let (mut app, contract_addr) = setup_commit_reveal_contract();
+
+    execute_start_flip_with_commitment(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        &sha256::digest(PLAYER_SECRET),
+    )
+    .unwrap();
+
+    // Nothing is revealed yet
+    let err = execute_do_flips(&mut app, contract_addr.clone()).unwrap_err();
+    assert_eq!(err, ContractError::NoFlipsToDoThisBlock);
+
+    // Only the flipper can reveal, and only the committed secret
+    let err = execute_reveal_flip(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked("random"),
+        0,
+        PLAYER_SECRET,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::FlipNotFound(0));
+
+    let err = execute_reveal_flip(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        0,
+        "wrong-secret",
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidReveal);
+
+    execute_reveal_flip(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        0,
+        PLAYER_SECRET,
+    )
+    .unwrap();
+
+    // Operator seed is still not revealed
+    let err = execute_do_flips(&mut app, contract_addr.clone()).unwrap_err();
+    assert_eq!(err, ContractError::NoFlipsToDoThisBlock);
+
+    let err =
+        sudo_reveal_operator_seed(&mut app, contract_addr.clone(), "wrong-seed", None).unwrap_err();
+    assert_eq!(err, ContractError::InvalidReveal);
+
+    sudo_reveal_operator_seed(&mut app, contract_addr.clone(), OPERATOR_SEED, None).unwrap();
+
+    let operator_seed = query_operator_seed(&app, contract_addr.clone(), None).unwrap();
+    assert_eq!(operator_seed.seed, Some(OPERATOR_SEED.to_string()));
+
+    let res = execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    assert_eq!(
+        get_flip_attr(&res, "player_secret"),
+        Some(PLAYER_SECRET.to_string())
+    );
+    assert_eq!(
+        get_flip_attr(&res, "operator_seed"),
+        Some(OPERATOR_SEED.to_string())
+    );
+    assert_eq!(get_flip_attr(&res, "timeout"), None);
+
+    // The proof holds both commitments, the reveals are checked against them
+    let proof = query_flip_proof(&app, contract_addr.clone(), 0).unwrap();
+    assert_eq!(proof.commitment, Some(sha256::digest(PLAYER_SECRET)));
+    assert_eq!(
+        proof.operator_commitment,
+        Some(sha256::digest(OPERATOR_SEED))
+    );
+    assert!(check_proof_randomness(&proof, None).is_empty());
+
+    // A secret that was never committed doesn't pass
+    let mut forged = proof;
+    forged.randomness = FlipRandomness::Revealed {
+        player_secret: "forged-secret".to_string(),
+        operator_seed: OPERATOR_SEED.to_string(),
+    };
+    assert_eq!(
+        check_proof_randomness(&forged, None),
+        vec!["player secret doesn't match the commitment".to_string()]
+    );
+
+    let flips = query_last_flips(&app, contract_addr).unwrap();
+    assert_eq!(flips.len(), 1);
+}
+
+#[test]
+fn test_commit_reveal_start_flip() {
+    let (mut app, contract_addr) = setup_commit_reveal_contract();
+
+    // Commitment is required
+    let err = execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MissingCommitment);
+
+    let err = execute_start_flip_with_commitment(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        PLAYER_SECRET,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidCommitment);
+
+    // Can't commit to a new seed before revealing the current one
+    let err = sudo_commit_operator_seed(
+        &mut app,
+        contract_addr.clone(),
+        &sha256::digest("next-seed"),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::OperatorSeedNotRevealed);
+
+    // No flips can be started once the operator seed is known
+    sudo_reveal_operator_seed(&mut app, contract_addr.clone(), OPERATOR_SEED, None).unwrap();
+
+    let err = execute_start_flip_with_commitment(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        &sha256::digest(PLAYER_SECRET),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoOperatorCommitment);
+
+    sudo_commit_operator_seed(
+        &mut app,
+        contract_addr.clone(),
+        &sha256::digest("next-seed"),
+    )
+    .unwrap();
+
+    execute_start_flip_with_commitment(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        &sha256::digest(PLAYER_SECRET),
+    )
+    .unwrap();
+
+    let operator_seed = query_operator_seed(&app, contract_addr.clone(), None).unwrap();
+    assert_eq!(operator_seed.commitment, sha256::digest("next-seed"));
+
+    // Can't change the randomness source while flips are waiting
+    let err =
+        sudo_update_randomness(&mut app, contract_addr, RandomnessSource::BlockHash).unwrap_err();
+    assert_eq!(err, ContractError::FlipsPending);
+}
+
+#[test]
+fn test_commit_reveal_start_flips() {
+    let (mut app, contract_addr) = setup_commit_reveal_contract();
+    sudo_update_max_pending_flips(&mut app, contract_addr.clone(), 2).unwrap();
+    let picks = vec![(PickTypes::Heads, MIN_BET), (PickTypes::Tails, MIN_BET)];
+    let secrets = [PLAYER_SECRET, "other-player-secret"];
+
+    // Every flip of the batch needs its own commitment
+    let err = execute_start_flips_with_commitments(
+        &mut app,
+        contract_addr.clone(),
+        picks.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        None,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MissingCommitment);
+
+    let err = execute_start_flips_with_commitments(
+        &mut app,
+        contract_addr.clone(),
+        picks.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        Some(vec![sha256::digest(PLAYER_SECRET)]),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WrongCommitmentsAmount);
+
+    execute_start_flips_with_commitments(
+        &mut app,
+        contract_addr.clone(),
+        picks,
+        Addr::unchecked(FLIPPER_ADDR),
+        Some(secrets.into_iter().map(sha256::digest).collect()),
+    )
+    .unwrap();
+
+    // Commitments follow the order of the picks
+    let err = execute_reveal_flip(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        0,
+        secrets[1],
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidReveal);
+
+    for (id, secret) in secrets.into_iter().enumerate() {
+        execute_reveal_flip(
+            &mut app,
+            contract_addr.clone(),
+            Addr::unchecked(FLIPPER_ADDR),
+            id as u64,
+            secret,
+        )
+        .unwrap();
+    }
+    sudo_reveal_operator_seed(&mut app, contract_addr.clone(), OPERATOR_SEED, None).unwrap();
+
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    let flips = query_last_flips(&app, contract_addr).unwrap();
+    assert_eq!(flips.len(), 2);
+}
+
+#[test]
+fn test_commit_reveal_player_timeout() {
+    let (mut app, contract_addr) = setup_commit_reveal_contract();
+
+    execute_start_flip_with_commitment(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        &sha256::digest(PLAYER_SECRET),
+    )
+    .unwrap();
+    sudo_reveal_operator_seed(&mut app, contract_addr.clone(), OPERATOR_SEED, None).unwrap();
+
+    app.update_block(|block| block.height += REVEAL_TIMEOUT);
+
+    // Player never revealed, so the flip is lost
+    let res = execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    assert_eq!(get_flip_attr(&res, "timeout"), Some("player".to_string()));
+    assert_eq!(get_flip_attr(&res, "result"), Some("lost".to_string()));
+
+    let flips = query_last_flips(&app, contract_addr).unwrap();
+    assert!(!flips[0].result);
+}
+
+#[test]
+fn test_commit_reveal_operator_timeout() {
+    let (mut app, contract_addr) = setup_commit_reveal_contract();
+
+    execute_start_flip_with_commitment(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        &sha256::digest(PLAYER_SECRET),
+    )
+    .unwrap();
+    execute_reveal_flip(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        0,
+        PLAYER_SECRET,
+    )
+    .unwrap();
+
+    let old_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+
+    app.update_block(|block| block.height += REVEAL_TIMEOUT);
+
+    // Operator never revealed, so the flip is won
+    let res = execute_do_flips(&mut app, contract_addr).unwrap();
+    assert_eq!(get_flip_attr(&res, "timeout"), Some("operator".to_string()));
+    assert_eq!(get_flip_attr(&res, "result"), Some("won".to_string()));
+
+    let new_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(
+        new_balance.amount,
+        old_balance.amount + MIN_BET * Uint128::new(2)
+    );
+}
@@ -14,12 +14,16 @@ use crate::{
 
 use super::utils::{
     executes::{
-        execute_do_flips, execute_start_flip, sudo_update_bank_limit, sudo_update_bet_limit,
-        sudo_update_fees, unwrap_execute,
+        execute_do_flips, execute_start_flip, execute_start_flip_with_client_seed,
+        execute_start_flips, sudo_update_bank_limit, sudo_update_bet_limit, sudo_update_fees,
+        sudo_update_max_pending_flips, unwrap_execute,
     },
     helpers::add_10_todo_flips,
-    queries::{query_config, query_last_flips, query_score},
-    setup::{setup_base_contract, FLIPPER_ADDR, FLIPPER_ADDR2, NATIVE_DENOM, PLUS_NANOS},
+    queries::{query_config, query_fees, query_flip_proof, query_last_flips, query_score},
+    setup::{
+        setup_base_contract, setup_with_multiple_denoms, FLIPPER_ADDR, FLIPPER_ADDR2, NATIVE_DENOM,
+        PLUS_NANOS, USDC_DENOM,
+    },
 };
 
 #[test]
@@ -29,7 +33,7 @@ fn test_do_single_flip() {
     execute_start_flip(
         &mut app,
         contract_addr.clone(),
-        PickTypes::Heads,
+        PickTypes::Tails,
         MIN_BET,
         Addr::unchecked(FLIPPER_ADDR),
         NATIVE_DENOM,
@@ -42,6 +46,7 @@ fn test_do_single_flip() {
     assert_eq!(
         flips[0],
         Flip {
+            id: 0,
             wallet: Addr::unchecked(FLIPPER_ADDR),
             amount: coin(MIN_BET.u128(), NATIVE_DENOM),
             result: false,
@@ -50,6 +55,8 @@ fn test_do_single_flip() {
                 result: false
             },
             timestamp: Timestamp::from_seconds(123456789).plus_nanos(PLUS_NANOS),
+            block: 2,
+            client_seed: None,
         }
     );
 
@@ -69,6 +76,47 @@ fn test_do_single_flip() {
     );
 }
 
+#[test]
+fn test_flip_with_client_seed() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    // Seed is too long
+    let err = execute_start_flip_with_client_seed(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        PickTypes::Tails,
+        &"a".repeat(65),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ClientSeedTooLong { max_len: 64 });
+
+    // Same flip as in test_do_single_flip, the client seed turns it into a win
+    execute_start_flip_with_client_seed(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        PickTypes::Tails,
+        "lucky",
+    )
+    .unwrap();
+    let res = execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    let flip_event = res
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-flip")
+        .unwrap();
+    assert!(flip_event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "client_seed" && attr.value == "lucky"));
+
+    let flips: Vec<Flip> = query_last_flips(&app, contract_addr).unwrap();
+    assert!(flips[0].result);
+    assert_eq!(flips[0].client_seed, Some("lucky".to_string()));
+}
+
 #[test]
 fn test_multiple_flips() {
     let (mut app, contract_addr) = setup_base_contract();
@@ -113,7 +161,7 @@ fn test_multiple_flips() {
     execute_do_flips(&mut app, contract_addr).unwrap();
 }
 
-/// only 1 active flip is allowed, so this should fail
+/// only 1 active flip is allowed by default, so this should fail
 #[test]
 fn test_2_flips_in_a_row() {
     let (mut app, contract_addr) = setup_base_contract();
@@ -140,7 +188,185 @@ fn test_2_flips_in_a_row() {
     )
     .unwrap_err();
 
-    assert_eq!(err, ContractError::AlreadyStartedFlip);
+    assert_eq!(err, ContractError::TooManyPendingFlips(1));
+}
+
+#[test]
+fn test_multiple_pending_flips() {
+    let (mut app, contract_addr) = setup_with_multiple_denoms();
+
+    let err = sudo_update_max_pending_flips(&mut app, contract_addr.clone(), 0).unwrap_err();
+    assert_eq!(err, ContractError::InvalidMaxPendingFlips);
+    sudo_update_max_pending_flips(&mut app, contract_addr.clone(), 3).unwrap();
+
+    for denom in [NATIVE_DENOM, USDC_DENOM, NATIVE_DENOM] {
+        execute_start_flip(
+            &mut app,
+            contract_addr.clone(),
+            PickTypes::Heads,
+            MIN_BET,
+            Addr::unchecked(FLIPPER_ADDR),
+            denom,
+            MIN_FUNDS,
+        )
+        .unwrap();
+    }
+
+    let err = execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::TooManyPendingFlips(3));
+
+    // All flips are resolved in the same batch
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    // Streak follows the results in the order the flips were started
+    let mut streak: Option<Streak> = None;
+    for id in 0..3 {
+        let proof = query_flip_proof(&app, contract_addr.clone(), id).unwrap();
+        match streak.as_mut() {
+            Some(streak) => streak.update(proof.result),
+            None => streak = Some(Streak::new(proof.result)),
+        }
+    }
+
+    let score = query_score(&app, contract_addr, FLIPPER_ADDR).unwrap();
+    assert_eq!(score.streak, streak.unwrap());
+}
+
+#[test]
+fn test_start_flips() {
+    let (mut app, contract_addr) = setup_base_contract();
+    sudo_update_max_pending_flips(&mut app, contract_addr.clone(), 3).unwrap();
+
+    let picks = vec![(PickTypes::Heads, MIN_BET), (PickTypes::Tails, MAX_BET)];
+    // 3.5% fee on each flip
+    let funds = MIN_FUNDS + Uint128::new(25_875_000);
+
+    let err = execute_start_flips(
+        &mut app,
+        contract_addr.clone(),
+        vec![],
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        funds,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoPicks);
+
+    // Funds must cover all the flips
+    let err = execute_start_flips(
+        &mut app,
+        contract_addr.clone(),
+        picks.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WrongPaidAmount);
+
+    // Every bet must be within the limits
+    let err = execute_start_flips(
+        &mut app,
+        contract_addr.clone(),
+        vec![
+            (PickTypes::Heads, MIN_BET),
+            (PickTypes::Tails, MIN_BET - Uint128::one()),
+        ],
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS * Uint128::new(2),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnderTheLimitBet {
+            min_limit: "5".to_string()
+        }
+    );
+
+    let res = execute_start_flips(
+        &mut app,
+        contract_addr.clone(),
+        picks.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        funds,
+    )
+    .unwrap();
+    let ids: Vec<String> = res
+        .events
+        .iter()
+        .filter(|event| event.ty == "wasm-start_flip")
+        .map(|event| {
+            event
+                .attributes
+                .iter()
+                .find(|attr| attr.key == "id")
+                .unwrap()
+                .value
+                .clone()
+        })
+        .collect();
+    assert_eq!(ids, vec!["0", "1"]);
+    assert_eq!(
+        query_fees(&app, contract_addr.clone(), NATIVE_DENOM).unwrap(),
+        funds - MIN_BET - MAX_BET
+    );
+
+    // The whole batch counts toward the pending flips cap
+    let err = execute_start_flips(
+        &mut app,
+        contract_addr.clone(),
+        picks,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        funds,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::TooManyPendingFlips(3));
+
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    for id in 0..2 {
+        query_flip_proof(&app, contract_addr.clone(), id).unwrap();
+    }
+}
+
+#[test]
+fn test_start_flips_block_limit() {
+    let (mut app, contract_addr) = setup_base_contract();
+    sudo_update_max_pending_flips(&mut app, contract_addr.clone(), 20).unwrap();
+
+    let picks = vec![(PickTypes::Heads, MIN_BET); 11];
+    let err = execute_start_flips(
+        &mut app,
+        contract_addr.clone(),
+        picks,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS * Uint128::new(11),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::BlockLimitReached);
+
+    let picks = vec![(PickTypes::Heads, MIN_BET); 10];
+    execute_start_flips(
+        &mut app,
+        contract_addr,
+        picks,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS * Uint128::new(10),
+    )
+    .unwrap();
 }
 
 #[test]
@@ -164,6 +390,8 @@ fn test_update_config_fees() {
         holders_bps: 8000,
         reserve_bps: 1000,
         flip_bps: 400,
+        jackpot_bps: 1000,
+        referral_bps: 500,
     };
 
     sudo_update_fees(&mut app, contract_addr.clone(), new_fees.clone()).unwrap();
@@ -216,6 +444,11 @@ fn test_wrong_funds() {
         &ExecuteMsg::Flip(FlipExecuteMsg::StartFlip {
             pick: PickTypes::Heads,
             amount: MIN_BET,
+            commitment: None,
+            client_seed: None,
+            use_voucher: None,
+            voucher_denom: None,
+            referrer: None,
         }),
         &[coin(MIN_FUNDS.u128(), NATIVE_DENOM), coin(1, "random")],
     ))
@@ -229,6 +462,11 @@ fn test_wrong_funds() {
         &ExecuteMsg::Flip(FlipExecuteMsg::StartFlip {
             pick: PickTypes::Heads,
             amount: MIN_BET,
+            commitment: None,
+            client_seed: None,
+            use_voucher: None,
+            voucher_denom: None,
+            referrer: None,
         }),
         &coins(MIN_FUNDS.u128(), "random"),
     ))
@@ -255,6 +493,8 @@ fn test_sudo_unauth() {
                 holders_bps: 7000,
                 reserve_bps: 1500,
                 flip_bps: 300,
+                jackpot_bps: 0,
+                referral_bps: 0,
             },
         }),
         &[],
@@ -331,7 +571,12 @@ fn test_no_flip_same_block() {
     let err = unwrap_execute(app.execute_contract(
         Addr::unchecked(FLIPPER_ADDR),
         contract_addr.clone(),
-        &ExecuteMsg::Flip(FlipExecuteMsg::DoFlips {}),
+        &ExecuteMsg::Flip(FlipExecuteMsg::DoFlips {
+            round: None,
+            signature: None,
+            house_seed: None,
+            limit: None,
+        }),
         &[],
     ))
     .unwrap_err();
@@ -353,10 +598,17 @@ fn test_missing_funds() {
 
     execute_do_flips(&mut app, contract_addr.clone()).unwrap();
 
+    // Only the fees are left
+    let balance = app
+        .wrap()
+        .query_balance(contract_addr.clone(), NATIVE_DENOM)
+        .unwrap()
+        .amount;
+    let fees = query_fees(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
     app.execute(
         contract_addr.clone(),
         cosmwasm_std::CosmosMsg::Bank(cosmwasm_std::BankMsg::Burn {
-            amount: coins(30000000000, NATIVE_DENOM),
+            amount: coins((balance - fees).u128(), NATIVE_DENOM),
         }),
     )
     .unwrap();
@@ -398,6 +650,33 @@ fn test_missing_funds_on_do_funds() {
     );
 }
 
+#[test]
+fn test_missing_funds_below_fees() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+
+    // The balance doesn't even cover the fees anymore
+    let balance = app
+        .wrap()
+        .query_balance(contract_addr.clone(), NATIVE_DENOM)
+        .unwrap()
+        .amount;
+    app.execute(
+        contract_addr.clone(),
+        cosmwasm_std::CosmosMsg::Bank(cosmwasm_std::BankMsg::Burn {
+            amount: coins(balance.u128(), NATIVE_DENOM),
+        }),
+    )
+    .unwrap();
+
+    let err = execute_do_flips(&mut app, contract_addr).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ContractMissingFunds(NATIVE_DENOM.to_string())
+    );
+}
+
 #[test]
 fn test_contract_is_paused() {
     let (mut app, contract_addr) = setup_base_contract();
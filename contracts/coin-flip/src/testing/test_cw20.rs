@@ -0,0 +1,168 @@
+use cosmwasm_std::{Addr, Uint128};
+
+use crate::{error::ContractError, helpers::cw20_denom, types::PickTypes};
+
+use super::utils::{
+    executes::{
+        execute_cw20_transfer, execute_do_flips, execute_start_cw20_flip, sudo_distribute,
+        sudo_withdraw_excess,
+    },
+    helpers::{get_dist_result, MIN_FEES, MIN_FUNDS},
+    queries::{query_cw20_balance, query_fees, query_last_flips},
+    setup::{
+        instantiate_cw20_token, setup_contract, setup_cw20_token, setup_with_cw20, FLIPPER_ADDR,
+        MIN_BANK_AMOUNT, MIN_BET, RESERVE_ADDR, TEAM_ADDR,
+    },
+};
+
+#[test]
+fn test_cw20_flip() {
+    let (mut app, contract_addr, token_addr) = setup_with_cw20();
+    let denom = cw20_denom(&token_addr);
+    let flipper_balance = query_cw20_balance(&app, token_addr.clone(), FLIPPER_ADDR).unwrap();
+
+    execute_start_cw20_flip(
+        &mut app,
+        contract_addr.clone(),
+        token_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        MIN_FUNDS,
+    )
+    .unwrap();
+
+    // The tokens are held by the contract until the flip is done
+    let contract_balance =
+        query_cw20_balance(&app, token_addr.clone(), contract_addr.as_str()).unwrap();
+    assert_eq!(contract_balance, MIN_BANK_AMOUNT + MIN_FUNDS);
+
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    let flips = query_last_flips(&app, contract_addr.clone()).unwrap();
+    assert_eq!(flips[0].amount.denom, denom);
+
+    let fees = query_fees(&app, contract_addr.clone(), &denom).unwrap();
+    assert_eq!(fees, MIN_FEES);
+
+    // A win is paid back in tokens
+    let payout = if flips[0].result {
+        MIN_BET * Uint128::new(2)
+    } else {
+        Uint128::zero()
+    };
+    let new_flipper_balance = query_cw20_balance(&app, token_addr.clone(), FLIPPER_ADDR).unwrap();
+    assert_eq!(new_flipper_balance, flipper_balance - MIN_FUNDS + payout);
+
+    let contract_balance = query_cw20_balance(&app, token_addr, contract_addr.as_str()).unwrap();
+    assert_eq!(contract_balance, MIN_BANK_AMOUNT + MIN_FUNDS - payout);
+}
+
+#[test]
+fn test_cw20_wrong_funds() {
+    let (mut app, contract_addr, token_addr) = setup_with_cw20();
+
+    // The tokens must pay the flip and its fee exactly
+    let err = execute_start_cw20_flip(
+        &mut app,
+        contract_addr.clone(),
+        token_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        MIN_BET,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WrongPaidAmount {});
+
+    // A token that was never added as a denom is rejected
+    let other_token_addr = instantiate_cw20_token(&mut app, &contract_addr);
+    let err = execute_start_cw20_flip(
+        &mut app,
+        contract_addr,
+        other_token_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        MIN_FUNDS,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WrongDenom {
+            denom: cw20_denom(&other_token_addr)
+        }
+    );
+}
+
+#[test]
+fn test_cw20_distribute() {
+    let (mut app, contract_addr) = setup_contract();
+    let token_addr = setup_cw20_token(&mut app, contract_addr.clone());
+    let denom = cw20_denom(&token_addr);
+
+    // Keep the bank above its limit even if the flip wins, so the reserve gets its fees
+    execute_cw20_transfer(
+        &mut app,
+        token_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        &contract_addr,
+        MIN_BET * Uint128::new(2),
+    )
+    .unwrap();
+
+    execute_start_cw20_flip(
+        &mut app,
+        contract_addr.clone(),
+        token_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        MIN_FUNDS,
+    )
+    .unwrap();
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    let res = sudo_distribute(&mut app, contract_addr.clone(), &denom).unwrap();
+    let res_data = get_dist_result(res);
+
+    // Fees are sent as tokens
+    let team_balance = query_cw20_balance(&app, token_addr.clone(), TEAM_ADDR).unwrap();
+    let reserve_balance = query_cw20_balance(&app, token_addr, RESERVE_ADDR).unwrap();
+    assert_eq!(team_balance, res_data.team_paid);
+    assert_eq!(reserve_balance, res_data.reserve_paid);
+
+    let fees = query_fees(&app, contract_addr, &denom).unwrap();
+    assert_eq!(
+        fees,
+        MIN_FEES - res_data.holders_paid - res_data.team_paid - res_data.reserve_paid
+    );
+}
+
+#[test]
+fn test_cw20_send_excess_funds() {
+    let (mut app, contract_addr, token_addr) = setup_with_cw20();
+    let denom = cw20_denom(&token_addr);
+
+    // The bank is at its limit
+    let err = sudo_withdraw_excess(&mut app, contract_addr.clone(), &denom).unwrap_err();
+    assert_eq!(err, ContractError::NoExcessFunds);
+
+    let excess_funds = Uint128::new(1_000_000);
+    execute_cw20_transfer(
+        &mut app,
+        token_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        &contract_addr,
+        excess_funds,
+    )
+    .unwrap();
+
+    sudo_withdraw_excess(&mut app, contract_addr.clone(), &denom).unwrap();
+
+    let reserve_balance = query_cw20_balance(&app, token_addr.clone(), RESERVE_ADDR).unwrap();
+    assert_eq!(reserve_balance, excess_funds);
+
+    let contract_balance = query_cw20_balance(&app, token_addr, contract_addr.as_str()).unwrap();
+    assert_eq!(contract_balance, MIN_BANK_AMOUNT);
+}
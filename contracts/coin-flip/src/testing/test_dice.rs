@@ -0,0 +1,144 @@
+use cosmwasm_std::{Addr, Uint128};
+
+use crate::{
+    error::ContractError,
+    randomness::{get_block_seed_at, replay_flip},
+    testing::utils::{
+        executes::{execute_do_flips, execute_start_flip, sudo_update_payout_limit},
+        queries::{query_config, query_flip_proof},
+    },
+    types::PickTypes,
+};
+
+use super::utils::{
+    helpers::MIN_FUNDS,
+    setup::{setup_base_contract, FLIPPER_ADDR, MIN_BET, NATIVE_DENOM},
+};
+
+#[test]
+fn test_roll_under_win_chance() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    for win_chance in [0, 9, 91, 100] {
+        let err = execute_start_flip(
+            &mut app,
+            contract_addr.clone(),
+            PickTypes::RollUnder { win_chance },
+            MIN_BET,
+            Addr::unchecked(FLIPPER_ADDR),
+            NATIVE_DENOM,
+            MIN_FUNDS,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidWinChance { min: 10, max: 90 });
+    }
+}
+
+#[test]
+fn test_roll_under_flip() {
+    let (mut app, contract_addr) = setup_base_contract();
+    let pick = PickTypes::RollUnder { win_chance: 25 };
+
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        pick.clone(),
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+    let old_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    // Won if the roll is under the win chance, paid 4 times the bet then
+    let proof = query_flip_proof(&app, contract_addr, 0).unwrap();
+    assert_eq!(proof.pick, pick);
+    let won = proof.outcome_random.unwrap() % 100 < 25;
+    assert_eq!(proof.result, won);
+    let payout = if won {
+        MIN_BET * Uint128::new(4)
+    } else {
+        Uint128::zero()
+    };
+    assert_eq!(proof.payout, payout);
+
+    let new_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(new_balance.amount, old_balance.amount + payout);
+
+    // Replaying the proof gives the same roll
+    let block_seed = get_block_seed_at(0, proof.block, proof.timestamp);
+    let replay = replay_flip(&proof, &block_seed);
+    assert_eq!(replay.outcome_random, proof.outcome_random);
+    assert_eq!(replay.result, won);
+}
+
+#[test]
+fn test_payout_limit() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    sudo_update_payout_limit(
+        &mut app,
+        contract_addr.clone(),
+        NATIVE_DENOM,
+        Some(MIN_BET * Uint128::new(3)),
+    )
+    .unwrap();
+    let config = query_config(&app, contract_addr.clone()).unwrap();
+    assert_eq!(
+        config.denom_limits[NATIVE_DENOM].max_payout,
+        Some(MIN_BET * Uint128::new(3))
+    );
+
+    // 4x payout is over the limit
+    let err = execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::RollUnder { win_chance: 25 },
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::OverThePayoutLimit {
+            max_limit: "15".to_string()
+        }
+    );
+
+    // 2x payout is fine
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::RollUnder { win_chance: 50 },
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    // No limit anymore
+    sudo_update_payout_limit(&mut app, contract_addr.clone(), NATIVE_DENOM, None).unwrap();
+    execute_start_flip(
+        &mut app,
+        contract_addr,
+        PickTypes::RollUnder { win_chance: 25 },
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+}
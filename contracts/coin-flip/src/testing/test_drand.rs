@@ -0,0 +1,170 @@
+use cosmwasm_std::{Addr, HexBinary, Timestamp};
+
+use crate::{
+    drand::{derive_randomness, next_round, round_time, verify_beacon},
+    error::ContractError,
+    randomness::{check_proof_randomness, FlipRandomness},
+    testing::utils::{
+        executes::execute_do_flips_with_beacon,
+        queries::query_flip_proof,
+        setup::{setup_drand_contract, DRAND_GENESIS_TIME, DRAND_PERIOD, DRAND_PUBKEY},
+    },
+    types::PickTypes,
+};
+
+use super::utils::{
+    executes::{execute_do_flips, execute_start_flip},
+    helpers::MIN_FUNDS,
+    queries::query_last_flips,
+    setup::{FLIPPER_ADDR, MIN_BET, NATIVE_DENOM},
+};
+
+const ROUND_4_SIGNATURE: &str = "9543e0fa2e6863e10d1876a8eaf08afc583fe5dfcd9e5bf2c678ff3804dfd5978d623bca499275fbdea980ab934e41a9";
+const ROUND_5_SIGNATURE: &str = "985ac5fe88d86e1b0be94719a721ecf0c1702145f63c8580d207700c6e6ddd5df5e76fdad62321832ddf352d50088fa3";
+const ROUND_5_RANDOMNESS: &str = "403756788176395fd8f78d678c351e3ee2f40a959c04cbbdd08055270622acbc";
+
+#[test]
+fn test_verify_beacon() {
+    let pubkey = HexBinary::from_hex(DRAND_PUBKEY).unwrap();
+    let signature = HexBinary::from_hex(ROUND_5_SIGNATURE).unwrap();
+
+    assert!(verify_beacon(pubkey.as_slice(), 5, signature.as_slice()));
+    // Signature of another round
+    assert!(!verify_beacon(pubkey.as_slice(), 4, signature.as_slice()));
+    // Garbage input
+    assert!(!verify_beacon(pubkey.as_slice(), 5, &[1, 2, 3]));
+    assert!(!verify_beacon(&[1, 2, 3], 5, signature.as_slice()));
+
+    assert_eq!(derive_randomness(signature.as_slice()), ROUND_5_RANDOMNESS);
+}
+
+#[test]
+fn test_drand_rounds() {
+    assert_eq!(
+        round_time(DRAND_GENESIS_TIME, DRAND_PERIOD, 1),
+        DRAND_GENESIS_TIME
+    );
+    assert_eq!(
+        round_time(DRAND_GENESIS_TIME, DRAND_PERIOD, 5),
+        DRAND_GENESIS_TIME + 12
+    );
+
+    let genesis = Timestamp::from_seconds(DRAND_GENESIS_TIME);
+    assert_eq!(
+        next_round(DRAND_GENESIS_TIME, DRAND_PERIOD, genesis.minus_seconds(1)),
+        1
+    );
+    // Round 1 is published exactly at genesis, so the next one is 2
+    assert_eq!(next_round(DRAND_GENESIS_TIME, DRAND_PERIOD, genesis), 2);
+    assert_eq!(
+        next_round(
+            DRAND_GENESIS_TIME,
+            DRAND_PERIOD,
+            genesis.plus_nanos(2_500_000_000)
+        ),
+        2
+    );
+    assert_eq!(
+        next_round(DRAND_GENESIS_TIME, DRAND_PERIOD, genesis.plus_seconds(3)),
+        3
+    );
+}
+
+#[test]
+fn test_drand_flip() {
+    let (mut app, contract_addr) = setup_drand_contract();
+
+    // Flip starts at 123456789, first round after that is round 5
+    let res = execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+    let drand_round = res
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-start_flip")
+        .unwrap()
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "drand_round")
+        .unwrap()
+        .value
+        .clone();
+    assert_eq!(drand_round, "5");
+
+    let err = execute_do_flips(&mut app, contract_addr.clone()).unwrap_err();
+    assert_eq!(err, ContractError::MissingBeacon);
+
+    // Round 5 is only published 3 seconds later
+    let err = execute_do_flips_with_beacon(&mut app, contract_addr.clone(), 5, ROUND_5_SIGNATURE)
+        .unwrap_err();
+    assert_eq!(err, ContractError::BeaconFromFuture(5));
+
+    app.update_block(|block| block.time = block.time.plus_seconds(DRAND_PERIOD));
+
+    let err = execute_do_flips_with_beacon(&mut app, contract_addr.clone(), 5, ROUND_4_SIGNATURE)
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidBeacon);
+
+    // Valid beacon, but the flip is not tied to round 4
+    let err = execute_do_flips_with_beacon(&mut app, contract_addr.clone(), 4, ROUND_4_SIGNATURE)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoFlipsToDoThisBlock);
+
+    let res = execute_do_flips_with_beacon(&mut app, contract_addr.clone(), 5, ROUND_5_SIGNATURE)
+        .unwrap();
+    let flip_event = res
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-flip")
+        .unwrap();
+    assert!(flip_event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "drand_round" && attr.value == "5"));
+    assert!(flip_event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "drand_randomness" && attr.value == ROUND_5_RANDOMNESS));
+
+    // The proof holds the beacon, its signature is checked with the drand public key
+    let pubkey = HexBinary::from_hex(DRAND_PUBKEY).unwrap();
+    let proof = query_flip_proof(&app, contract_addr.clone(), 0).unwrap();
+    assert_eq!(proof.drand_round, Some(5));
+    assert_eq!(
+        proof.signature,
+        Some(HexBinary::from_hex(ROUND_5_SIGNATURE).unwrap())
+    );
+    assert!(check_proof_randomness(&proof, Some(pubkey.as_slice())).is_empty());
+    assert_eq!(check_proof_randomness(&proof, None).len(), 1);
+
+    // The beacon can't be swapped for another round or another randomness
+    let mut forged = proof.clone();
+    forged.randomness = FlipRandomness::Drand {
+        round: 4,
+        randomness: ROUND_5_RANDOMNESS.to_string(),
+    };
+    forged.drand_round = Some(4);
+    assert_eq!(
+        check_proof_randomness(&forged, Some(pubkey.as_slice())),
+        vec!["drand signature is not valid for round 4".to_string()]
+    );
+    let mut forged = proof;
+    forged.randomness = FlipRandomness::Drand {
+        round: 5,
+        randomness: sha256::digest("forged"),
+    };
+    assert_eq!(
+        check_proof_randomness(&forged, Some(pubkey.as_slice())),
+        vec!["drand randomness doesn't match the signature".to_string()]
+    );
+
+    let flips = query_last_flips(&app, contract_addr).unwrap();
+    assert_eq!(flips.len(), 1);
+}
@@ -0,0 +1,251 @@
+use cosmwasm_std::Addr;
+
+use crate::{
+    error::ContractError,
+    randomness::FlipRandomness,
+    testing::utils::{
+        executes::{
+            execute_do_flips_with_house_seed, execute_do_flips_with_limit_and_house_seed,
+            sudo_commit_house_chain,
+        },
+        queries::{query_flip_proof, query_house_chain},
+        setup::setup_hash_chain_contract,
+    },
+    types::{HouseChain, NextHouseChain, PickTypes},
+};
+
+use super::utils::{
+    executes::{execute_do_flips, execute_start_flip},
+    helpers::MIN_FUNDS,
+    queries::query_last_flips,
+    setup::{BaseApp, FLIPPER_ADDR, FLIPPER_ADDR2, MIN_BET, NATIVE_DENOM},
+};
+
+/// Build a seed chain, the first seed is the secret and every next one is the hash of the previous
+fn build_chain(secret: &str, length: usize) -> Vec<String> {
+    let mut seed = secret.to_string();
+    let mut chain = vec![seed.clone()];
+    for _ in 0..length {
+        seed = sha256::digest(seed.as_str());
+        chain.push(seed.clone());
+    }
+    chain
+}
+
+fn start_flip(app: &mut BaseApp, contract_addr: Addr) -> Result<(), ContractError> {
+    execute_start_flip(
+        app,
+        contract_addr,
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .map(|_| ())
+}
+
+#[test]
+fn test_hash_chain_flip() {
+    let (mut app, contract_addr) = setup_hash_chain_contract();
+    let chain = build_chain("house-secret", 2);
+
+    // Can't flip before the house commits to a chain
+    let err = start_flip(&mut app, contract_addr.clone()).unwrap_err();
+    assert_eq!(err, ContractError::NoHouseChain);
+
+    let err = sudo_commit_house_chain(&mut app, contract_addr.clone(), &chain[2], 0).unwrap_err();
+    assert_eq!(err, ContractError::InvalidHouseChainLength);
+
+    sudo_commit_house_chain(&mut app, contract_addr.clone(), &chain[2], 2).unwrap();
+    assert_eq!(
+        query_house_chain(&app, contract_addr.clone()).unwrap(),
+        HouseChain {
+            head: chain[2].clone(),
+            remaining: 2,
+            revealed: 0,
+            next: None,
+        }
+    );
+
+    start_flip(&mut app, contract_addr.clone()).unwrap();
+
+    let err = execute_do_flips(&mut app, contract_addr.clone()).unwrap_err();
+    assert_eq!(err, ContractError::MissingHouseSeed);
+
+    // Seeds must be revealed in order
+    let err =
+        execute_do_flips_with_house_seed(&mut app, contract_addr.clone(), &chain[0]).unwrap_err();
+    assert_eq!(err, ContractError::InvalidHouseSeed);
+
+    let res = execute_do_flips_with_house_seed(&mut app, contract_addr.clone(), &chain[1]).unwrap();
+    let flip_event = res
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-flip")
+        .unwrap();
+    assert!(flip_event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "house_seed" && attr.value == chain[1]));
+    assert_eq!(
+        query_house_chain(&app, contract_addr.clone()).unwrap(),
+        HouseChain {
+            head: chain[1].clone(),
+            remaining: 1,
+            revealed: 1,
+            next: None,
+        }
+    );
+    assert_eq!(
+        query_last_flips(&app, contract_addr.clone()).unwrap().len(),
+        1
+    );
+}
+
+#[test]
+fn test_hash_chain_rotation() {
+    let (mut app, contract_addr) = setup_hash_chain_contract();
+    let chain = build_chain("house-secret", 1);
+    let next_chain = build_chain("next-house-secret", 3);
+
+    sudo_commit_house_chain(&mut app, contract_addr.clone(), &chain[1], 1).unwrap();
+    start_flip(&mut app, contract_addr.clone()).unwrap();
+    execute_do_flips_with_house_seed(&mut app, contract_addr.clone(), &chain[0]).unwrap();
+
+    // Chain is used up, no more flips until we rotate
+    let err = start_flip(&mut app, contract_addr.clone()).unwrap_err();
+    assert_eq!(err, ContractError::HouseChainUsedUp);
+
+    // A used up chain is replaced right away
+    sudo_commit_house_chain(&mut app, contract_addr.clone(), &next_chain[3], 3).unwrap();
+    assert_eq!(
+        query_house_chain(&app, contract_addr.clone()).unwrap(),
+        HouseChain {
+            head: next_chain[3].clone(),
+            remaining: 3,
+            revealed: 1,
+            next: None,
+        }
+    );
+
+    start_flip(&mut app, contract_addr.clone()).unwrap();
+    execute_do_flips_with_house_seed(&mut app, contract_addr.clone(), &next_chain[2]).unwrap();
+    assert_eq!(query_last_flips(&app, contract_addr).unwrap().len(), 2);
+}
+
+#[test]
+fn test_hash_chain_rotation_flips_pending() {
+    let (mut app, contract_addr) = setup_hash_chain_contract();
+    let chain = build_chain("house-secret", 1);
+    let next_chain = build_chain("next-house-secret", 1);
+
+    sudo_commit_house_chain(&mut app, contract_addr.clone(), &chain[1], 1).unwrap();
+    start_flip(&mut app, contract_addr.clone()).unwrap();
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Tails,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR2),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+
+    // The last seed only resolved one flip, the other one waits on the used up chain
+    execute_do_flips_with_limit_and_house_seed(&mut app, contract_addr.clone(), 1, &chain[0])
+        .unwrap();
+    let err =
+        sudo_commit_house_chain(&mut app, contract_addr.clone(), &next_chain[1], 1).unwrap_err();
+    assert_eq!(err, ContractError::FlipsPending);
+
+    // Its seed is revealed already, it is done without a new one
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    let proof = query_flip_proof(&app, contract_addr.clone(), 1).unwrap();
+    assert_eq!(
+        proof.randomness,
+        FlipRandomness::HouseSeed {
+            seed: chain[0].clone()
+        }
+    );
+    sudo_commit_house_chain(&mut app, contract_addr, &next_chain[1], 1).unwrap();
+}
+
+#[test]
+fn test_hash_chain_seed_tied_at_start() {
+    let (mut app, contract_addr) = setup_hash_chain_contract();
+    let chain = build_chain("house-secret", 3);
+
+    sudo_commit_house_chain(&mut app, contract_addr.clone(), &chain[3], 3).unwrap();
+    start_flip(&mut app, contract_addr.clone()).unwrap();
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Tails,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR2),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+
+    // The limit leaves a flip for later, it keeps the seed it was tied to when it started
+    execute_do_flips_with_limit_and_house_seed(&mut app, contract_addr.clone(), 1, &chain[2])
+        .unwrap();
+    start_flip(&mut app, contract_addr.clone()).unwrap();
+    execute_do_flips_with_house_seed(&mut app, contract_addr.clone(), &chain[1]).unwrap();
+
+    for (id, seed) in [(0, &chain[2]), (1, &chain[2]), (2, &chain[1])] {
+        let proof = query_flip_proof(&app, contract_addr.clone(), id).unwrap();
+        assert_eq!(
+            proof.randomness,
+            FlipRandomness::HouseSeed { seed: seed.clone() }
+        );
+    }
+}
+
+#[test]
+fn test_hash_chain_queued_rotation() {
+    let (mut app, contract_addr) = setup_hash_chain_contract();
+    let chain = build_chain("house-secret", 1);
+    let next_chain = build_chain("next-house-secret", 3);
+
+    sudo_commit_house_chain(&mut app, contract_addr.clone(), &chain[1], 1).unwrap();
+    start_flip(&mut app, contract_addr.clone()).unwrap();
+
+    // The current chain still has a seed, so the new one is queued behind it, even with flips waiting
+    sudo_commit_house_chain(&mut app, contract_addr.clone(), &next_chain[3], 3).unwrap();
+    assert_eq!(
+        query_house_chain(&app, contract_addr.clone()).unwrap(),
+        HouseChain {
+            head: chain[1].clone(),
+            remaining: 1,
+            revealed: 0,
+            next: Some(NextHouseChain {
+                head: next_chain[3].clone(),
+                length: 3,
+            }),
+        }
+    );
+
+    // The queued chain can't be revealed before the current one is used up
+    let err = execute_do_flips_with_house_seed(&mut app, contract_addr.clone(), &next_chain[2])
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidHouseSeed);
+    execute_do_flips_with_house_seed(&mut app, contract_addr.clone(), &chain[0]).unwrap();
+
+    // Flips keep going, the queued chain takes over for them
+    start_flip(&mut app, contract_addr.clone()).unwrap();
+    execute_do_flips_with_house_seed(&mut app, contract_addr.clone(), &next_chain[2]).unwrap();
+    assert_eq!(
+        query_house_chain(&app, contract_addr.clone()).unwrap(),
+        HouseChain {
+            head: next_chain[2].clone(),
+            remaining: 2,
+            revealed: 2,
+            next: None,
+        }
+    );
+    assert_eq!(query_last_flips(&app, contract_addr).unwrap().len(), 2);
+}
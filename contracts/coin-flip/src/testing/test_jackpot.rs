@@ -0,0 +1,203 @@
+use cosmwasm_std::{coin, Addr, Uint128};
+use cw_multi_test::AppResponse;
+
+use crate::types::{Fees, PickTypes};
+
+use super::utils::{
+    executes::{
+        execute_cancel_flip, execute_do_flips, execute_start_flip, sudo_distribute,
+        sudo_update_fees, sudo_update_jackpot_odds, sudo_withdraw_excess,
+    },
+    helpers::{add_balance, MIN_FEES, MIN_FUNDS},
+    queries::{query_config, query_fees, query_jackpot},
+    setup::{
+        setup_base_contract, setup_contract, BaseApp, FLIPPER_ADDR, MIN_BANK_AMOUNT, MIN_BET,
+        NATIVE_DENOM,
+    },
+};
+
+/// 10% of the flip fees go to the jackpot
+const JACKPOT_SHARE: Uint128 = Uint128::new(17500);
+
+fn setup_jackpot_contract() -> (BaseApp, Addr) {
+    let (mut app, contract_addr) = setup_base_contract();
+    set_jackpot_fees(&mut app, contract_addr.clone());
+
+    (app, contract_addr)
+}
+
+fn set_jackpot_fees(app: &mut BaseApp, contract_addr: Addr) {
+    sudo_update_fees(
+        app,
+        contract_addr,
+        Fees {
+            team_bps: 1500,
+            holders_bps: 7000,
+            reserve_bps: 1500,
+            flip_bps: 350,
+            jackpot_bps: 1000,
+            referral_bps: 0,
+        },
+    )
+    .unwrap();
+}
+
+fn do_flip(app: &mut BaseApp, contract_addr: Addr) -> AppResponse {
+    execute_start_flip(
+        app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+    execute_do_flips(app, contract_addr).unwrap()
+}
+
+#[test]
+fn test_jackpot_funded_by_fees() {
+    let (mut app, contract_addr) = setup_jackpot_contract();
+
+    // Without odds the jackpot is never drawn, it only grows
+    do_flip(&mut app, contract_addr.clone());
+    do_flip(&mut app, contract_addr.clone());
+
+    let jackpot = query_jackpot(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert_eq!(jackpot, JACKPOT_SHARE * Uint128::new(2));
+
+    let fees = query_fees(&app, contract_addr, NATIVE_DENOM).unwrap();
+    assert_eq!(fees, (MIN_FEES - JACKPOT_SHARE) * Uint128::new(2));
+}
+
+#[test]
+fn test_jackpot_won() {
+    let (mut app, contract_addr) = setup_jackpot_contract();
+    do_flip(&mut app, contract_addr.clone());
+
+    // Every rolled flip wins the jackpot, funded by its own fee too
+    sudo_update_jackpot_odds(&mut app, contract_addr.clone(), 1).unwrap();
+    let config = query_config(&app, contract_addr.clone()).unwrap();
+    assert_eq!(config.jackpot_odds, 1);
+
+    let res = do_flip(&mut app, contract_addr.clone());
+
+    let event = res
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-jackpot")
+        .unwrap();
+    let attr = |key: &str| {
+        event
+            .attributes
+            .iter()
+            .find(|attr| attr.key == key)
+            .map(|attr| attr.value.clone())
+            .unwrap()
+    };
+    assert_eq!(attr("winner"), FLIPPER_ADDR);
+    assert_eq!(
+        attr("jackpot"),
+        coin((JACKPOT_SHARE * Uint128::new(2)).u128(), NATIVE_DENOM).to_string()
+    );
+
+    let jackpot = query_jackpot(&app, contract_addr, NATIVE_DENOM).unwrap();
+    assert!(jackpot.is_zero());
+}
+
+#[test]
+fn test_jackpot_not_excess_funds() {
+    let (mut app, contract_addr) = setup_jackpot_contract();
+    add_balance(&mut app, contract_addr.clone(), 30_100_000_000);
+    do_flip(&mut app, contract_addr.clone());
+
+    let jackpot = query_jackpot(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert_eq!(jackpot, JACKPOT_SHARE);
+
+    sudo_withdraw_excess(&mut app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+
+    // The jackpot stays in the contract with the fees and the bank
+    let fees = query_fees(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    let contract_balance = app
+        .wrap()
+        .query_balance(contract_addr, NATIVE_DENOM)
+        .unwrap()
+        .amount;
+    assert_eq!(contract_balance, MIN_BANK_AMOUNT + fees + jackpot);
+}
+
+#[test]
+fn test_jackpot_funded_before_distribute() {
+    let (mut app, contract_addr) = setup_contract();
+    set_jackpot_fees(&mut app, contract_addr.clone());
+
+    // The jackpot gets its share when the fee is paid
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+    let jackpot = query_jackpot(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert_eq!(jackpot, JACKPOT_SHARE);
+    let fees = query_fees(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert_eq!(fees, MIN_FEES - JACKPOT_SHARE);
+
+    // A distribution before the flip is resolved doesn't take it
+    sudo_distribute(&mut app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    let jackpot = query_jackpot(&app, contract_addr, NATIVE_DENOM).unwrap();
+    assert_eq!(jackpot, JACKPOT_SHARE);
+}
+
+#[test]
+fn test_jackpot_share_refunded_on_cancel() {
+    let (mut app, contract_addr) = setup_jackpot_contract();
+    let old_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+
+    // Starting and cancelling flips doesn't grow the jackpot out of the bank
+    for _ in 0..3 {
+        execute_start_flip(
+            &mut app,
+            contract_addr.clone(),
+            PickTypes::Heads,
+            MIN_BET,
+            Addr::unchecked(FLIPPER_ADDR),
+            NATIVE_DENOM,
+            MIN_FUNDS,
+        )
+        .unwrap();
+        execute_cancel_flip(
+            &mut app,
+            contract_addr.clone(),
+            Addr::unchecked(FLIPPER_ADDR),
+        )
+        .unwrap();
+    }
+
+    let jackpot = query_jackpot(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert!(jackpot.is_zero());
+    let fees = query_fees(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert!(fees.is_zero());
+
+    let new_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(new_balance, old_balance);
+    let contract_balance = app
+        .wrap()
+        .query_balance(contract_addr, NATIVE_DENOM)
+        .unwrap()
+        .amount;
+    assert_eq!(contract_balance, MIN_BANK_AMOUNT);
+}
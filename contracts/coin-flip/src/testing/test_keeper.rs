@@ -0,0 +1,187 @@
+use cosmwasm_std::{coin, Addr, Uint128};
+use cw_multi_test::AppResponse;
+
+use crate::{
+    error::ContractError,
+    testing::utils::{
+        executes::{
+            execute_accept_challenge, execute_do_flips_with_limit, execute_open_challenge,
+            sudo_update_keeper_reward,
+        },
+        helpers::add_10_todo_flips,
+        queries::{query_fees, query_flip_proof, query_keeper_status},
+        setup::next_block,
+    },
+    types::{KeeperReward, PickTypes},
+};
+
+use super::utils::{
+    executes::{execute_do_flips, execute_start_flip},
+    helpers::{MIN_FEES, MIN_FUNDS},
+    setup::{setup_base_contract, BaseApp, FLIPPER_ADDR, FLIPPER_ADDR2, MIN_BET, NATIVE_DENOM},
+};
+
+fn start_flip(app: &mut BaseApp, contract_addr: Addr) {
+    execute_start_flip(
+        app,
+        contract_addr,
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR2),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+}
+
+fn get_attr(res: &AppResponse, key: &str) -> Option<String> {
+    res.events
+        .iter()
+        .filter(|event| event.ty == "wasm")
+        .flat_map(|event| event.attributes.iter())
+        .find(|attr| attr.key == key)
+        .map(|attr| attr.value.clone())
+}
+
+#[test]
+fn test_keeper_reward_bps() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    let err = sudo_update_keeper_reward(&mut app, contract_addr.clone(), KeeperReward::Bps(10_001))
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidKeeperReward);
+    sudo_update_keeper_reward(&mut app, contract_addr.clone(), KeeperReward::Bps(1000)).unwrap();
+
+    start_flip(&mut app, contract_addr.clone());
+
+    // Flips are never ready in the block they started in
+    let status = query_keeper_status(&app, contract_addr.clone()).unwrap();
+    assert_eq!(status.ready_flips, 0);
+    assert!(status.bounty.is_empty());
+
+    app.update_block(next_block);
+    let status = query_keeper_status(&app, contract_addr.clone()).unwrap();
+    assert_eq!(status.ready_flips, 1);
+    assert_eq!(status.denoms, vec![NATIVE_DENOM.to_string()]);
+
+    // 10% of the flip fee
+    let bounty = MIN_FEES.multiply_ratio(1u128, 10u128);
+    assert_eq!(status.bounty, vec![coin(bounty.u128(), NATIVE_DENOM)]);
+
+    let old_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+
+    // Keeper is the sender of DoFlips
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    let new_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(new_balance.amount, old_balance.amount + bounty);
+    assert_eq!(
+        query_fees(&app, contract_addr, NATIVE_DENOM).unwrap(),
+        MIN_FEES - bounty
+    );
+}
+
+#[test]
+fn test_keeper_reward_pvp() {
+    let (mut app, contract_addr) = setup_base_contract();
+    sudo_update_keeper_reward(&mut app, contract_addr.clone(), KeeperReward::Bps(1000)).unwrap();
+
+    execute_open_challenge(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR2),
+        PickTypes::Heads,
+        MIN_BET,
+        MIN_FUNDS,
+    )
+    .unwrap();
+    execute_accept_challenge(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        0,
+        MIN_FUNDS,
+    )
+    .unwrap();
+    app.update_block(next_block);
+
+    // Both players paid the flip fee, the bounty is 10% of the two fees
+    let fees = MIN_FEES * Uint128::new(2);
+    let bounty = fees.multiply_ratio(1u128, 10u128);
+    let status = query_keeper_status(&app, contract_addr.clone()).unwrap();
+    assert_eq!(status.bounty, vec![coin(bounty.u128(), NATIVE_DENOM)]);
+
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    assert_eq!(
+        query_fees(&app, contract_addr, NATIVE_DENOM).unwrap(),
+        fees - bounty
+    );
+}
+
+#[test]
+fn test_keeper_reward_flat() {
+    let (mut app, contract_addr) = setup_base_contract();
+    sudo_update_keeper_reward(
+        &mut app,
+        contract_addr.clone(),
+        KeeperReward::Flat(Uint128::new(1_000_000)),
+    )
+    .unwrap();
+
+    start_flip(&mut app, contract_addr.clone());
+    app.update_block(next_block);
+
+    // Flat reward is capped to the flip fee
+    let status = query_keeper_status(&app, contract_addr.clone()).unwrap();
+    assert_eq!(status.bounty, vec![coin(MIN_FEES.u128(), NATIVE_DENOM)]);
+
+    let res = execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    let keeper_event = res
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-keeper_reward")
+        .unwrap();
+    assert!(keeper_event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "keeper" && attr.value == FLIPPER_ADDR));
+    assert_eq!(
+        query_fees(&app, contract_addr, NATIVE_DENOM).unwrap(),
+        Uint128::zero()
+    );
+}
+
+#[test]
+fn test_do_flips_limit() {
+    let (mut app, contract_addr) = setup_base_contract();
+    add_10_todo_flips(&mut app, contract_addr.clone());
+
+    let err = execute_do_flips_with_limit(&mut app, contract_addr.clone(), 0).unwrap_err();
+    assert_eq!(err, ContractError::InvalidDoFlipsLimit);
+
+    // Oldest flips are done first, the rest stays in the queue.
+    // Only the next batch is counted, so the count stays bounded too
+    let res = execute_do_flips_with_limit(&mut app, contract_addr.clone(), 4).unwrap();
+    assert_eq!(get_attr(&res, "ready_flips_left"), Some("4".to_string()));
+    for id in 0..4 {
+        query_flip_proof(&app, contract_addr.clone(), id).unwrap();
+    }
+    query_flip_proof(&app, contract_addr.clone(), 4).unwrap_err();
+
+    let status = query_keeper_status(&app, contract_addr.clone()).unwrap();
+    assert_eq!(status.ready_flips, 6);
+
+    // A limit above the queue does every ready flip
+    let res = execute_do_flips_with_limit(&mut app, contract_addr.clone(), 20).unwrap();
+    assert_eq!(get_attr(&res, "ready_flips_left"), Some("0".to_string()));
+    query_flip_proof(&app, contract_addr.clone(), 9).unwrap();
+
+    let err = execute_do_flips_with_limit(&mut app, contract_addr, 1).unwrap_err();
+    assert_eq!(err, ContractError::NoFlipsToDo);
+}
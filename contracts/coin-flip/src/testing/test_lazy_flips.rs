@@ -0,0 +1,189 @@
+use cosmwasm_std::{coins, Addr, BankMsg, CosmosMsg, Uint128};
+use cw_multi_test::{AppResponse, Executor};
+
+use crate::{
+    testing::utils::{
+        helpers::add_10_todo_flips,
+        queries::{query_flip_proof, query_keeper_status, query_last_flips},
+        setup::next_block,
+    },
+    types::PickTypes,
+};
+
+use super::utils::{
+    executes::{execute_open_challenge, execute_start_flip},
+    helpers::MIN_FUNDS,
+    setup::{setup_base_contract, FLIPPER_ADDR, FLIPPER_ADDR2, MIN_BET, NATIVE_DENOM},
+};
+
+fn get_attr(res: &AppResponse, ty: &str, key: &str) -> Option<String> {
+    res.events
+        .iter()
+        .filter(|event| event.ty == ty)
+        .flat_map(|event| event.attributes.iter())
+        .find(|attr| attr.key == key)
+        .map(|attr| attr.value.clone())
+}
+
+#[test]
+fn test_lazy_flips_on_start_flip() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR2),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+    app.update_block(next_block);
+
+    // Another player's flip resolves the ready one, no keeper needed
+    let res = execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+    assert_eq!(get_attr(&res, "wasm", "lazy_flips"), Some("1".to_string()));
+    assert_eq!(
+        get_attr(&res, "wasm-flip", "flipper"),
+        Some(FLIPPER_ADDR2.to_string())
+    );
+    assert_eq!(
+        get_attr(&res, "wasm-start_flip", "id"),
+        Some("1".to_string())
+    );
+
+    let proof = query_flip_proof(&app, contract_addr.clone(), 0).unwrap();
+    assert_eq!(proof.wallet, Addr::unchecked(FLIPPER_ADDR2));
+    assert_eq!(
+        query_last_flips(&app, contract_addr.clone()).unwrap().len(),
+        1
+    );
+
+    // The new flip waits for the next block
+    let status = query_keeper_status(&app, contract_addr).unwrap();
+    assert_eq!(status.ready_flips, 0);
+}
+
+#[test]
+fn test_lazy_flips_own_pending_flip() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+    app.update_block(next_block);
+
+    // The ready flip of the flipper is resolved first, it doesn't count as pending anymore
+    let res = execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+    assert_eq!(get_attr(&res, "wasm", "lazy_flips"), Some("1".to_string()));
+    assert_eq!(
+        get_attr(&res, "wasm-start_flip", "id"),
+        Some("1".to_string())
+    );
+
+    let proof = query_flip_proof(&app, contract_addr.clone(), 0).unwrap();
+    assert_eq!(proof.wallet, Addr::unchecked(FLIPPER_ADDR));
+    assert_eq!(query_last_flips(&app, contract_addr).unwrap().len(), 1);
+}
+
+#[test]
+fn test_lazy_flips_limit() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    app.update_block(next_block);
+
+    let res = execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+    assert_eq!(get_attr(&res, "wasm", "lazy_flips"), Some("5".to_string()));
+
+    // Oldest flips are resolved first, the rest is left for the keeper
+    for id in 0..5 {
+        query_flip_proof(&app, contract_addr.clone(), id).unwrap();
+    }
+    query_flip_proof(&app, contract_addr.clone(), 5).unwrap_err();
+
+    let status = query_keeper_status(&app, contract_addr).unwrap();
+    assert_eq!(status.ready_flips, 5);
+}
+
+#[test]
+fn test_lazy_flips_failing() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR2),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+    app.update_block(next_block);
+
+    // The house can't pay the ready flip anymore
+    app.execute(
+        contract_addr.clone(),
+        CosmosMsg::Bank(BankMsg::Burn {
+            amount: coins(30_000_000_000, NATIVE_DENOM),
+        }),
+    )
+    .unwrap();
+
+    // The resolution the bank can't pay doesn't block the execute of another player
+    let res = execute_open_challenge(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        PickTypes::Heads,
+        Uint128::new(100_000_000),
+        Uint128::new(103_500_000),
+    )
+    .unwrap();
+    assert_eq!(
+        get_attr(&res, "wasm", "lazy_flips_skipped"),
+        Some("bank".to_string())
+    );
+    assert_eq!(get_attr(&res, "wasm", "lazy_flips"), None);
+
+    // The flip is left to the keeper
+    query_flip_proof(&app, contract_addr.clone(), 0).unwrap_err();
+    let status = query_keeper_status(&app, contract_addr).unwrap();
+    assert_eq!(status.ready_flips, 1);
+}
@@ -0,0 +1,313 @@
+use cosmwasm_std::{coin, Addr, Uint128};
+
+use crate::{
+    error::ContractError,
+    types::{EpochLength, LeaderboardConfig, LeaderboardPrize, PickTypes, Standing},
+};
+
+use super::utils::{
+    executes::{
+        execute_do_flips, execute_finalize_epoch, execute_start_flip, sudo_distribute,
+        sudo_update_leaderboard,
+    },
+    helpers::{add_10_todo_flips, add_balance, MIN_FUNDS},
+    queries::{query_epoch_score, query_leaderboard, query_prize_pool},
+    setup::{
+        setup_base_contract, setup_contract, BaseApp, FLIPPER_ADDR, FLIPPER_ADDR2, MIN_BET,
+        NATIVE_DENOM, RESERVE_ADDR,
+    },
+};
+
+const DAY: u64 = 86_400;
+
+fn daily_leaderboard(prizes: Vec<LeaderboardPrize>) -> LeaderboardConfig {
+    LeaderboardConfig {
+        epoch_length: EpochLength::Daily,
+        denom: NATIVE_DENOM.to_string(),
+        prizes,
+    }
+}
+
+/// Flip a multiple of the min bet and do it
+fn do_flip(app: &mut BaseApp, contract_addr: Addr, flipper: &str, bets: u128) {
+    execute_start_flip(
+        app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET * Uint128::new(bets),
+        Addr::unchecked(flipper),
+        NATIVE_DENOM,
+        MIN_FUNDS * Uint128::new(bets),
+    )
+    .unwrap();
+    execute_do_flips(app, contract_addr).unwrap();
+}
+
+#[test]
+fn test_leaderboard_standings() {
+    let (mut app, contract_addr) = setup_base_contract();
+    sudo_update_leaderboard(
+        &mut app,
+        contract_addr.clone(),
+        Some(daily_leaderboard(vec![])),
+    )
+    .unwrap();
+
+    do_flip(&mut app, contract_addr.clone(), FLIPPER_ADDR, 1);
+    do_flip(&mut app, contract_addr.clone(), FLIPPER_ADDR2, 3);
+    do_flip(&mut app, contract_addr.clone(), FLIPPER_ADDR, 1);
+
+    let leaderboard = query_leaderboard(&app, contract_addr.clone(), None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(leaderboard.epoch, 0);
+    assert!(!leaderboard.finalized);
+    assert_eq!(
+        leaderboard.volume,
+        vec![
+            Standing {
+                wallet: Addr::unchecked(FLIPPER_ADDR2),
+                value: MIN_BET * Uint128::new(3),
+            },
+            Standing {
+                wallet: Addr::unchecked(FLIPPER_ADDR),
+                value: MIN_BET * Uint128::new(2),
+            },
+        ]
+    );
+
+    // The other boards follow the epoch scores, whatever the results were
+    for wallet in [FLIPPER_ADDR, FLIPPER_ADDR2] {
+        let score = query_epoch_score(&app, contract_addr.clone(), wallet, None).unwrap();
+        let standing = |board: &Vec<Standing>| {
+            board
+                .iter()
+                .find(|standing| standing.wallet == wallet)
+                .map(|standing| standing.value)
+                .unwrap_or_default()
+        };
+        assert_eq!(
+            standing(&leaderboard.win_streak),
+            Uint128::from(score.best_streak)
+        );
+        assert_eq!(standing(&leaderboard.biggest_win), score.biggest_win);
+    }
+}
+
+#[test]
+fn test_leaderboard_next_epoch() {
+    let (mut app, contract_addr) = setup_base_contract();
+    sudo_update_leaderboard(
+        &mut app,
+        contract_addr.clone(),
+        Some(daily_leaderboard(vec![])),
+    )
+    .unwrap();
+
+    do_flip(&mut app, contract_addr.clone(), FLIPPER_ADDR, 1);
+    let first_leaderboard = query_leaderboard(&app, contract_addr.clone(), None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(first_leaderboard.end.seconds() % DAY, 0);
+
+    // The next epoch only starts with a flip
+    app.update_block(|block| block.time = block.time.plus_seconds(DAY));
+    let leaderboard = query_leaderboard(&app, contract_addr.clone(), None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(leaderboard.epoch, 0);
+
+    do_flip(&mut app, contract_addr.clone(), FLIPPER_ADDR2, 1);
+    let leaderboard = query_leaderboard(&app, contract_addr.clone(), None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(leaderboard.epoch, 1);
+    assert_eq!(leaderboard.start, first_leaderboard.end);
+    assert_eq!(
+        leaderboard.volume,
+        vec![Standing {
+            wallet: Addr::unchecked(FLIPPER_ADDR2),
+            value: MIN_BET,
+        }]
+    );
+
+    // Past epochs keep their standings
+    let past_leaderboard = query_leaderboard(&app, contract_addr.clone(), Some(0))
+        .unwrap()
+        .unwrap();
+    assert_eq!(past_leaderboard, first_leaderboard);
+    let score = query_epoch_score(&app, contract_addr.clone(), FLIPPER_ADDR, Some(0)).unwrap();
+    assert_eq!(score.volume, MIN_BET);
+    let score = query_epoch_score(&app, contract_addr, FLIPPER_ADDR, None).unwrap();
+    assert!(score.volume.is_zero());
+}
+
+#[test]
+fn test_finalize_epoch() {
+    let (mut app, contract_addr) = setup_contract();
+    // Keep the bank above its limit whatever the flips pay out, so the reserve share is paid
+    add_balance(&mut app, contract_addr.clone(), 30_100_000_000);
+    sudo_update_leaderboard(
+        &mut app,
+        contract_addr.clone(),
+        Some(daily_leaderboard(vec![
+            LeaderboardPrize::Coin(coin(50_000, NATIVE_DENOM)),
+            LeaderboardPrize::Nft,
+        ])),
+    )
+    .unwrap();
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    // The reserve share fills the prize pool with the coin prizes of every board
+    sudo_distribute(&mut app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    let prize_pool = query_prize_pool(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert_eq!(prize_pool, Uint128::new(150_000));
+
+    let err = execute_finalize_epoch(&mut app, contract_addr.clone(), 0).unwrap_err();
+    assert_eq!(err, ContractError::EpochNotOver(0));
+
+    app.update_block(|block| block.time = block.time.plus_seconds(DAY));
+    let res = execute_finalize_epoch(&mut app, contract_addr.clone(), 0).unwrap();
+
+    let leaderboard = query_leaderboard(&app, contract_addr.clone(), Some(0))
+        .unwrap()
+        .unwrap();
+    assert!(leaderboard.finalized);
+
+    // The first rank of every board gets the coin, the NFT pool is empty so the second rank gets nothing
+    let winners = [
+        &leaderboard.volume,
+        &leaderboard.win_streak,
+        &leaderboard.biggest_win,
+    ]
+    .into_iter()
+    .filter_map(|board| board.first())
+    .map(|standing| standing.wallet.to_string())
+    .collect::<Vec<_>>();
+    let paid_winners = res
+        .events
+        .iter()
+        .filter(|event| event.ty == "wasm-leaderboard_prize")
+        .map(|event| {
+            event
+                .attributes
+                .iter()
+                .find(|attr| attr.key == "winner")
+                .unwrap()
+                .value
+                .clone()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(paid_winners, winners);
+
+    let prize_pool = query_prize_pool(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert_eq!(
+        prize_pool,
+        Uint128::new(150_000) - Uint128::new(50_000) * Uint128::from(winners.len() as u128)
+    );
+
+    let err = execute_finalize_epoch(&mut app, contract_addr, 0).unwrap_err();
+    assert_eq!(err, ContractError::EpochFinalized(0));
+}
+
+#[test]
+fn test_prize_pool_released() {
+    let (mut app, contract_addr) = setup_contract();
+    add_balance(&mut app, contract_addr.clone(), 30_100_000_000);
+    sudo_update_leaderboard(
+        &mut app,
+        contract_addr.clone(),
+        Some(daily_leaderboard(vec![LeaderboardPrize::Coin(coin(
+            50_000,
+            NATIVE_DENOM,
+        ))])),
+    )
+    .unwrap();
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    sudo_distribute(&mut app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    let prize_pool = query_prize_pool(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert_eq!(prize_pool, Uint128::new(150_000));
+
+    // A new config still paying prizes in the denom keeps its pool
+    sudo_update_leaderboard(
+        &mut app,
+        contract_addr.clone(),
+        Some(daily_leaderboard(vec![LeaderboardPrize::Coin(coin(
+            10_000,
+            NATIVE_DENOM,
+        ))])),
+    )
+    .unwrap();
+    let prize_pool = query_prize_pool(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert_eq!(prize_pool, Uint128::new(150_000));
+
+    // Without a leaderboard the pool goes back to the reserve
+    let reserve_balance = app
+        .wrap()
+        .query_balance(RESERVE_ADDR, NATIVE_DENOM)
+        .unwrap()
+        .amount;
+    sudo_update_leaderboard(&mut app, contract_addr.clone(), None).unwrap();
+    let prize_pool = query_prize_pool(&app, contract_addr, NATIVE_DENOM).unwrap();
+    assert!(prize_pool.is_zero());
+    assert_eq!(
+        app.wrap()
+            .query_balance(RESERVE_ADDR, NATIVE_DENOM)
+            .unwrap()
+            .amount,
+        reserve_balance + Uint128::new(150_000)
+    );
+}
+
+#[test]
+fn test_invalid_leaderboard() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    let err = execute_finalize_epoch(&mut app, contract_addr.clone(), 0).unwrap_err();
+    assert_eq!(err, ContractError::NoLeaderboard);
+
+    let too_many_prizes = vec![LeaderboardPrize::Nft; 11];
+    let err = sudo_update_leaderboard(
+        &mut app,
+        contract_addr.clone(),
+        Some(daily_leaderboard(too_many_prizes)),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidLeaderboard);
+
+    let err = sudo_update_leaderboard(
+        &mut app,
+        contract_addr.clone(),
+        Some(LeaderboardConfig {
+            epoch_length: EpochLength::Weekly,
+            denom: "random".to_string(),
+            prizes: vec![],
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidLeaderboard);
+
+    let err = sudo_update_leaderboard(
+        &mut app,
+        contract_addr.clone(),
+        Some(daily_leaderboard(vec![LeaderboardPrize::Coin(coin(
+            0,
+            NATIVE_DENOM,
+        ))])),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidLeaderboard);
+
+    sudo_update_leaderboard(
+        &mut app,
+        contract_addr.clone(),
+        Some(daily_leaderboard(vec![])),
+    )
+    .unwrap();
+    let err = execute_finalize_epoch(&mut app, contract_addr, 5).unwrap_err();
+    assert_eq!(err, ContractError::EpochNotFound(5));
+}
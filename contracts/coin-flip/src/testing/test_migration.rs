@@ -1,211 +1,157 @@
-use std::collections::{HashMap, HashSet};
-
-use coin_flip_v07 as ccf07;
 use cosmwasm_std::{
-    testing::{mock_dependencies, mock_env, mock_info},
-    Addr, Uint128,
+    coin,
+    testing::{mock_dependencies, mock_env},
+    Addr, Order, Storage, Timestamp, Uint128,
 };
-use sg_std::NATIVE_DENOM;
 
 use crate::{
     contract::migrate,
-    error::ContractError,
-    state::{ALLOWED_SEND_NFT, CONFIG, FEES, STREAK_REWARDS},
-    testing::utils::setup::{MAX_BET, MIN_BANK_AMOUNT, MIN_BET, USDC_DENOM},
-    types::{Config, DenomLimit, Fees, StreakReward, Wallets},
+    state::{flips, todo_flips, v08, FLIP_ID},
+    testing::utils::setup::{MIN_BET, NATIVE_DENOM},
+    types::{Flip, PickTypes, Streak, TodoFlip},
 };
 
-use super::utils::setup::{CREATOR_ADDR, RESERVE_ADDR, TEAM_ADDR};
+use super::utils::setup::{FLIPPER_ADDR, FLIPPER_ADDR2};
 
 #[test]
-fn test_07_to_08() {
+fn test_08_flips_to_maps() {
     let mut deps = mock_dependencies();
-    let info = mock_info(CREATOR_ADDR, &[]);
-
-    ccf07::contract::instantiate(
-        deps.as_mut(),
-        mock_env(),
-        info,
-        ccf07::msg::InstantiateMsg {
-            admin: CREATOR_ADDR.into(),
-            denoms: vec![NATIVE_DENOM.into()],
-            wallets: ccf07::types::Wallets {
-                team: TEAM_ADDR.into(),
-                reserve: RESERVE_ADDR.into(),
-            },
-            fees: ccf07::types::Fees {
-                team_bps: 1500,
-                holders_bps: 7000,
-                reserve_bps: 1500,
-                flip_bps: 350,
-            },
-            bank_limit: None,
-            min_bet_limit: None,
-            max_bet_limit: None,
-            flips_per_block_limit: None,
-            sg721_addr: None,
-        },
-    )
-    .unwrap();
 
-    let old_config = ccf07::state::CONFIG.load(deps.as_ref().storage).unwrap();
+    // Storage as written by v0.8, flip 2 is waiting and the last flips have no id
+    deps.storage.set(
+        b"last_flips",
+        br#"[{"wallet":"some_flipper","amount":{"denom":"ustars","amount":"5000000"},"result":false,"streak":{"amount":1,"result":false},"timestamp":"5000000000"},{"wallet":"some_flipper2","amount":{"denom":"ustars","amount":"5000000"},"result":true,"streak":{"amount":2,"result":true},"timestamp":"6000000000"}]"#,
+    );
+    deps.storage.set(
+        b"todo_flips",
+        br#"[{"id":2,"wallet":"some_flipper","amount":{"denom":"ustars","amount":"5000000"},"pick":"tails","block":7,"timestamp":"7000000000"}]"#,
+    );
+    deps.storage.set(b"flip_id", b"2");
 
     migrate(
         deps.as_mut(),
         mock_env(),
-        crate::msg::MigrateMsg::FromV07 {
-            nft_pool_max: 4,
-            streak_nft_winning_amount: 5,
-            streak_rewards: vec![
-                StreakReward::new(2, Uint128::new(200000)),
-                StreakReward::new(4, Uint128::new(400000)),
-                StreakReward::new(5, Uint128::new(500000)),
-            ],
-            allowed_to_send_nft: vec![TEAM_ADDR.into(), CREATOR_ADDR.into()],
-        },
+        crate::msg::MigrateMsg::FromV08 {},
     )
     .unwrap();
 
-    let new_config = CONFIG.load(deps.as_ref().storage).unwrap();
-    let mut new_denom_limits: HashMap<String, DenomLimit> = HashMap::new();
-
-    new_denom_limits.insert(
-        NATIVE_DENOM.to_string(),
-        DenomLimit {
-            min: MIN_BET,
-            max: MAX_BET,
-            bank: MIN_BANK_AMOUNT,
-        },
-    );
+    assert!(deps.storage.get(b"last_flips").is_none());
+    assert!(deps.storage.get(b"todo_flips").is_none());
 
+    let amount = coin(5_000_000, "ustars");
     assert_eq!(
-        new_config,
-        Config {
-            admin: old_config.admin,
-            denoms: HashSet::from_iter(old_config.denoms),
-            denom_limits: new_denom_limits,
-            flips_per_block_limit: old_config.flips_per_block_limit,
-            wallets: Wallets {
-                team: old_config.wallets.team,
-                reserve: old_config.wallets.reserve
-            },
-            fees: Fees {
-                team_bps: old_config.fees.team_bps,
-                holders_bps: old_config.fees.holders_bps,
-                reserve_bps: old_config.fees.reserve_bps,
-                flip_bps: old_config.fees.flip_bps
-            },
-            sg721_addr: old_config.sg721_addr,
-            is_paused: old_config.is_paused,
-            nft_pool_max: 4,
-            streak_nft_winning_amount: 5,
+        todo_flips().load(deps.as_ref().storage, 2).unwrap(),
+        TodoFlip {
+            id: 2,
+            wallet: Addr::unchecked(FLIPPER_ADDR),
+            amount: amount.clone(),
+            pick: PickTypes::Tails,
+            block: 7,
+            timestamp: Timestamp::from_seconds(7),
+            commit: None,
+            drand_round: None,
+            nois_job_id: None,
+            house_seed_index: None,
+            fee: Uint128::zero(),
+            jackpot_share: Uint128::zero(),
+            referral_shares: vec![],
+            client_seed: None,
+            opponent: None,
+            auto_bet: false,
+            voucher: false,
         }
     );
+    let wallet_todo_flips = todo_flips()
+        .idx
+        .wallet
+        .prefix(Addr::unchecked(FLIPPER_ADDR))
+        .keys(deps.as_ref().storage, None, None, Order::Ascending)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(wallet_todo_flips, vec![2]);
 
-    let streak_rewards = STREAK_REWARDS.load(deps.as_ref().storage).unwrap();
+    let new_flips = flips()
+        .range(deps.as_ref().storage, None, None, Order::Ascending)
+        .map(|item| item.unwrap().1)
+        .collect::<Vec<_>>();
     assert_eq!(
-        streak_rewards,
+        new_flips,
         vec![
-            StreakReward::new(2, Uint128::new(200000)),
-            StreakReward::new(4, Uint128::new(400000)),
-            StreakReward::new(5, Uint128::new(500000)),
+            Flip {
+                id: 3,
+                wallet: Addr::unchecked(FLIPPER_ADDR),
+                amount: amount.clone(),
+                result: false,
+                streak: Streak {
+                    amount: 1,
+                    result: false,
+                },
+                timestamp: Timestamp::from_seconds(5),
+                block: 0,
+                client_seed: None,
+            },
+            Flip {
+                id: 4,
+                wallet: Addr::unchecked(FLIPPER_ADDR2),
+                amount,
+                result: true,
+                streak: Streak {
+                    amount: 2,
+                    result: true,
+                },
+                timestamp: Timestamp::from_seconds(6),
+                block: 0,
+                client_seed: None,
+            },
         ]
     );
-
-    let allowed_to_send_nft = ALLOWED_SEND_NFT.load(deps.as_ref().storage).unwrap();
-    assert_eq!(
-        allowed_to_send_nft,
-        vec![Addr::unchecked(TEAM_ADDR), Addr::unchecked(CREATOR_ADDR)]
-    );
-
-    let fees = FEES
-        .load(deps.as_ref().storage, NATIVE_DENOM.to_string())
-        .unwrap();
-    assert!(fees.is_zero());
-
-    // Should error because usdc fees doesn't exists
-    FEES.load(deps.as_ref().storage, USDC_DENOM.to_string())
-        .unwrap_err();
+    assert_eq!(FLIP_ID.load(deps.as_ref().storage).unwrap(), 4);
 }
 
 #[test]
-fn test_07_to_08_failing() {
+fn test_08_flips_new_ids() {
     let mut deps = mock_dependencies();
-    let info = mock_info(CREATOR_ADDR, &[]);
-
-    ccf07::contract::instantiate(
-        deps.as_mut(),
-        mock_env(),
-        info,
-        ccf07::msg::InstantiateMsg {
-            admin: CREATOR_ADDR.into(),
-            denoms: vec![NATIVE_DENOM.into()],
-            wallets: ccf07::types::Wallets {
-                team: TEAM_ADDR.into(),
-                reserve: RESERVE_ADDR.into(),
-            },
-            fees: ccf07::types::Fees {
-                team_bps: 1500,
-                holders_bps: 7000,
-                reserve_bps: 1500,
-                flip_bps: 350,
+    let amount = coin(MIN_BET.u128(), NATIVE_DENOM);
+
+    // v0.8 doesn't keep the id of the last flips, every one gets a new id
+    let old_flips = (1..=5)
+        .map(|seconds| v08::Flip {
+            wallet: Addr::unchecked(FLIPPER_ADDR),
+            amount: amount.clone(),
+            result: seconds % 2 == 0,
+            streak: Streak {
+                amount: 1,
+                result: seconds % 2 == 0,
             },
-            bank_limit: None,
-            min_bet_limit: None,
-            max_bet_limit: None,
-            flips_per_block_limit: None,
-            sg721_addr: None,
-        },
-    )
-    .unwrap();
+            timestamp: Timestamp::from_seconds(seconds),
+        })
+        .collect::<Vec<_>>();
+    v08::FLIPS.save(deps.as_mut().storage, &old_flips).unwrap();
+    FLIP_ID.save(deps.as_mut().storage, &9).unwrap();
 
-    let err = migrate(
+    migrate(
         deps.as_mut(),
         mock_env(),
-        crate::msg::MigrateMsg::FromV07 {
-            nft_pool_max: 4,
-            streak_nft_winning_amount: 5,
-            streak_rewards: vec![
-                StreakReward::new(2, Uint128::new(200000)),
-                StreakReward::new(4, Uint128::new(400000)),
-                StreakReward::new(5, Uint128::new(500000)),
-            ],
-            allowed_to_send_nft: vec![],
-        },
+        crate::msg::MigrateMsg::FromV08 {},
     )
-    .unwrap_err();
-    assert_eq!(err, ContractError::EmptyAllowedToSendNft);
+    .unwrap();
 
-    let err = migrate(
-        deps.as_mut(),
-        mock_env(),
-        crate::msg::MigrateMsg::FromV07 {
-            nft_pool_max: 4,
-            streak_nft_winning_amount: 5,
-            streak_rewards: vec![
-                StreakReward::new(2, Uint128::new(200000)),
-                StreakReward::new(4, Uint128::new(400000)),
-            ],
-            allowed_to_send_nft: vec![TEAM_ADDR.into(), CREATOR_ADDR.into()],
-        },
-    )
-    .unwrap_err();
-    assert_eq!(err, ContractError::LowStreakAmount);
+    let new_flips = flips()
+        .range(deps.as_ref().storage, None, None, Order::Ascending)
+        .map(|item| item.unwrap().1)
+        .collect::<Vec<_>>();
+    assert_eq!(new_flips.len(), 5);
+    for (new_flip, old_flip) in new_flips.iter().zip(old_flips) {
+        assert_eq!(new_flip.wallet, old_flip.wallet);
+        assert_eq!(new_flip.result, old_flip.result);
+        assert_eq!(new_flip.timestamp, old_flip.timestamp);
+        assert_eq!(new_flip.block, 0);
+    }
+    assert_eq!(
+        new_flips.iter().map(|flip| flip.id).collect::<Vec<_>>(),
+        vec![10, 11, 12, 13, 14]
+    );
 
-    let err = migrate(
-        deps.as_mut(),
-        mock_env(),
-        crate::msg::MigrateMsg::FromV07 {
-            nft_pool_max: 4,
-            streak_nft_winning_amount: 6,
-            streak_rewards: vec![
-                StreakReward::new(2, Uint128::new(200000)),
-                StreakReward::new(4, Uint128::new(400000)),
-                StreakReward::new(5, Uint128::new(500000)),
-            ],
-            allowed_to_send_nft: vec![TEAM_ADDR.into(), CREATOR_ADDR.into()],
-        },
-    )
-    .unwrap_err();
-    assert_eq!(err, ContractError::NftWinNotMatchLastStreakReward);
+    // New flips continue after the migrated ones
+    assert_eq!(FLIP_ID.load(deps.as_ref().storage).unwrap(), 14);
 }
@@ -33,6 +33,7 @@ fn test_update_denoms() {
             min: MIN_BET,
             max: MIN_BET,
             bank: MIN_BANK_AMOUNT,
+            max_payout: None,
         },
     )
     .unwrap();
@@ -142,6 +143,7 @@ fn test_add_existing_denom() {
             min: MIN_BET,
             max: MIN_BET,
             bank: MIN_BANK_AMOUNT,
+            max_payout: None,
         },
     )
     .unwrap_err();
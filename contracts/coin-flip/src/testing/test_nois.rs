@@ -0,0 +1,154 @@
+use cosmwasm_std::{Addr, Uint128};
+
+use crate::{
+    error::ContractError,
+    testing::utils::{
+        executes::execute_nois_callback,
+        queries::{query_fees, query_keeper_status},
+        setup::{setup_nois_contract, NOIS_TIMEOUT},
+    },
+    types::PickTypes,
+};
+
+use super::utils::{
+    executes::{execute_do_flips, execute_start_flip},
+    helpers::MIN_FUNDS,
+    queries::query_last_flips,
+    setup::{FLIPPER_ADDR, MIN_BET, NATIVE_DENOM},
+};
+
+const NOIS_RANDOMNESS: &str = "403756788176395fd8f78d678c351e3ee2f40a959c04cbbdd08055270622acbc";
+
+#[test]
+fn test_nois_flip() {
+    let (mut app, contract_addr, proxy_addr) = setup_nois_contract();
+
+    let res = execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+
+    // Randomness was requested from the proxy
+    assert!(res.events.iter().any(|event| event.ty == "execute"
+        && event
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "_contract_address" && attr.value == proxy_addr.as_str())));
+
+    // Flip is only resolved by the proxy callback
+    let err = execute_do_flips(&mut app, contract_addr.clone()).unwrap_err();
+    assert_eq!(err, ContractError::NoFlipsToDoThisBlock);
+    assert_eq!(
+        query_keeper_status(&app, contract_addr.clone())
+            .unwrap()
+            .ready_flips,
+        0
+    );
+
+    // Only the proxy can call us back
+    let err = execute_nois_callback(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        "flip-0",
+        NOIS_RANDOMNESS,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+
+    let err = execute_nois_callback(
+        &mut app,
+        contract_addr.clone(),
+        proxy_addr.clone(),
+        "flip-1",
+        NOIS_RANDOMNESS,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoisJobNotFound("flip-1".to_string()));
+
+    let res = execute_nois_callback(
+        &mut app,
+        contract_addr.clone(),
+        proxy_addr,
+        "flip-0",
+        NOIS_RANDOMNESS,
+    )
+    .unwrap();
+    let flip_event = res
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-flip")
+        .unwrap();
+    assert!(flip_event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "nois_job_id" && attr.value == "flip-0"));
+    assert!(flip_event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "nois_randomness" && attr.value == NOIS_RANDOMNESS));
+
+    let flips = query_last_flips(&app, contract_addr).unwrap();
+    assert_eq!(flips.len(), 1);
+}
+
+#[test]
+fn test_nois_timeout_refund() {
+    let (mut app, contract_addr, proxy_addr) = setup_nois_contract();
+
+    let old_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+
+    app.update_block(|block| block.height += NOIS_TIMEOUT);
+
+    // Proxy never called us back, the flip is refunded with its fee
+    let res = execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+    assert!(res
+        .events
+        .iter()
+        .any(|event| event.ty == "wasm-refund_flip"));
+    assert!(!res.events.iter().any(|event| event.ty == "wasm-flip"));
+
+    let new_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(new_balance.amount, old_balance.amount);
+    assert_eq!(
+        query_fees(&app, contract_addr.clone(), NATIVE_DENOM).unwrap(),
+        Uint128::zero()
+    );
+
+    // Late callback is rejected
+    let err = execute_nois_callback(
+        &mut app,
+        contract_addr.clone(),
+        proxy_addr,
+        "flip-0",
+        NOIS_RANDOMNESS,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoisJobNotFound("flip-0".to_string()));
+
+    let flips = query_last_flips(&app, contract_addr).unwrap();
+    assert!(flips.is_empty());
+}
@@ -1,6 +1,7 @@
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Uint128};
 
 use crate::{
+    randomness::{check_proof_randomness, get_block_seed_at, replay_flip, FlipRandomness},
     testing::utils::setup::{next_block, MIN_BET, NATIVE_DENOM},
     types::PickTypes,
 };
@@ -8,7 +9,7 @@ use crate::{
 use super::utils::{
     executes::{execute_do_flips, execute_start_flip},
     helpers::{add_balance, add_balances, FLIPPER_PREFIX, MIN_FEES, MIN_FUNDS},
-    queries::{query_fees, query_last_flips, query_should_flip},
+    queries::{query_fees, query_flip_proof, query_keeper_status, query_last_flips},
     setup::{setup_base_contract, FLIPPER_ADDR, FLIPPER_ADDR2},
 };
 
@@ -214,7 +215,7 @@ fn test_get_flip_todo() {
 }
 
 #[test]
-fn test_should_flip() {
+fn test_keeper_status() {
     let (mut app, contract_addr) = setup_base_contract();
     execute_start_flip(
         &mut app,
@@ -229,6 +230,59 @@ fn test_should_flip() {
 
     app.update_block(next_block);
 
-    let res = query_should_flip(&app, contract_addr).unwrap();
-    assert!(res)
+    let res = query_keeper_status(&app, contract_addr).unwrap();
+    assert_eq!(res.ready_flips, 1);
+    assert_eq!(res.denoms, vec![NATIVE_DENOM.to_string()]);
+    // No keeper reward by default
+    assert!(res.bounty.is_empty());
+}
+
+#[test]
+fn test_get_flip_proof() {
+    let (mut app, contract_addr) = setup_base_contract();
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+
+    // No proof until the flip is resolved
+    query_flip_proof(&app, contract_addr.clone(), 0).unwrap_err();
+
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    let block = app.block_info();
+    let proof = query_flip_proof(&app, contract_addr, 0).unwrap();
+    assert_eq!(proof.wallet, Addr::unchecked(FLIPPER_ADDR));
+    assert_eq!(proof.fee, MIN_FEES);
+    assert_eq!(proof.start_block, 1);
+    assert_eq!(proof.block, block.height);
+    assert_eq!(proof.randomness, FlipRandomness::Block);
+    assert_eq!(
+        proof.seed,
+        sha256::digest(format!("0{}{}", block.height, block.time.nanos()))
+    );
+
+    // Picked heads and the outcome random is even, so the flip is won
+    assert!(proof.result);
+    assert_eq!(proof.outcome_random.unwrap() % 2, 0);
+    assert_eq!(proof.payout, MIN_BET * Uint128::new(2));
+
+    // Replaying the proof gives the same flip
+    assert!(check_proof_randomness(&proof, None).is_empty());
+    let block_seed = get_block_seed_at(proof.tx_index, proof.block, proof.timestamp);
+    let replay = replay_flip(&proof, &block_seed);
+    assert_eq!(replay.seed, proof.seed);
+    assert_eq!(replay.outcome_random, proof.outcome_random);
+    assert!(replay.result);
+
+    // Same roll with the other pick is lost
+    let mut tampered = proof.clone();
+    tampered.pick = PickTypes::Tails;
+    assert!(!replay_flip(&tampered, &block_seed).result);
 }
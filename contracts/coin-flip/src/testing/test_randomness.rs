@@ -0,0 +1,94 @@
+use cosmwasm_std::{coin, Addr, Timestamp, Uint128};
+
+use crate::{
+    randomness::{do_a_flip, get_prize_random},
+    types::{PickTypes, TodoFlip},
+};
+
+use super::utils::setup::{MIN_BET, NATIVE_DENOM};
+
+/// Amount of simulated batches, each one resolves 10 flips with the same seed
+const BATCHES: u64 = 5000;
+const FLIPS_PER_BATCH: u64 = 10;
+
+fn todo_flip(id: u64, wallet: &str) -> TodoFlip {
+    TodoFlip {
+        id,
+        wallet: Addr::unchecked(wallet),
+        amount: coin(MIN_BET.u128(), NATIVE_DENOM),
+        pick: PickTypes::Heads,
+        block: 1,
+        timestamp: Timestamp::from_seconds(123456789),
+        commit: None,
+        drand_round: None,
+        nois_job_id: None,
+        house_seed_index: None,
+        fee: Uint128::zero(),
+        jackpot_share: Uint128::zero(),
+        referral_shares: vec![],
+        client_seed: None,
+        opponent: None,
+        auto_bet: false,
+        voucher: false,
+    }
+}
+
+fn batch_seed(batch: u64) -> String {
+    sha256::digest(format!("seed-{batch}"))
+}
+
+#[test]
+fn test_flip_distribution() {
+    let mut heads: u64 = 0;
+
+    for batch in 0..BATCHES {
+        let seed = batch_seed(batch);
+
+        for i in 0..FLIPS_PER_BATCH {
+            let todo_flip = todo_flip(batch * FLIPS_PER_BATCH + i, &format!("flipper-{i}"));
+            if do_a_flip(&todo_flip, &seed) {
+                heads += 1;
+            }
+        }
+    }
+
+    // 50000 flips, standard deviation is ~112
+    let total = BATCHES * FLIPS_PER_BATCH;
+    assert!(heads.abs_diff(total / 2) < 500, "heads: {heads}/{total}");
+}
+
+#[test]
+fn test_flip_independence() {
+    // Both addresses have the same byte sum parity, which used to give them the same result
+    let wallet_1 = "flipper-0";
+    let wallet_2 = "flipper-2";
+
+    let mut same_result: u64 = 0;
+    let mut same_prize_parity: u64 = 0;
+
+    for batch in 0..BATCHES {
+        let seed = batch_seed(batch);
+        let flip_1 = todo_flip(batch * 2, wallet_1);
+        let flip_2 = todo_flip(batch * 2 + 1, wallet_2);
+
+        let result_1 = do_a_flip(&flip_1, &seed);
+        if result_1 == do_a_flip(&flip_2, &seed) {
+            same_result += 1;
+        }
+
+        // The prize pick must not follow the outcome
+        if result_1 == get_prize_random(&seed, &flip_1).is_multiple_of(2) {
+            same_prize_parity += 1;
+        }
+    }
+
+    // 5000 pairs, standard deviation is ~35
+    assert!(
+        same_result.abs_diff(BATCHES / 2) < 175,
+        "same result: {same_result}/{BATCHES}"
+    );
+    assert!(
+        same_prize_parity.abs_diff(BATCHES / 2) < 175,
+        "same prize parity: {same_prize_parity}/{BATCHES}"
+    );
+}
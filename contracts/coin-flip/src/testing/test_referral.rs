@@ -0,0 +1,236 @@
+use cosmwasm_std::{coin, Addr, Uint128};
+
+use crate::{error::ContractError, types::Fees};
+
+use super::utils::{
+    executes::{
+        execute_cancel_flip, execute_claim_referral_rewards, execute_do_flips,
+        execute_start_flip_with_referrer, sudo_distribute, sudo_update_fees, sudo_withdraw_excess,
+    },
+    helpers::{add_balance, MIN_FEES},
+    queries::{query_fees, query_referral_stats},
+    setup::{
+        setup_base_contract, setup_contract, BaseApp, FLIPPER_ADDR, FLIPPER_ADDR2, MIN_BANK_AMOUNT,
+        NATIVE_DENOM,
+    },
+};
+
+/// 10% of the flip fees go to the referrer
+const REFERRAL_SHARE: Uint128 = Uint128::new(17500);
+const REFERRER_ADDR: &str = "referrer";
+
+fn setup_referral_contract() -> (BaseApp, Addr) {
+    let (mut app, contract_addr) = setup_base_contract();
+    set_referral_fees(&mut app, contract_addr.clone());
+
+    (app, contract_addr)
+}
+
+fn set_referral_fees(app: &mut BaseApp, contract_addr: Addr) {
+    sudo_update_fees(
+        app,
+        contract_addr,
+        Fees {
+            team_bps: 1500,
+            holders_bps: 7000,
+            reserve_bps: 1500,
+            flip_bps: 350,
+            jackpot_bps: 0,
+            referral_bps: 1000,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_referral_rewards() {
+    let (mut app, contract_addr) = setup_referral_contract();
+
+    let res = execute_start_flip_with_referrer(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        REFERRER_ADDR,
+    )
+    .unwrap();
+    assert!(res
+        .events
+        .iter()
+        .any(|event| event.ty == "wasm-bind_referrer"));
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    // The referral share is taken out of the fees when they are paid
+    let fees = query_fees(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert_eq!(fees, MIN_FEES - REFERRAL_SHARE);
+
+    let stats = query_referral_stats(&app, contract_addr.clone(), REFERRER_ADDR).unwrap();
+    assert_eq!(stats.referrer, None);
+    assert_eq!(stats.referrals, 1);
+    assert_eq!(
+        stats.claimable,
+        vec![coin(REFERRAL_SHARE.u128(), NATIVE_DENOM)]
+    );
+
+    // The first referrer stays bound
+    execute_start_flip_with_referrer(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        FLIPPER_ADDR2,
+    )
+    .unwrap();
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    let stats = query_referral_stats(&app, contract_addr.clone(), FLIPPER_ADDR).unwrap();
+    assert_eq!(stats.referrer, Some(Addr::unchecked(REFERRER_ADDR)));
+    let stats = query_referral_stats(&app, contract_addr.clone(), FLIPPER_ADDR2).unwrap();
+    assert_eq!(stats.referrals, 0);
+    assert!(stats.earned.is_empty());
+
+    let earned = coin((REFERRAL_SHARE * Uint128::new(2)).u128(), NATIVE_DENOM);
+    let stats = query_referral_stats(&app, contract_addr.clone(), REFERRER_ADDR).unwrap();
+    assert_eq!(stats.claimable, vec![earned.clone()]);
+
+    execute_claim_referral_rewards(&mut app, contract_addr.clone(), REFERRER_ADDR).unwrap();
+    let balance = app
+        .wrap()
+        .query_balance(REFERRER_ADDR, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(balance, earned);
+
+    let stats = query_referral_stats(&app, contract_addr.clone(), REFERRER_ADDR).unwrap();
+    assert!(stats.claimable.is_empty());
+    assert_eq!(stats.earned, vec![earned]);
+
+    let err = execute_claim_referral_rewards(&mut app, contract_addr, REFERRER_ADDR).unwrap_err();
+    assert_eq!(err, ContractError::NoReferralRewards);
+}
+
+#[test]
+fn test_self_referral() {
+    let (mut app, contract_addr) = setup_referral_contract();
+
+    let err = execute_start_flip_with_referrer(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        FLIPPER_ADDR,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::SelfReferral);
+
+    let stats = query_referral_stats(&app, contract_addr, FLIPPER_ADDR).unwrap();
+    assert_eq!(stats.referrer, None);
+}
+
+#[test]
+fn test_referral_rewards_not_excess_funds() {
+    let (mut app, contract_addr) = setup_referral_contract();
+    add_balance(&mut app, contract_addr.clone(), 30_100_000_000);
+
+    execute_start_flip_with_referrer(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        REFERRER_ADDR,
+    )
+    .unwrap();
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    sudo_withdraw_excess(&mut app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+
+    // The unclaimed rewards stay in the contract with the fees and the bank
+    let fees = query_fees(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    let contract_balance = app
+        .wrap()
+        .query_balance(contract_addr.clone(), NATIVE_DENOM)
+        .unwrap()
+        .amount;
+    assert_eq!(contract_balance, MIN_BANK_AMOUNT + fees + REFERRAL_SHARE);
+
+    execute_claim_referral_rewards(&mut app, contract_addr, REFERRER_ADDR).unwrap();
+}
+
+#[test]
+fn test_referral_rewards_before_distribute() {
+    let (mut app, contract_addr) = setup_contract();
+    set_referral_fees(&mut app, contract_addr.clone());
+
+    // The referral share is held when the fee is paid, and credited when the flip is resolved
+    execute_start_flip_with_referrer(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        REFERRER_ADDR,
+    )
+    .unwrap();
+    let fees = query_fees(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert_eq!(fees, MIN_FEES - REFERRAL_SHARE);
+    let stats = query_referral_stats(&app, contract_addr.clone(), REFERRER_ADDR).unwrap();
+    assert!(stats.claimable.is_empty());
+
+    // A distribution before the flip is resolved doesn't take the referral share
+    sudo_distribute(&mut app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    let reward = coin(REFERRAL_SHARE.u128(), NATIVE_DENOM);
+    let stats = query_referral_stats(&app, contract_addr.clone(), REFERRER_ADDR).unwrap();
+    assert_eq!(stats.claimable, vec![reward.clone()]);
+
+    execute_claim_referral_rewards(&mut app, contract_addr, REFERRER_ADDR).unwrap();
+    let balance = app
+        .wrap()
+        .query_balance(REFERRER_ADDR, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(balance, reward);
+}
+
+#[test]
+fn test_referral_share_refunded_on_cancel() {
+    let (mut app, contract_addr) = setup_referral_contract();
+    let old_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+
+    // Starting and cancelling referred flips doesn't earn the referrer anything
+    for _ in 0..3 {
+        execute_start_flip_with_referrer(
+            &mut app,
+            contract_addr.clone(),
+            Addr::unchecked(FLIPPER_ADDR),
+            REFERRER_ADDR,
+        )
+        .unwrap();
+        execute_cancel_flip(
+            &mut app,
+            contract_addr.clone(),
+            Addr::unchecked(FLIPPER_ADDR),
+        )
+        .unwrap();
+    }
+
+    let stats = query_referral_stats(&app, contract_addr.clone(), REFERRER_ADDR).unwrap();
+    assert_eq!(stats.referrals, 1);
+    assert!(stats.earned.is_empty());
+    assert!(stats.claimable.is_empty());
+    let err =
+        execute_claim_referral_rewards(&mut app, contract_addr.clone(), REFERRER_ADDR).unwrap_err();
+    assert_eq!(err, ContractError::NoReferralRewards);
+
+    let fees = query_fees(&app, contract_addr.clone(), NATIVE_DENOM).unwrap();
+    assert!(fees.is_zero());
+    let new_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(new_balance, old_balance);
+
+    // Nothing is held for the referrer anymore, the bank is whole
+    let contract_balance = app
+        .wrap()
+        .query_balance(contract_addr, NATIVE_DENOM)
+        .unwrap()
+        .amount;
+    assert_eq!(contract_balance, MIN_BANK_AMOUNT);
+}
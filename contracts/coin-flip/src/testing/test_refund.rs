@@ -0,0 +1,190 @@
+use cosmwasm_std::{Addr, Uint128};
+
+use crate::{
+    error::ContractError,
+    testing::utils::{
+        executes::{
+            execute_accept_challenge, execute_open_challenge, execute_refund,
+            execute_start_flip_with_commitment, sudo_update_flip_expiry, sudo_update_pause,
+        },
+        queries::query_fees,
+        setup::{next_block, setup_commit_reveal_contract, BaseApp, PLAYER_SECRET},
+    },
+    types::{PickTypes, DEFAULT_FLIP_EXPIRY},
+};
+
+use super::utils::{
+    executes::execute_start_flip,
+    helpers::MIN_FUNDS,
+    setup::{setup_base_contract, FLIPPER_ADDR, FLIPPER_ADDR2, MIN_BET, NATIVE_DENOM},
+};
+
+#[test]
+fn test_refund_expired_flip() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    let old_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+
+    execute_start_flip(
+        &mut app,
+        contract_addr.clone(),
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+
+    let err = execute_refund(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::FlipNotExpired(1 + DEFAULT_FLIP_EXPIRY));
+
+    let err = sudo_update_flip_expiry(&mut app, contract_addr.clone(), 0).unwrap_err();
+    assert_eq!(err, ContractError::InvalidFlipExpiry);
+    sudo_update_flip_expiry(&mut app, contract_addr.clone(), 5).unwrap();
+
+    app.update_block(|block| block.height += 4);
+    let err = execute_refund(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::FlipNotExpired(6));
+
+    // Only the flipper can refund their flip
+    app.update_block(next_block);
+    let err = execute_refund(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR2),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoFlipsToRefund);
+
+    // Refunds are still possible while paused
+    sudo_update_pause(&mut app, contract_addr.clone(), true).unwrap();
+    let res = execute_refund(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+    )
+    .unwrap();
+    let refund_event = res
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-refund_flip")
+        .unwrap();
+    assert!(refund_event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "reason" && attr.value == "expired"));
+
+    // Bet and fee are back, and the fee is out of the fees to distribute
+    let new_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(new_balance.amount, old_balance.amount);
+    assert_eq!(
+        query_fees(&app, contract_addr.clone(), NATIVE_DENOM).unwrap(),
+        Uint128::zero()
+    );
+
+    let err = execute_refund(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoFlipsToRefund);
+
+    // Flipper is free to flip again
+    sudo_update_pause(&mut app, contract_addr.clone(), false).unwrap();
+    execute_start_flip(
+        &mut app,
+        contract_addr,
+        PickTypes::Heads,
+        MIN_BET,
+        Addr::unchecked(FLIPPER_ADDR),
+        NATIVE_DENOM,
+        MIN_FUNDS,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_refund_commit_reveal_flip() {
+    let (mut app, contract_addr) = setup_commit_reveal_contract();
+
+    execute_start_flip_with_commitment(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        &sha256::digest(PLAYER_SECRET),
+    )
+    .unwrap();
+
+    // Withholding the secret is a timeout loss, not a refund
+    app.update_block(|block| block.height += DEFAULT_FLIP_EXPIRY);
+    let err = execute_refund(&mut app, contract_addr, Addr::unchecked(FLIPPER_ADDR)).unwrap_err();
+    assert_eq!(err, ContractError::FlipNotRefundable);
+}
+
+#[test]
+fn test_refund_challenge_by_opponent() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    let query_balance =
+        |app: &BaseApp, addr: &str| app.wrap().query_balance(addr, NATIVE_DENOM).unwrap().amount;
+    let creator_balance = query_balance(&app, FLIPPER_ADDR);
+    let opponent_balance = query_balance(&app, FLIPPER_ADDR2);
+
+    let stake = Uint128::new(100_000_000);
+    let stake_funds = Uint128::new(103_500_000);
+    execute_open_challenge(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        PickTypes::Heads,
+        stake,
+        stake_funds,
+    )
+    .unwrap();
+    execute_accept_challenge(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR2),
+        0,
+        stake_funds,
+    )
+    .unwrap();
+
+    // The opponent can refund the challenge as well
+    app.update_block(|block| block.height += DEFAULT_FLIP_EXPIRY);
+    execute_refund(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR2),
+    )
+    .unwrap();
+
+    // Both players get their stake and fee back
+    assert_eq!(query_balance(&app, FLIPPER_ADDR), creator_balance);
+    assert_eq!(query_balance(&app, FLIPPER_ADDR2), opponent_balance);
+    assert_eq!(
+        query_fees(&app, contract_addr.clone(), NATIVE_DENOM).unwrap(),
+        Uint128::zero()
+    );
+
+    let err = execute_refund(&mut app, contract_addr, Addr::unchecked(FLIPPER_ADDR)).unwrap_err();
+    assert_eq!(err, ContractError::NoFlipsToRefund);
+}
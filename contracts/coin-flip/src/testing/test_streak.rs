@@ -73,7 +73,7 @@ fn test_streak_claim() {
     execute_start_flip(
         &mut app,
         contract_addr.clone(),
-        PickTypes::Heads,
+        PickTypes::Tails,
         MIN_BET,
         Addr::unchecked(FLIPPER_ADDR),
         NATIVE_DENOM,
@@ -149,7 +149,7 @@ fn test_streak_claim_win_streak() {
     execute_start_flip(
         &mut app,
         contract_addr.clone(),
-        PickTypes::Tails,
+        PickTypes::Heads,
         MIN_BET,
         Addr::unchecked(FLIPPER_ADDR),
         NATIVE_DENOM,
@@ -221,7 +221,7 @@ fn test_streak_nft_rewards() {
     execute_start_flip(
         &mut app,
         contract_addr.clone(),
-        PickTypes::Heads,
+        PickTypes::Tails,
         MIN_BET,
         Addr::unchecked(FLIPPER_ADDR),
         NATIVE_DENOM,
@@ -306,7 +306,7 @@ fn test_streak_nft_rewards_win_streak() {
     execute_start_flip(
         &mut app,
         contract_addr.clone(),
-        PickTypes::Tails,
+        PickTypes::Heads,
         MIN_BET,
         Addr::unchecked(FLIPPER_ADDR),
         NATIVE_DENOM,
@@ -391,7 +391,7 @@ fn test_no_nft_pool() {
     execute_start_flip(
         &mut app,
         contract_addr.clone(),
-        PickTypes::Heads,
+        PickTypes::Tails,
         MIN_BET,
         Addr::unchecked(FLIPPER_ADDR),
         NATIVE_DENOM,
@@ -490,7 +490,7 @@ fn test_no_nft_pool_win_streak() {
     execute_start_flip(
         &mut app,
         contract_addr.clone(),
-        PickTypes::Tails,
+        PickTypes::Heads,
         MIN_BET,
         Addr::unchecked(FLIPPER_ADDR),
         NATIVE_DENOM,
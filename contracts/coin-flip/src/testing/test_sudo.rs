@@ -1,15 +1,17 @@
 use std::vec;
 
-use cosmwasm_std::{to_json_binary, Addr, Uint128, WasmMsg};
+use cosmwasm_std::{to_json_binary, Addr, BankMsg, Uint128, WasmMsg};
 use cw_multi_test::Executor;
-use sg_std::NATIVE_DENOM;
 
 use crate::{
     error::ContractError,
     testing::utils::{
-        executes::{sudo_transfer_nft, sudo_withdraw_nft_from_pool},
+        executes::{
+            sudo_distribute, sudo_transfer_nft, sudo_update_bank_limit, sudo_update_bet_limit,
+            sudo_update_payout_limit, sudo_withdraw_nft_from_pool,
+        },
         queries::query_nft_owner,
-        setup::{CREATOR_ADDR, MIN_BANK_AMOUNT, TEAM_ADDR},
+        setup::{CREATOR_ADDR, MIN_BANK_AMOUNT, NATIVE_DENOM, TEAM_ADDR},
     },
     types::StreakReward,
 };
@@ -119,6 +121,62 @@ fn test_withdraw_excess_funds() {
     assert_eq!(err, ContractError::NoExcessFunds)
 }
 
+#[test]
+fn test_withdraw_excess_funds_missing_funds() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    add_10_todo_flips(&mut app, contract_addr.clone());
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    // The balance doesn't even cover the fees anymore
+    let balance = app
+        .wrap()
+        .query_balance(contract_addr.clone(), NATIVE_DENOM)
+        .unwrap();
+    app.execute(
+        contract_addr.clone(),
+        BankMsg::Burn {
+            amount: vec![balance],
+        }
+        .into(),
+    )
+    .unwrap();
+
+    let err = sudo_withdraw_excess(&mut app, contract_addr, NATIVE_DENOM).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::ContractMissingFunds(NATIVE_DENOM.to_string())
+    );
+}
+
+#[test]
+fn test_sudo_unknown_denom() {
+    let (mut app, contract_addr) = setup_base_contract();
+    let not_found = ContractError::DenomNotFound {
+        denom: "uunknown".to_string(),
+    };
+
+    let err = sudo_update_bank_limit(&mut app, contract_addr.clone(), "uunknown", MIN_BANK_AMOUNT)
+        .unwrap_err();
+    assert_eq!(err, not_found);
+    let err = sudo_update_bet_limit(
+        &mut app,
+        contract_addr.clone(),
+        "uunknown",
+        Uint128::one(),
+        Uint128::new(2),
+    )
+    .unwrap_err();
+    assert_eq!(err, not_found);
+    let err =
+        sudo_update_payout_limit(&mut app, contract_addr.clone(), "uunknown", None).unwrap_err();
+    assert_eq!(err, not_found);
+    let err = sudo_distribute(&mut app, contract_addr.clone(), "uunknown").unwrap_err();
+    assert_eq!(err, not_found);
+    let err = sudo_withdraw_excess(&mut app, contract_addr, "uunknown").unwrap_err();
+    assert_eq!(err, not_found);
+}
+
 #[test]
 fn test_transfer_nft() {
     let (mut app, contract_addr, nft_contract1, _) = setup_with_nft_pool();
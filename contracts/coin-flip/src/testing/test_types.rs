@@ -1,10 +1,10 @@
-use crate::types::Streak;
+use cosmwasm_std::Uint128;
+
+use crate::types::{AutoBetStrategy, BetProgression, PickRule, PickTypes, Streak};
 
 #[cfg(test)]
 #[test]
 fn test_streak_reward() {
-    use cosmwasm_std::Uint128;
-
     use crate::types::StreakReward;
 
     let streak_reward = StreakReward::new(1, Uint128::new(100));
@@ -46,3 +46,81 @@ fn test_streak_update() {
         }
     );
 }
+
+#[test]
+fn test_pick_payout() {
+    let amount = Uint128::new(1_000_000);
+
+    assert_eq!(PickTypes::Heads.payout(amount), Uint128::new(2_000_000));
+    assert_eq!(PickTypes::Tails.payout(amount), Uint128::new(2_000_000));
+    assert_eq!(
+        PickTypes::RollUnder { win_chance: 50 }.payout(amount),
+        Uint128::new(2_000_000)
+    );
+    assert_eq!(
+        PickTypes::RollUnder { win_chance: 10 }.payout(amount),
+        Uint128::new(10_000_000)
+    );
+    // Rounded down
+    assert_eq!(
+        PickTypes::RollUnder { win_chance: 30 }.payout(amount),
+        Uint128::new(3_333_333)
+    );
+
+    assert!(PickTypes::RollUnder { win_chance: 10 }.is_valid());
+    assert!(PickTypes::RollUnder { win_chance: 90 }.is_valid());
+    assert!(!PickTypes::RollUnder { win_chance: 9 }.is_valid());
+    assert!(!PickTypes::RollUnder { win_chance: 91 }.is_valid());
+}
+
+#[test]
+fn test_auto_bet_strategy() {
+    let base_bet = Uint128::new(1_000_000);
+    let strategy = AutoBetStrategy {
+        pick: PickTypes::Heads,
+        pick_rule: PickRule::FollowLastSide,
+        base_bet,
+        progression: BetProgression::Martingale,
+        stop_loss: None,
+        take_profit: None,
+        max_rounds: 10,
+    };
+
+    let bet = Uint128::new(4_000_000);
+    assert_eq!(strategy.next_bet(bet, false), Uint128::new(8_000_000));
+    assert_eq!(strategy.next_bet(bet, true), base_bet);
+
+    let strategy = AutoBetStrategy {
+        progression: BetProgression::AntiMartingale,
+        ..strategy
+    };
+    assert_eq!(strategy.next_bet(bet, false), base_bet);
+    assert_eq!(strategy.next_bet(bet, true), Uint128::new(8_000_000));
+
+    // Follows the side the flip landed on
+    assert_eq!(
+        strategy.next_pick(&PickTypes::Heads, true),
+        PickTypes::Heads
+    );
+    assert_eq!(
+        strategy.next_pick(&PickTypes::Heads, false),
+        PickTypes::Tails
+    );
+
+    let strategy = AutoBetStrategy {
+        pick_rule: PickRule::Alternate,
+        ..strategy
+    };
+    assert_eq!(
+        strategy.next_pick(&PickTypes::Tails, true),
+        PickTypes::Heads
+    );
+    assert_eq!(
+        strategy.next_pick(&PickTypes::Tails, false),
+        PickTypes::Heads
+    );
+
+    // Roll-under picks have no sides
+    let roll_under = PickTypes::RollUnder { win_chance: 25 };
+    assert_eq!(strategy.next_pick(&roll_under, false), roll_under);
+}
@@ -0,0 +1,233 @@
+use cosmwasm_std::{coins, Addr, Uint128};
+use cw_multi_test::Executor;
+
+use crate::{
+    error::ContractError,
+    msg::{ExecuteMsg, FlipExecuteMsg},
+    testing::utils::{
+        executes::{
+            execute_do_flips, execute_grant_voucher, execute_revoke_voucher,
+            execute_start_voucher_flip, sudo_update_voucher_issuers, unwrap_execute,
+        },
+        queries::{query_fees, query_flip_proof, query_vouchers},
+    },
+    types::PickTypes,
+};
+
+use super::utils::setup::{
+    next_block, setup_base_contract, CREATOR_ADDR, FLIPPER_ADDR, FLIPPER_ADDR2, MIN_BET,
+    NATIVE_DENOM,
+};
+
+#[test]
+fn test_voucher_flip() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    execute_grant_voucher(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(CREATOR_ADDR),
+        FLIPPER_ADDR,
+        MIN_BET,
+        2,
+        100,
+    )
+    .unwrap();
+
+    let vouchers = query_vouchers(&app, contract_addr.clone(), FLIPPER_ADDR).unwrap();
+    assert_eq!(vouchers.len(), 1);
+    assert_eq!(vouchers[0].flips, 2);
+    assert_eq!(vouchers[0].amount.amount, MIN_BET);
+
+    let old_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+
+    // No funds are sent, the voucher pays for the flip
+    execute_start_voucher_flip(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        PickTypes::Heads,
+        MIN_BET,
+    )
+    .unwrap();
+
+    let vouchers = query_vouchers(&app, contract_addr.clone(), FLIPPER_ADDR).unwrap();
+    assert_eq!(vouchers[0].flips, 1);
+
+    execute_do_flips(&mut app, contract_addr.clone()).unwrap();
+
+    // A win only pays the profit, and there is no flip fee
+    let proof = query_flip_proof(&app, contract_addr.clone(), 0).unwrap();
+    assert!(proof.voucher);
+    assert_eq!(proof.fee, Uint128::zero());
+    let payout = if proof.result {
+        MIN_BET
+    } else {
+        Uint128::zero()
+    };
+    assert_eq!(proof.payout, payout);
+
+    let new_balance = app
+        .wrap()
+        .query_balance(FLIPPER_ADDR, NATIVE_DENOM)
+        .unwrap();
+    assert_eq!(new_balance.amount, old_balance.amount + payout);
+
+    let fees = query_fees(&app, contract_addr, NATIVE_DENOM).unwrap();
+    assert_eq!(fees, Uint128::zero());
+}
+
+#[test]
+fn test_voucher_issuers() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    let err = execute_grant_voucher(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR2),
+        FLIPPER_ADDR,
+        MIN_BET,
+        1,
+        100,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized);
+
+    sudo_update_voucher_issuers(
+        &mut app,
+        contract_addr.clone(),
+        vec![FLIPPER_ADDR2.to_string()],
+    )
+    .unwrap();
+    execute_grant_voucher(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR2),
+        FLIPPER_ADDR,
+        MIN_BET,
+        1,
+        100,
+    )
+    .unwrap();
+
+    execute_revoke_voucher(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR2),
+        FLIPPER_ADDR,
+        0,
+    )
+    .unwrap();
+    let vouchers = query_vouchers(&app, contract_addr.clone(), FLIPPER_ADDR).unwrap();
+    assert!(vouchers.is_empty());
+
+    let err = execute_revoke_voucher(
+        &mut app,
+        contract_addr,
+        Addr::unchecked(FLIPPER_ADDR2),
+        FLIPPER_ADDR,
+        0,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::VoucherNotFound(0));
+}
+
+#[test]
+fn test_voucher_errors() {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    let err = execute_grant_voucher(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(CREATOR_ADDR),
+        FLIPPER_ADDR,
+        MIN_BET,
+        0,
+        100,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidVoucherFlips);
+
+    let err = execute_start_voucher_flip(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        PickTypes::Heads,
+        MIN_BET,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoVoucher);
+
+    execute_grant_voucher(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(CREATOR_ADDR),
+        FLIPPER_ADDR,
+        MIN_BET,
+        1,
+        1,
+    )
+    .unwrap();
+
+    // Vouchers are for their amount only
+    let err = execute_start_voucher_flip(
+        &mut app,
+        contract_addr.clone(),
+        Addr::unchecked(FLIPPER_ADDR),
+        PickTypes::Heads,
+        MIN_BET * Uint128::new(2),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoVoucher);
+
+    // And for their denom only
+    let err = unwrap_execute(app.execute_contract(
+        Addr::unchecked(FLIPPER_ADDR),
+        contract_addr.clone(),
+        &ExecuteMsg::Flip(FlipExecuteMsg::StartFlip {
+            pick: PickTypes::Heads,
+            amount: MIN_BET,
+            commitment: None,
+            client_seed: None,
+            use_voucher: Some(true),
+            voucher_denom: Some("uother".to_string()),
+            referrer: None,
+        }),
+        &[],
+    ))
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoVoucher);
+
+    // No funds are sent with a voucher
+    let err = unwrap_execute(app.execute_contract(
+        Addr::unchecked(FLIPPER_ADDR),
+        contract_addr.clone(),
+        &ExecuteMsg::Flip(FlipExecuteMsg::StartFlip {
+            pick: PickTypes::Heads,
+            amount: MIN_BET,
+            commitment: None,
+            client_seed: None,
+            use_voucher: Some(true),
+            voucher_denom: None,
+            referrer: None,
+        }),
+        &coins(MIN_BET.u128(), NATIVE_DENOM),
+    ))
+    .unwrap_err();
+    assert_eq!(err, ContractError::WrongFundsAmount);
+
+    app.update_block(next_block);
+
+    let err = execute_start_voucher_flip(
+        &mut app,
+        contract_addr,
+        Addr::unchecked(FLIPPER_ADDR),
+        PickTypes::Heads,
+        MIN_BET,
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoVoucher);
+}
@@ -1,15 +1,25 @@
 use std::collections::HashSet;
 
-use cosmwasm_std::{coins, to_json_binary, Addr, Empty, Uint128};
+use cosmwasm_std::{coin, coins, to_json_binary, Addr, Empty, HexBinary, Uint128};
+use cw20::Cw20ExecuteMsg;
 use cw_multi_test::{AppResponse, Executor};
 
 use crate::{
     error::ContractError,
-    msg::{ExecuteMsg, FlipExecuteMsg, StreakExecuteMsg, SudoMsg},
-    types::{DenomLimit, Fees, PickTypes, StreakReward},
+    msg::{
+        AutoBetExecuteMsg, ChallengeExecuteMsg, ExecuteMsg, FlipExecuteMsg, LeaderboardExecuteMsg,
+        NoisCallback, ReceiveMsg, ReferralExecuteMsg, StreakExecuteMsg, SudoMsg, VoucherExecuteMsg,
+    },
+    types::{
+        AutoBetStrategy, CancelFeePolicy, DenomLimit, Fees, KeeperReward, LeaderboardConfig,
+        PickTypes, RandomnessSource, StreakReward,
+    },
 };
 
-use super::setup::{next_block, BaseApp, CREATOR_ADDR, FLIPPER_ADDR};
+use super::{
+    helpers::MIN_FUNDS,
+    setup::{next_block, BaseApp, CREATOR_ADDR, FLIPPER_ADDR, MIN_BET, NATIVE_DENOM},
+};
 
 pub(crate) fn unwrap_execute(
     res: Result<AppResponse, anyhow::Error>,
@@ -36,11 +46,193 @@ pub fn execute_start_flip(
         &ExecuteMsg::Flip(FlipExecuteMsg::StartFlip {
             pick,
             amount: flip_amount,
+            commitment: None,
+            client_seed: None,
+            use_voucher: None,
+            voucher_denom: None,
+            referrer: None,
+        }),
+        &funds,
+    ))
+}
+
+/// Send CW20 tokens to the contract to start a flip with them
+pub fn execute_start_cw20_flip(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    token_addr: Addr,
+    pick: PickTypes,
+    flip_amount: Uint128,
+    flipper: Addr,
+    funds: Uint128,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(
+        app.execute_contract(
+            flipper,
+            token_addr,
+            &Cw20ExecuteMsg::Send {
+                contract: contract_addr.to_string(),
+                amount: funds,
+                msg: to_json_binary(&ReceiveMsg::StartFlip {
+                    pick,
+                    amount: flip_amount,
+                    commitment: None,
+                    client_seed: None,
+                    referrer: None,
+                })
+                .unwrap(),
+            },
+            &[],
+        ),
+    )
+}
+
+pub fn execute_cw20_transfer(
+    app: &mut BaseApp,
+    token_addr: Addr,
+    sender: Addr,
+    recipient: &Addr,
+    amount: Uint128,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        sender,
+        token_addr,
+        &Cw20ExecuteMsg::Transfer {
+            recipient: recipient.to_string(),
+            amount,
+        },
+        &[],
+    ))
+}
+
+pub fn execute_start_flips(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    picks: Vec<(PickTypes, Uint128)>,
+    flipper: Addr,
+    denom: &str,
+    funds: Uint128,
+) -> Result<AppResponse, ContractError> {
+    let funds = coins(funds.u128(), denom);
+    unwrap_execute(app.execute_contract(
+        flipper,
+        contract_addr,
+        &ExecuteMsg::Flip(FlipExecuteMsg::StartFlips {
+            picks,
+            commitments: None,
+            client_seed: None,
+            referrer: None,
         }),
         &funds,
     ))
 }
 
+pub fn execute_start_flips_with_commitments(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    picks: Vec<(PickTypes, Uint128)>,
+    flipper: Addr,
+    commitments: Option<Vec<String>>,
+) -> Result<AppResponse, ContractError> {
+    let funds = MIN_FUNDS * Uint128::from(picks.len() as u128);
+    unwrap_execute(app.execute_contract(
+        flipper,
+        contract_addr,
+        &ExecuteMsg::Flip(FlipExecuteMsg::StartFlips {
+            picks,
+            commitments,
+            client_seed: None,
+            referrer: None,
+        }),
+        &coins(funds.u128(), NATIVE_DENOM),
+    ))
+}
+
+pub fn execute_start_flip_with_commitment(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    flipper: Addr,
+    commitment: &str,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        flipper,
+        contract_addr,
+        &ExecuteMsg::Flip(FlipExecuteMsg::StartFlip {
+            pick: PickTypes::Heads,
+            amount: MIN_BET,
+            commitment: Some(commitment.to_string()),
+            client_seed: None,
+            use_voucher: None,
+            voucher_denom: None,
+            referrer: None,
+        }),
+        &coins(MIN_FUNDS.u128(), NATIVE_DENOM),
+    ))
+}
+
+pub fn execute_start_flip_with_client_seed(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    flipper: Addr,
+    pick: PickTypes,
+    client_seed: &str,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        flipper,
+        contract_addr,
+        &ExecuteMsg::Flip(FlipExecuteMsg::StartFlip {
+            pick,
+            amount: MIN_BET,
+            commitment: None,
+            client_seed: Some(client_seed.to_string()),
+            use_voucher: None,
+            voucher_denom: None,
+            referrer: None,
+        }),
+        &coins(MIN_FUNDS.u128(), NATIVE_DENOM),
+    ))
+}
+
+pub fn execute_start_flip_with_referrer(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    flipper: Addr,
+    referrer: &str,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        flipper,
+        contract_addr,
+        &ExecuteMsg::Flip(FlipExecuteMsg::StartFlip {
+            pick: PickTypes::Heads,
+            amount: MIN_BET,
+            commitment: None,
+            client_seed: None,
+            use_voucher: None,
+            voucher_denom: None,
+            referrer: Some(referrer.to_string()),
+        }),
+        &coins(MIN_FUNDS.u128(), NATIVE_DENOM),
+    ))
+}
+
+pub fn execute_reveal_flip(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    flipper: Addr,
+    id: u64,
+    secret: &str,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        flipper,
+        contract_addr,
+        &ExecuteMsg::Flip(FlipExecuteMsg::RevealFlip {
+            id,
+            secret: secret.to_string(),
+        }),
+        &[],
+    ))
+}
+
 pub fn execute_do_flips(
     app: &mut BaseApp,
     contract_addr: Addr,
@@ -49,7 +241,301 @@ pub fn execute_do_flips(
     unwrap_execute(app.execute_contract(
         Addr::unchecked(FLIPPER_ADDR),
         contract_addr,
-        &ExecuteMsg::Flip(FlipExecuteMsg::DoFlips {}),
+        &ExecuteMsg::Flip(FlipExecuteMsg::DoFlips {
+            round: None,
+            signature: None,
+            house_seed: None,
+            limit: None,
+        }),
+        &[],
+    ))
+}
+
+pub fn execute_do_flips_with_limit(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    limit: u32,
+) -> Result<AppResponse, ContractError> {
+    app.update_block(next_block);
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(FLIPPER_ADDR),
+        contract_addr,
+        &ExecuteMsg::Flip(FlipExecuteMsg::DoFlips {
+            round: None,
+            signature: None,
+            house_seed: None,
+            limit: Some(limit),
+        }),
+        &[],
+    ))
+}
+
+pub fn execute_do_flips_with_beacon(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    round: u64,
+    signature: &str,
+) -> Result<AppResponse, ContractError> {
+    app.update_block(next_block);
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(FLIPPER_ADDR),
+        contract_addr,
+        &ExecuteMsg::Flip(FlipExecuteMsg::DoFlips {
+            round: Some(round),
+            signature: Some(HexBinary::from_hex(signature).unwrap()),
+            house_seed: None,
+            limit: None,
+        }),
+        &[],
+    ))
+}
+
+pub fn execute_do_flips_with_limit_and_house_seed(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    limit: u32,
+    house_seed: &str,
+) -> Result<AppResponse, ContractError> {
+    app.update_block(next_block);
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(FLIPPER_ADDR),
+        contract_addr,
+        &ExecuteMsg::Flip(FlipExecuteMsg::DoFlips {
+            round: None,
+            signature: None,
+            house_seed: Some(house_seed.to_string()),
+            limit: Some(limit),
+        }),
+        &[],
+    ))
+}
+
+pub fn execute_nois_callback(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    sender: Addr,
+    job_id: &str,
+    randomness: &str,
+) -> Result<AppResponse, ContractError> {
+    app.update_block(next_block);
+    let published = app.block_info().time;
+    unwrap_execute(app.execute_contract(
+        sender,
+        contract_addr,
+        &ExecuteMsg::NoisReceive {
+            callback: NoisCallback {
+                job_id: job_id.to_string(),
+                published,
+                randomness: HexBinary::from_hex(randomness).unwrap(),
+            },
+        },
+        &[],
+    ))
+}
+
+pub fn execute_do_flips_with_house_seed(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    house_seed: &str,
+) -> Result<AppResponse, ContractError> {
+    app.update_block(next_block);
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(FLIPPER_ADDR),
+        contract_addr,
+        &ExecuteMsg::Flip(FlipExecuteMsg::DoFlips {
+            round: None,
+            signature: None,
+            house_seed: Some(house_seed.to_string()),
+            limit: None,
+        }),
+        &[],
+    ))
+}
+
+pub fn execute_refund(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    flipper: Addr,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        flipper,
+        contract_addr,
+        &ExecuteMsg::Flip(FlipExecuteMsg::Refund {}),
+        &[],
+    ))
+}
+
+pub fn execute_cancel_flip(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    flipper: Addr,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        flipper,
+        contract_addr,
+        &ExecuteMsg::Flip(FlipExecuteMsg::CancelFlip {}),
+        &[],
+    ))
+}
+
+pub fn execute_open_challenge(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    creator: Addr,
+    pick: PickTypes,
+    amount: Uint128,
+    funds: Uint128,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        creator,
+        contract_addr,
+        &ExecuteMsg::Challenge(ChallengeExecuteMsg::Open { pick, amount }),
+        &coins(funds.u128(), NATIVE_DENOM),
+    ))
+}
+
+pub fn execute_accept_challenge(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    opponent: Addr,
+    id: u64,
+    funds: Uint128,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        opponent,
+        contract_addr,
+        &ExecuteMsg::Challenge(ChallengeExecuteMsg::Accept { id }),
+        &coins(funds.u128(), NATIVE_DENOM),
+    ))
+}
+
+pub fn execute_cancel_challenge(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    sender: Addr,
+    id: u64,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        sender,
+        contract_addr,
+        &ExecuteMsg::Challenge(ChallengeExecuteMsg::Cancel { id }),
+        &[],
+    ))
+}
+
+pub fn execute_start_auto_bet(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    wallet: Addr,
+    strategy: AutoBetStrategy,
+    funds: Uint128,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        wallet,
+        contract_addr,
+        &ExecuteMsg::AutoBet(AutoBetExecuteMsg::Start { strategy }),
+        &coins(funds.u128(), NATIVE_DENOM),
+    ))
+}
+
+pub fn execute_withdraw_auto_bet(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    wallet: Addr,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        wallet,
+        contract_addr,
+        &ExecuteMsg::AutoBet(AutoBetExecuteMsg::Withdraw {}),
+        &[],
+    ))
+}
+
+pub fn execute_start_voucher_flip(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    flipper: Addr,
+    pick: PickTypes,
+    flip_amount: Uint128,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        flipper,
+        contract_addr,
+        &ExecuteMsg::Flip(FlipExecuteMsg::StartFlip {
+            pick,
+            amount: flip_amount,
+            commitment: None,
+            client_seed: None,
+            use_voucher: Some(true),
+            voucher_denom: None,
+            referrer: None,
+        }),
+        &[],
+    ))
+}
+
+pub fn execute_grant_voucher(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    issuer: Addr,
+    wallet: &str,
+    amount: Uint128,
+    flips: u32,
+    expires_in: u64,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        issuer,
+        contract_addr,
+        &ExecuteMsg::Voucher(VoucherExecuteMsg::Grant {
+            wallet: wallet.to_string(),
+            amount: coin(amount.u128(), NATIVE_DENOM),
+            flips,
+            expires_in,
+        }),
+        &[],
+    ))
+}
+
+pub fn execute_revoke_voucher(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    issuer: Addr,
+    wallet: &str,
+    id: u64,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        issuer,
+        contract_addr,
+        &ExecuteMsg::Voucher(VoucherExecuteMsg::Revoke {
+            wallet: wallet.to_string(),
+            id,
+        }),
+        &[],
+    ))
+}
+
+pub fn execute_finalize_epoch(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    epoch: u64,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(FLIPPER_ADDR),
+        contract_addr,
+        &ExecuteMsg::Leaderboard(LeaderboardExecuteMsg::FinalizeEpoch { epoch }),
+        &[],
+    ))
+}
+
+pub fn execute_claim_referral_rewards(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    sender: &str,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(sender),
+        contract_addr,
+        &ExecuteMsg::Referral(ReferralExecuteMsg::ClaimReferralRewards {}),
         &[],
     ))
 }
@@ -116,6 +602,23 @@ pub fn sudo_update_bet_limit(
     ))
 }
 
+pub fn sudo_update_payout_limit(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    denom: &str,
+    max_payout: Option<Uint128>,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(CREATOR_ADDR),
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::UpdatePayoutLimit {
+            denom: denom.to_string(),
+            max_payout,
+        }),
+        &[],
+    ))
+}
+
 pub fn sudo_update_pause(
     app: &mut BaseApp,
     contract_addr: Addr,
@@ -241,6 +744,172 @@ pub fn sudo_transfer_nft(
     ))
 }
 
+pub fn sudo_update_randomness(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    randomness: RandomnessSource,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(CREATOR_ADDR),
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::UpdateRandomness { randomness }),
+        &[],
+    ))
+}
+
+pub fn sudo_commit_operator_seed(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    commitment: &str,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(CREATOR_ADDR),
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::CommitOperatorSeed {
+            commitment: commitment.to_string(),
+        }),
+        &[],
+    ))
+}
+
+pub fn sudo_reveal_operator_seed(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    seed: &str,
+    next_commitment: Option<&str>,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(CREATOR_ADDR),
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::RevealOperatorSeed {
+            seed: seed.to_string(),
+            next_commitment: next_commitment.map(|c| c.to_string()),
+        }),
+        &[],
+    ))
+}
+
+pub fn sudo_commit_house_chain(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    head: &str,
+    length: u64,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(CREATOR_ADDR),
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::CommitHouseChain {
+            head: head.to_string(),
+            length,
+        }),
+        &[],
+    ))
+}
+
+pub fn sudo_update_flip_expiry(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    blocks: u64,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(CREATOR_ADDR),
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::UpdateFlipExpiry { blocks }),
+        &[],
+    ))
+}
+
+pub fn sudo_update_cancel_fee_policy(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    policy: CancelFeePolicy,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(CREATOR_ADDR),
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::UpdateCancelFeePolicy { policy }),
+        &[],
+    ))
+}
+
+pub fn sudo_update_max_pending_flips(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    max: u32,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(CREATOR_ADDR),
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::UpdateMaxPendingFlips { max }),
+        &[],
+    ))
+}
+
+pub fn sudo_update_keeper_reward(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    reward: KeeperReward,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(CREATOR_ADDR),
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::UpdateKeeperReward { reward }),
+        &[],
+    ))
+}
+
+pub fn sudo_update_challenge_expiry(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    blocks: u64,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(CREATOR_ADDR),
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::UpdateChallengeExpiry { blocks }),
+        &[],
+    ))
+}
+
+pub fn sudo_update_voucher_issuers(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    issuers: Vec<String>,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(CREATOR_ADDR),
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::UpdateVoucherIssuers { issuers }),
+        &[],
+    ))
+}
+
+pub fn sudo_update_jackpot_odds(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    odds: u64,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(CREATOR_ADDR),
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::UpdateJackpotOdds { odds }),
+        &[],
+    ))
+}
+
+pub fn sudo_update_leaderboard(
+    app: &mut BaseApp,
+    contract_addr: Addr,
+    leaderboard: Option<LeaderboardConfig>,
+) -> Result<AppResponse, ContractError> {
+    unwrap_execute(app.execute_contract(
+        Addr::unchecked(CREATOR_ADDR),
+        contract_addr,
+        &ExecuteMsg::Sudo(SudoMsg::UpdateLeaderboard { leaderboard }),
+        &[],
+    ))
+}
+
 pub fn execute_send_nft_to_pool(
     app: &mut BaseApp,
     sender: &str,
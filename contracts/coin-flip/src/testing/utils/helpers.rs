@@ -83,7 +83,7 @@ pub fn mint_nfts(app: &mut BaseApp, nft_contract_addr: Addr, sender: Addr, to_mi
         unwrap_execute(app.execute_contract(
             sender.clone(),
             nft_contract_addr.clone(),
-            &sg721::ExecuteMsg::Mint::<Empty, Empty>(cw721_base::MintMsg {
+            &cw721_base::ExecuteMsg::<Empty, Empty>::Mint(cw721_base::MintMsg {
                 token_id: i.to_string(),
                 owner: format!("{FLIPPER_PREFIX}{i}").to_string(),
                 token_uri: Some("ipfs://sdfsdf.com".to_string()),
@@ -1,9 +1,13 @@
 use cosmwasm_std::{Addr, Coin, StdError, Uint128};
+use cw20::{BalanceResponse, Cw20QueryMsg};
 use cw721::OwnerOfResponse;
 
 use crate::{
-    msg::{DryDistributionResponse, QueryMsg},
-    types::{Config, Flip, FlipScore, NftReward},
+    msg::{DryDistributionResponse, KeeperStatusResponse, QueryMsg, ReferralStatsResponse},
+    types::{
+        AutoBet, Challenge, Config, EpochScore, Flip, FlipProof, FlipScore, HouseChain,
+        Leaderboard, NftReward, OperatorSeed, Voucher,
+    },
 };
 
 use super::setup::BaseApp;
@@ -22,6 +26,65 @@ pub fn query_fees(app: &BaseApp, contract_addr: Addr, denom: &str) -> Result<Uin
     )
 }
 
+pub fn query_jackpot(app: &BaseApp, contract_addr: Addr, denom: &str) -> Result<Uint128, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetJackpot {
+            denom: denom.to_string(),
+        },
+    )
+}
+
+pub fn query_leaderboard(
+    app: &BaseApp,
+    contract_addr: Addr,
+    epoch: Option<u64>,
+) -> Result<Option<Leaderboard>, StdError> {
+    app.wrap()
+        .query_wasm_smart(contract_addr, &QueryMsg::GetLeaderboard { epoch })
+}
+
+pub fn query_epoch_score(
+    app: &BaseApp,
+    contract_addr: Addr,
+    address: &str,
+    epoch: Option<u64>,
+) -> Result<EpochScore, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetEpochScore {
+            address: address.to_string(),
+            epoch,
+        },
+    )
+}
+
+pub fn query_prize_pool(
+    app: &BaseApp,
+    contract_addr: Addr,
+    denom: &str,
+) -> Result<Uint128, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetPrizePool {
+            denom: denom.to_string(),
+        },
+    )
+}
+
+pub fn query_referral_stats(
+    app: &BaseApp,
+    contract_addr: Addr,
+    address: &str,
+) -> Result<ReferralStatsResponse, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetReferralStats {
+            address: address.to_string(),
+        },
+    )
+}
+
 pub fn query_all_fees(app: &BaseApp, contract_addr: Addr) -> Result<Vec<Coin>, StdError> {
     app.wrap()
         .query_wasm_smart(contract_addr, &QueryMsg::GetAllFeesAmount {})
@@ -63,9 +126,77 @@ pub fn query_nft_pool(app: &BaseApp, contract_addr: Addr) -> Result<Vec<NftRewar
         .query_wasm_smart(contract_addr, &QueryMsg::GetNftPool {})
 }
 
-pub fn query_should_flip(app: &BaseApp, contract_addr: Addr) -> Result<bool, StdError> {
+pub fn query_keeper_status(
+    app: &BaseApp,
+    contract_addr: Addr,
+) -> Result<KeeperStatusResponse, StdError> {
     app.wrap()
-        .query_wasm_smart(contract_addr, &QueryMsg::ShouldDoFlips {})
+        .query_wasm_smart(contract_addr, &QueryMsg::GetKeeperStatus {})
+}
+
+pub fn query_operator_seed(
+    app: &BaseApp,
+    contract_addr: Addr,
+    round: Option<u64>,
+) -> Result<OperatorSeed, StdError> {
+    app.wrap()
+        .query_wasm_smart(contract_addr, &QueryMsg::GetOperatorSeed { round })
+}
+
+pub fn query_flip_proof(
+    app: &BaseApp,
+    contract_addr: Addr,
+    id: u64,
+) -> Result<FlipProof, StdError> {
+    app.wrap()
+        .query_wasm_smart(contract_addr, &QueryMsg::GetFlipProof { id })
+}
+
+pub fn query_house_chain(app: &BaseApp, contract_addr: Addr) -> Result<HouseChain, StdError> {
+    app.wrap()
+        .query_wasm_smart(contract_addr, &QueryMsg::GetHouseChain {})
+}
+
+pub fn query_challenges(
+    app: &BaseApp,
+    contract_addr: Addr,
+    denom: &str,
+    start_after: Option<u64>,
+) -> Result<Vec<Challenge>, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetChallenges {
+            denom: denom.to_string(),
+            start_after,
+            limit: None,
+        },
+    )
+}
+
+pub fn query_auto_bet(
+    app: &BaseApp,
+    contract_addr: Addr,
+    address: &str,
+) -> Result<Option<AutoBet>, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetAutoBet {
+            address: address.to_string(),
+        },
+    )
+}
+
+pub fn query_vouchers(
+    app: &BaseApp,
+    contract_addr: Addr,
+    address: &str,
+) -> Result<Vec<Voucher>, StdError> {
+    app.wrap().query_wasm_smart(
+        contract_addr,
+        &QueryMsg::GetVouchers {
+            address: address.to_string(),
+        },
+    )
 }
 
 pub fn query_nft_owner(
@@ -81,3 +212,17 @@ pub fn query_nft_owner(
         },
     )
 }
+
+pub fn query_cw20_balance(
+    app: &BaseApp,
+    token_addr: Addr,
+    address: &str,
+) -> Result<Uint128, StdError> {
+    let res: BalanceResponse = app.wrap().query_wasm_smart(
+        token_addr,
+        &Cw20QueryMsg::Balance {
+            address: address.to_string(),
+        },
+    )?;
+    Ok(res.balance)
+}
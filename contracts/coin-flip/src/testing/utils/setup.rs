@@ -1,23 +1,30 @@
 use std::collections::HashSet;
 
 use cosmwasm_std::{
-    coin, coins, testing::MockApi, Addr, BlockInfo, Empty, MemoryStorage, Timestamp, Uint128,
+    coin, coins, testing::MockApi, to_json_binary, Addr, Binary, BlockInfo, Deps, DepsMut, Empty,
+    Env, HexBinary, MemoryStorage, MessageInfo, StdResult, Timestamp, Uint128,
 };
 use cw_multi_test::{
     App, BankKeeper, BasicAppBuilder, Contract, ContractWrapper, Executor, FailingModule,
     WasmKeeper,
 };
 
+use cw20::Cw20Coin;
+#[cfg(feature = "stargaze")]
 use sg721::{CollectionInfo, RoyaltyInfoResponse};
-use sg_std::StargazeMsgWrapper;
 
 use crate::{
-    msg::InstantiateMsg,
-    types::{DenomLimit, Fees, StreakReward, Wallets},
+    chain::{CustomMsg, Response},
+    helpers::cw20_denom,
+    msg::{InstantiateMsg, NoisProxyExecuteMsg},
+    types::{DenomLimit, Fees, RandomnessSource, StreakReward, Wallets},
 };
 
 use super::{
-    executes::{execute_send_nft_to_pool, sudo_add_new_denom, sudo_update_sg721, unwrap_execute},
+    executes::{
+        execute_send_nft_to_pool, sudo_add_new_denom, sudo_commit_operator_seed,
+        sudo_update_randomness, sudo_update_sg721, unwrap_execute,
+    },
     helpers::{add_balance, mint_nfts},
 };
 
@@ -25,14 +32,14 @@ pub type BaseApp = App<
     BankKeeper,
     MockApi,
     MemoryStorage,
-    FailingModule<StargazeMsgWrapper, Empty, Empty>,
-    WasmKeeper<StargazeMsgWrapper, Empty>,
+    FailingModule<CustomMsg, Empty, Empty>,
+    WasmKeeper<CustomMsg, Empty>,
 >;
 
 pub const FLIPPER_ADDR: &str = "some_flipper";
 pub const FLIPPER_ADDR2: &str = "some_flipper2";
 pub const CREATOR_ADDR: &str = "creator";
-pub const NATIVE_DENOM: &str = "ustars";
+pub const NATIVE_DENOM: &str = crate::chain::NATIVE_DENOM;
 pub const USDC_DENOM: &str = "uusdc";
 pub const TEST_STREAK_REWARDS: [StreakReward; 3] = [
     StreakReward::new(2, Uint128::new(100000)),
@@ -53,7 +60,21 @@ pub const RESERVE_ADDR: &str = "reserve_wallet";
 
 pub const PLUS_NANOS: u64 = 654321;
 
-pub fn nft_contract() -> Box<dyn Contract<StargazeMsgWrapper>> {
+// Commit-reveal
+pub const REVEAL_TIMEOUT: u64 = 5;
+pub const OPERATOR_SEED: &str = "operator-seed";
+pub const PLAYER_SECRET: &str = "player-secret";
+
+// Drand, recorded from a test chain
+pub const DRAND_GENESIS_TIME: u64 = 123456780;
+pub const DRAND_PERIOD: u64 = 3;
+pub const DRAND_PUBKEY: &str = "b89a2f0216f516cf7552e9d8d992a69906bd460e18df6acab96f66e02017b087c9efcc9ff3c393a341705b31a25824af01a8ee42cc6c380d3e6b40e3bf172b13270e22f24d7418ebd8173b61d876c4b90fb6280fd092fabf3480373ad844aa9c";
+
+// Nois
+pub const NOIS_TIMEOUT: u64 = 5;
+
+#[cfg(feature = "stargaze")]
+pub fn nft_contract() -> Box<dyn Contract<CustomMsg>> {
     let contract = ContractWrapper::new(
         sg721_base::entry::execute,
         sg721_base::entry::instantiate,
@@ -62,7 +83,85 @@ pub fn nft_contract() -> Box<dyn Contract<StargazeMsgWrapper>> {
     Box::new(contract)
 }
 
-pub fn flip_contract() -> Box<dyn Contract<StargazeMsgWrapper>> {
+#[cfg(feature = "kujira")]
+pub fn nft_contract() -> Box<dyn Contract<CustomMsg>> {
+    let contract = ContractWrapper::new_with_empty(
+        cw721_base::entry::execute,
+        cw721_base::entry::instantiate,
+        cw721_base::entry::query,
+    );
+    Box::new(contract)
+}
+
+#[cfg(feature = "stargaze")]
+fn instantiate_nft_contract(
+    app: &mut BaseApp,
+    code_id: u64,
+    sender: Addr,
+    name: &str,
+    symbol: &str,
+    minter: &str,
+    label: &str,
+) -> Addr {
+    app.instantiate_contract(
+        code_id,
+        sender,
+        &sg721::InstantiateMsg {
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            minter: minter.to_string(),
+            collection_info: CollectionInfo::<RoyaltyInfoResponse> {
+                description: name.to_string(),
+                image: "https://example.net".to_string(),
+                creator: CREATOR_ADDR.to_string(),
+                external_link: None,
+                explicit_content: Some(false),
+                start_trading_time: None,
+                royalty_info: None,
+            },
+        },
+        &[],
+        label,
+        None,
+    )
+    .unwrap()
+}
+
+#[cfg(feature = "kujira")]
+fn instantiate_nft_contract(
+    app: &mut BaseApp,
+    code_id: u64,
+    sender: Addr,
+    name: &str,
+    symbol: &str,
+    minter: &str,
+    label: &str,
+) -> Addr {
+    app.instantiate_contract(
+        code_id,
+        sender,
+        &cw721_base::InstantiateMsg {
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            minter: minter.to_string(),
+        },
+        &[],
+        label,
+        None,
+    )
+    .unwrap()
+}
+
+pub fn cw20_contract() -> Box<dyn Contract<CustomMsg>> {
+    let contract = ContractWrapper::new_with_empty(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    );
+    Box::new(contract)
+}
+
+pub fn flip_contract() -> Box<dyn Contract<CustomMsg>> {
     let contract = ContractWrapper::new(
         crate::contract::execute,
         crate::contract::instantiate,
@@ -71,6 +170,36 @@ pub fn flip_contract() -> Box<dyn Contract<StargazeMsgWrapper>> {
     Box::new(contract)
 }
 
+fn nois_proxy_execute(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: NoisProxyExecuteMsg,
+) -> StdResult<Response> {
+    Ok(Response::default())
+}
+
+fn nois_proxy_instantiate(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty,
+) -> StdResult<Response> {
+    Ok(Response::default())
+}
+
+fn nois_proxy_query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    to_json_binary(&Empty {})
+}
+
+/// Mock of the nois proxy, accepts randomness requests and does nothing,
+/// tests call the flip contract back as the proxy themselves
+pub fn nois_proxy_contract() -> Box<dyn Contract<CustomMsg>> {
+    let contract =
+        ContractWrapper::new(nois_proxy_execute, nois_proxy_instantiate, nois_proxy_query);
+    Box::new(contract)
+}
+
 pub fn next_block(block: &mut BlockInfo) {
     block.time = block.time.plus_nanos(PLUS_NANOS);
     block.height += 1;
@@ -78,7 +207,7 @@ pub fn next_block(block: &mut BlockInfo) {
 
 /// Basic setup for unit test on a single contract
 pub fn setup_base_contract() -> (BaseApp, Addr) {
-    let mut app: BaseApp = BasicAppBuilder::<StargazeMsgWrapper, Empty>::new_custom()
+    let mut app: BaseApp = BasicAppBuilder::<CustomMsg, Empty>::new_custom()
         .with_block(BlockInfo {
             height: 1,
             time: Timestamp::from_seconds(123456789),
@@ -122,10 +251,20 @@ pub fn setup_base_contract() -> (BaseApp, Addr) {
             holders_bps: 7000,
             reserve_bps: 1500,
             flip_bps: 350,
+            jackpot_bps: 0,
+            referral_bps: 0,
         },
         denom_limits: vec![(NATIVE_DENOM.to_string(), MIN_BET, MAX_BET, MIN_BANK_AMOUNT)],
-        flips_per_block_limit: None,
+        flips_per_block_limit: Some(10),
         sg721_addr: None,
+        randomness: None,
+        flip_expiry: None,
+        cancel_fee_policy: None,
+        max_pending_flips: None,
+        keeper_reward: None,
+        challenge_expiry: None,
+        jackpot_odds: None,
+        leaderboard: None,
         nft_pool_max: 4,
         streak_nft_winning_amount: 5,
         streak_rewards: TEST_STREAK_REWARDS.into(),
@@ -152,29 +291,15 @@ pub fn setup_contract() -> (BaseApp, Addr) {
     let (mut app, contract_addr) = setup_base_contract();
     let nft_code_id = app.store_code(nft_contract());
 
-    let nft_addr = app
-        .instantiate_contract(
-            nft_code_id,
-            contract_addr.clone(),
-            &sg721::InstantiateMsg {
-                name: "Test NFT".to_string(),
-                symbol: "TEST".to_string(),
-                minter: contract_addr.to_string(),
-                collection_info: CollectionInfo::<RoyaltyInfoResponse> {
-                    description: "Test NFT".to_string(),
-                    image: "https://example.net".to_string(),
-                    creator: CREATOR_ADDR.to_string(),
-                    external_link: None,
-                    explicit_content: Some(false),
-                    start_trading_time: None,
-                    royalty_info: None,
-                },
-            },
-            &[],
-            "flip contract",
-            None,
-        )
-        .unwrap();
+    let nft_addr = instantiate_nft_contract(
+        &mut app,
+        nft_code_id,
+        contract_addr.clone(),
+        "Test NFT",
+        "TEST",
+        contract_addr.as_str(),
+        "flip contract",
+    );
 
     mint_nfts(&mut app, nft_addr.clone(), contract_addr.clone(), 777);
 
@@ -186,60 +311,32 @@ pub fn setup_contract() -> (BaseApp, Addr) {
 pub fn setup_nft_contracts(app: &mut BaseApp, contract_addr: Addr) -> (Addr, Addr) {
     // Create two NFT contracts
     let nft_code_id = app.store_code(nft_contract());
-    let nft_addr1 = app
-        .instantiate_contract(
-            nft_code_id,
-            contract_addr.clone(),
-            &sg721::InstantiateMsg {
-                name: "Test NFT".to_string(),
-                symbol: "TEST".to_string(),
-                minter: Addr::unchecked(CREATOR_ADDR).into(),
-                collection_info: CollectionInfo::<RoyaltyInfoResponse> {
-                    description: "Test NFT".to_string(),
-                    image: "https://example.net".to_string(),
-                    creator: CREATOR_ADDR.to_string(),
-                    external_link: None,
-                    explicit_content: Some(false),
-                    start_trading_time: None,
-                    royalty_info: None,
-                },
-            },
-            &[],
-            "nft1 contract",
-            None,
-        )
-        .unwrap();
+    let nft_addr1 = instantiate_nft_contract(
+        app,
+        nft_code_id,
+        contract_addr.clone(),
+        "Test NFT",
+        "TEST",
+        CREATOR_ADDR,
+        "nft1 contract",
+    );
 
-    let nft_addr2 = app
-        .instantiate_contract(
-            nft_code_id,
-            contract_addr,
-            &sg721::InstantiateMsg {
-                name: "Test NFT2".to_string(),
-                symbol: "TEST2".to_string(),
-                minter: Addr::unchecked(CREATOR_ADDR).into(),
-                collection_info: CollectionInfo::<RoyaltyInfoResponse> {
-                    description: "Test NFT2".to_string(),
-                    image: "https://example.net".to_string(),
-                    creator: CREATOR_ADDR.to_string(),
-                    external_link: None,
-                    explicit_content: Some(false),
-                    start_trading_time: None,
-                    royalty_info: None,
-                },
-            },
-            &[],
-            "nft2 contract",
-            None,
-        )
-        .unwrap();
+    let nft_addr2 = instantiate_nft_contract(
+        app,
+        nft_code_id,
+        contract_addr,
+        "Test NFT2",
+        "TEST2",
+        CREATOR_ADDR,
+        "nft2 contract",
+    );
 
     // mint 5 nfts for each collection
     for i in 1..=5 {
         unwrap_execute(app.execute_contract(
             Addr::unchecked(CREATOR_ADDR),
             nft_addr1.clone(),
-            &sg721::ExecuteMsg::Mint::<Empty, Empty>(cw721_base::MintMsg {
+            &cw721_base::ExecuteMsg::<Empty, Empty>::Mint(cw721_base::MintMsg {
                 token_id: i.to_string(),
                 owner: CREATOR_ADDR.to_string(),
                 token_uri: Some("ipfs://sdfsdf.com".to_string()),
@@ -252,7 +349,7 @@ pub fn setup_nft_contracts(app: &mut BaseApp, contract_addr: Addr) -> (Addr, Add
         unwrap_execute(app.execute_contract(
             Addr::unchecked(CREATOR_ADDR),
             nft_addr2.clone(),
-            &sg721::ExecuteMsg::Mint::<Empty, Empty>(cw721_base::MintMsg {
+            &cw721_base::ExecuteMsg::<Empty, Empty>::Mint(cw721_base::MintMsg {
                 token_id: i.to_string(),
                 owner: CREATOR_ADDR.to_string(),
                 token_uri: Some("ipfs://sdfsdf.com".to_string()),
@@ -317,9 +414,142 @@ pub fn setup_with_multiple_denoms() -> (BaseApp, Addr) {
             min: MIN_BET,
             max: MAX_BET,
             bank: MIN_BANK_AMOUNT,
+            max_payout: None,
+        },
+    )
+    .unwrap();
+
+    (app, contract_addr)
+}
+
+/// Create a CW20 token held by the flipper, with the bank of the contract funded in it
+pub fn instantiate_cw20_token(app: &mut BaseApp, contract_addr: &Addr) -> Addr {
+    let cw20_code_id = app.store_code(cw20_contract());
+
+    app.instantiate_contract(
+        cw20_code_id,
+        Addr::unchecked(CREATOR_ADDR),
+        &cw20_base::msg::InstantiateMsg {
+            name: "Test Token".to_string(),
+            symbol: "TEST".to_string(),
+            decimals: 6,
+            initial_balances: vec![
+                Cw20Coin {
+                    address: FLIPPER_ADDR.to_string(),
+                    amount: Uint128::new(999999999999999),
+                },
+                Cw20Coin {
+                    address: contract_addr.to_string(),
+                    amount: MIN_BANK_AMOUNT,
+                },
+            ],
+            mint: None,
+            marketing: None,
+        },
+        &[],
+        "cw20 token",
+        None,
+    )
+    .unwrap()
+}
+
+/// Create a CW20 token and add it as a denom of the contract
+pub fn setup_cw20_token(app: &mut BaseApp, contract_addr: Addr) -> Addr {
+    let token_addr = instantiate_cw20_token(app, &contract_addr);
+
+    sudo_add_new_denom(
+        app,
+        contract_addr,
+        &cw20_denom(&token_addr),
+        DenomLimit {
+            min: MIN_BET,
+            max: MAX_BET,
+            bank: MIN_BANK_AMOUNT,
+            max_payout: None,
         },
     )
     .unwrap();
 
+    token_addr
+}
+
+pub fn setup_with_cw20() -> (BaseApp, Addr, Addr) {
+    let (mut app, contract_addr) = setup_base_contract();
+    let token_addr = setup_cw20_token(&mut app, contract_addr.clone());
+
+    (app, contract_addr, token_addr)
+}
+
+pub fn setup_commit_reveal_contract() -> (BaseApp, Addr) {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    sudo_update_randomness(
+        &mut app,
+        contract_addr.clone(),
+        RandomnessSource::CommitReveal {
+            reveal_timeout: REVEAL_TIMEOUT,
+        },
+    )
+    .unwrap();
+    sudo_commit_operator_seed(
+        &mut app,
+        contract_addr.clone(),
+        &sha256::digest(OPERATOR_SEED),
+    )
+    .unwrap();
+
+    (app, contract_addr)
+}
+
+pub fn setup_drand_contract() -> (BaseApp, Addr) {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    sudo_update_randomness(
+        &mut app,
+        contract_addr.clone(),
+        RandomnessSource::Drand {
+            pubkey: HexBinary::from_hex(DRAND_PUBKEY).unwrap(),
+            genesis_time: DRAND_GENESIS_TIME,
+            period: DRAND_PERIOD,
+        },
+    )
+    .unwrap();
+
+    (app, contract_addr)
+}
+
+pub fn setup_nois_contract() -> (BaseApp, Addr, Addr) {
+    let (mut app, contract_addr) = setup_base_contract();
+    let proxy_code_id = app.store_code(nois_proxy_contract());
+
+    let proxy_addr = app
+        .instantiate_contract(
+            proxy_code_id,
+            Addr::unchecked(CREATOR_ADDR),
+            &Empty {},
+            &[],
+            "nois proxy",
+            None,
+        )
+        .unwrap();
+
+    sudo_update_randomness(
+        &mut app,
+        contract_addr.clone(),
+        RandomnessSource::Nois {
+            proxy: proxy_addr.clone(),
+            timeout: NOIS_TIMEOUT,
+        },
+    )
+    .unwrap();
+
+    (app, contract_addr, proxy_addr)
+}
+
+pub fn setup_hash_chain_contract() -> (BaseApp, Addr) {
+    let (mut app, contract_addr) = setup_base_contract();
+
+    sudo_update_randomness(&mut app, contract_addr.clone(), RandomnessSource::HashChain).unwrap();
+
     (app, contract_addr)
 }
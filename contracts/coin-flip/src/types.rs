@@ -1,9 +1,10 @@
 use std::collections::{HashMap, HashSet};
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Coin, Decimal, Env, Timestamp, Uint128};
+use cosmwasm_std::{Addr, Coin, Decimal, Env, HexBinary, Timestamp, Uint128};
 
 use crate::helpers::bps_to_decimal;
+use crate::randomness::FlipRandomness;
 
 #[cw_serde]
 pub struct Config {
@@ -18,6 +19,176 @@ pub struct Config {
 
     pub nft_pool_max: u32,
     pub streak_nft_winning_amount: u32,
+
+    #[serde(default)]
+    pub randomness: RandomnessSource,
+    /// Amount of blocks after which a waiting flip can be refunded
+    #[serde(default = "default_flip_expiry")]
+    pub flip_expiry: u64,
+    #[serde(default)]
+    pub cancel_fee_policy: CancelFeePolicy,
+    /// Amount of flips a wallet can have waiting at the same time
+    #[serde(default = "default_max_pending_flips")]
+    pub max_pending_flips: u32,
+    #[serde(default)]
+    pub keeper_reward: KeeperReward,
+    /// Amount of blocks a PvP challenge can be accepted for
+    #[serde(default = "default_challenge_expiry")]
+    pub challenge_expiry: u64,
+    /// A rolled flip has one chance in jackpot_odds to win the jackpot of its denom, 0 never draws it
+    #[serde(default)]
+    pub jackpot_odds: u64,
+    /// Ranks the flips of every epoch when set
+    #[serde(default)]
+    pub leaderboard: Option<LeaderboardConfig>,
+}
+
+/// Flip expiry of configs saved before it existed, about an hour of blocks
+pub const DEFAULT_FLIP_EXPIRY: u64 = 600;
+
+fn default_flip_expiry() -> u64 {
+    DEFAULT_FLIP_EXPIRY
+}
+
+/// Challenge expiry of configs saved before it existed, about a day of blocks
+pub const DEFAULT_CHALLENGE_EXPIRY: u64 = 14_400;
+
+fn default_challenge_expiry() -> u64 {
+    DEFAULT_CHALLENGE_EXPIRY
+}
+
+/// Max pending flips of configs saved before it existed, a single flip per wallet
+pub const DEFAULT_MAX_PENDING_FLIPS: u32 = 1;
+
+fn default_max_pending_flips() -> u32 {
+    DEFAULT_MAX_PENDING_FLIPS
+}
+
+/// What happens to the flip fee when a flip is cancelled
+#[cw_serde]
+#[derive(Default)]
+pub enum CancelFeePolicy {
+    /// Bet and flip fee are sent back
+    #[default]
+    FullRefund,
+    /// Only the bet is sent back, the flip fee is kept
+    KeepFee,
+}
+
+/// What the caller of DoFlips earns for every flip it resolves, taken from the flip fee
+#[cw_serde]
+#[derive(Default)]
+pub enum KeeperReward {
+    #[default]
+    None,
+    /// Bps of the flip fee
+    Bps(u64),
+    /// Flat amount in the denom of the flip, capped to the flip fee
+    Flat(Uint128),
+}
+
+impl KeeperReward {
+    pub fn is_valid(&self) -> bool {
+        match self {
+            KeeperReward::Bps(bps) => *bps <= 10_000,
+            _ => true,
+        }
+    }
+
+    pub fn calculate(&self, flip_fee: Uint128) -> Uint128 {
+        match self {
+            KeeperReward::None => Uint128::zero(),
+            KeeperReward::Bps(bps) => flip_fee.multiply_ratio(*bps, 10_000_u128),
+            KeeperReward::Flat(amount) => (*amount).min(flip_fee),
+        }
+    }
+}
+
+/// Where the randomness of the flips comes from
+#[cw_serde]
+#[derive(Default)]
+pub enum RandomnessSource {
+    /// Hash of the block info of the DoFlips tx
+    #[default]
+    BlockHash,
+    /// Player commits to a secret on StartFlip and the operator commits to a seed ahead of time,
+    /// the flip is resolved only once both are revealed.
+    CommitReveal {
+        /// Amount of blocks after the flip started we wait for reveals before timing out
+        reveal_timeout: u64,
+    },
+    /// Every flip is tied to the first drand round published after it started,
+    /// the flip is resolved once the beacon of that round is submitted on DoFlips.
+    Drand {
+        /// Public key of the drand chain (unchained G1 signatures, like quicknet)
+        pubkey: HexBinary,
+        /// Time of the first round, in seconds
+        genesis_time: u64,
+        /// Seconds between rounds
+        period: u64,
+    },
+    /// Randomness is requested from a nois proxy when the flip starts,
+    /// the flip is resolved once the proxy calls us back with it.
+    Nois {
+        proxy: Addr,
+        /// Amount of blocks we wait for the callback before refunding the flip
+        timeout: u64,
+    },
+    /// The house commits to the final hash of a sha256 seed chain,
+    /// every DoFlips reveals the next preimage and the flips are resolved with it.
+    HashChain,
+}
+
+/// Operator seed commitment for the commit-reveal mode
+#[cw_serde]
+pub struct OperatorSeed {
+    /// sha256 hex of the seed
+    pub commitment: String,
+    /// The seed, once revealed
+    pub seed: Option<String>,
+    /// Block the commitment was made in
+    pub block: u64,
+}
+
+/// House seed chain of the hash-chain mode
+#[cw_serde]
+pub struct HouseChain {
+    /// Last revealed seed, or the committed final hash if nothing was revealed yet
+    pub head: String,
+    /// Amount of seeds left to reveal
+    pub remaining: u64,
+    /// Amount of seeds revealed so far over all the chains, the index of the head
+    #[serde(default)]
+    pub revealed: u64,
+    /// Chain committed while this one still had seeds, it takes over once this one is used up
+    pub next: Option<NextHouseChain>,
+}
+
+#[cw_serde]
+pub struct NextHouseChain {
+    pub head: String,
+    pub length: u64,
+}
+
+impl HouseChain {
+    /// Move to the next chain if this one is used up, returns if there is a seed left to reveal
+    pub fn rotate_if_used_up(&mut self) -> bool {
+        if self.remaining == 0 {
+            if let Some(next) = self.next.take() {
+                self.head = next.head;
+                self.remaining = next.length;
+            }
+        }
+        self.remaining > 0
+    }
+}
+
+/// Verified drand beacon
+#[cw_serde]
+pub struct DrandBeacon {
+    pub signature: HexBinary,
+    /// sha256 hex of the signature
+    pub randomness: String,
 }
 
 #[cw_serde]
@@ -25,6 +196,9 @@ pub struct DenomLimit {
     pub min: Uint128,
     pub max: Uint128,
     pub bank: Uint128,
+    /// Max amount a single flip can pay out, no limit if not set
+    #[serde(default)]
+    pub max_payout: Option<Uint128>,
 }
 
 #[cw_serde]
@@ -58,6 +232,34 @@ impl NftReward {
 pub enum PickTypes {
     Heads,
     Tails,
+    /// Dice roll from 0 to 99, won if it's under the win chance, the payout is scaled to match
+    RollUnder {
+        win_chance: u8,
+    },
+}
+
+/// Lowest win chance of a roll-under pick, in percent
+pub const MIN_WIN_CHANCE: u8 = 10;
+/// Highest win chance of a roll-under pick, in percent
+pub const MAX_WIN_CHANCE: u8 = 90;
+
+impl PickTypes {
+    pub fn is_valid(&self) -> bool {
+        match self {
+            PickTypes::RollUnder { win_chance } => {
+                (MIN_WIN_CHANCE..=MAX_WIN_CHANCE).contains(win_chance)
+            }
+            _ => true,
+        }
+    }
+
+    /// Amount paid to the flipper if the bet is won
+    pub fn payout(&self, amount: Uint128) -> Uint128 {
+        match self {
+            PickTypes::Heads | PickTypes::Tails => amount * Uint128::new(2),
+            PickTypes::RollUnder { win_chance } => amount.multiply_ratio(100_u128, *win_chance),
+        }
+    }
 }
 
 #[cw_serde]
@@ -72,6 +274,12 @@ pub struct Fees {
     pub holders_bps: u64,
     pub reserve_bps: u64,
     pub flip_bps: u64,
+    /// Share of each flip fee going to the jackpot of its denom, the rest is distributed
+    #[serde(default)]
+    pub jackpot_bps: u64,
+    /// Share of each flip fee credited to the referrer of the wallet that paid it
+    #[serde(default)]
+    pub referral_bps: u64,
 }
 
 impl Fees {
@@ -92,6 +300,16 @@ impl Fees {
         }
     }
 
+    /// Part of the paid fees going to the jackpot
+    pub fn jackpot_share(&self, paid_fees: Uint128) -> Uint128 {
+        paid_fees.multiply_ratio(self.jackpot_bps, 10_000_u64)
+    }
+
+    /// Part of the fee paid by a referred wallet going to its referrer
+    pub fn referral_share(&self, paid_fee: Uint128) -> Uint128 {
+        paid_fee.multiply_ratio(self.referral_bps, 10_000_u64)
+    }
+
     pub fn to_uint_floor(&self, to_pay: Decimal) -> Uint128 {
         let decimal_fractional = Uint128::from(
             10_u128
@@ -112,11 +330,64 @@ pub struct FeesToPay {
 
 #[cw_serde]
 pub struct Flip {
+    pub id: u64,
     pub wallet: Addr,
     pub amount: Coin,
     pub result: bool,
     pub streak: Streak,
     pub timestamp: Timestamp,
+    /// Block the flip was resolved in
+    pub block: u64,
+    pub client_seed: Option<String>,
+}
+
+/// Everything needed to recompute the result of a resolved flip
+#[cw_serde]
+pub struct FlipProof {
+    pub id: u64,
+    pub wallet: Addr,
+    pub amount: Coin,
+    pub pick: PickTypes,
+    /// Flip fee paid by the flipper
+    pub fee: Uint128,
+    /// Amount paid to the flipper, zero if the flip was lost
+    pub payout: Uint128,
+    pub result: bool,
+    pub client_seed: Option<String>,
+    /// Block the flip was started in
+    pub start_block: u64,
+    /// Block the flip was resolved in
+    pub block: u64,
+    pub timestamp: Timestamp,
+    /// Seed material the flip was resolved with
+    pub randomness: FlipRandomness,
+    /// Index of the DoFlips transaction in its block, part of the block seed
+    #[serde(default)]
+    pub tx_index: u32,
+    /// sha256 hex of the player secret, for commit-reveal flips
+    #[serde(default)]
+    pub commitment: Option<String>,
+    /// sha256 hex of the operator seed, for commit-reveal flips
+    #[serde(default)]
+    pub operator_commitment: Option<String>,
+    /// Drand round the flip was tied to when it started
+    #[serde(default)]
+    pub drand_round: Option<u64>,
+    /// Signature of the drand beacon the flip was resolved with
+    #[serde(default)]
+    pub signature: Option<HexBinary>,
+    /// Seed derived from the seed material
+    pub seed: String,
+    /// Random number derived from the seed, the flip id and the wallet, heads if it's even.
+    /// Roll-under picks roll it modulo 100
+    /// None when the result was decided by a reveal timeout
+    pub outcome_random: Option<u64>,
+    /// Opponent of a PvP flip, it gets the payout of both stakes if the flipper lost
+    #[serde(default)]
+    pub opponent: Option<Addr>,
+    /// Paid by a voucher, a win only pays the profit
+    #[serde(default)]
+    pub voucher: bool,
 }
 
 #[cw_serde]
@@ -172,4 +443,293 @@ pub struct TodoFlip {
     pub pick: PickTypes,
     pub block: u64,
     pub timestamp: Timestamp,
+    /// Commit-reveal data, only set for flips started in commit-reveal mode
+    pub commit: Option<FlipCommit>,
+    /// Drand round the flip is resolved with, only set for flips started in drand mode
+    pub drand_round: Option<u64>,
+    /// Job id of the randomness request, only set for flips started in nois mode
+    pub nois_job_id: Option<String>,
+    /// Index of the house seed the flip is resolved with, only set for flips started in hash-chain mode
+    #[serde(default)]
+    pub house_seed_index: Option<u64>,
+    /// Flip fee paid by the flipper
+    #[serde(default)]
+    pub fee: Uint128,
+    /// Part of the fees paid for the flip that went into the jackpot, taken back on refund
+    #[serde(default)]
+    pub jackpot_share: Uint128,
+    /// Shares of the fees paid for the flip held for the referrers of the players,
+    /// credited once the flip is resolved
+    #[serde(default)]
+    pub referral_shares: Vec<ReferralShare>,
+    /// Seed of the flipper, mixed into the outcome of the flip
+    pub client_seed: Option<String>,
+    /// Player who accepted the PvP challenge of the flipper, paid if the flipper loses.
+    /// Both players staked the amount and paid the fee
+    #[serde(default)]
+    pub opponent: Option<Addr>,
+    /// Queued by the auto-bet of the flipper, paid out of and back into its budget
+    #[serde(default)]
+    pub auto_bet: bool,
+    /// Paid by a voucher, the bank covers the bet
+    #[serde(default)]
+    pub voucher: bool,
+}
+
+impl TodoFlip {
+    /// Flip fees paid for the flip, both players paid one on a PvP flip
+    pub fn paid_fees(&self) -> Uint128 {
+        if self.opponent.is_some() {
+            self.fee * Uint128::new(2)
+        } else {
+            self.fee
+        }
+    }
+
+    /// Amount paid if the flip is won, only the profit for voucher flips since their bet was never paid
+    pub fn payout(&self) -> Uint128 {
+        let payout = self.pick.payout(self.amount.amount);
+        if self.voucher {
+            payout - self.amount.amount
+        } else {
+            payout
+        }
+    }
+}
+
+/// Share of a flip fee held for the referrer of the player who paid it
+#[cw_serde]
+pub struct ReferralShare {
+    pub referrer: Addr,
+    pub amount: Uint128,
+}
+
+/// Free flips granted to a wallet, the bank covers their bets
+#[cw_serde]
+pub struct Voucher {
+    pub id: u64,
+    /// Bet of each free flip
+    pub amount: Coin,
+    /// Free flips left
+    pub flips: u32,
+    /// Block from which the voucher can't be used anymore
+    pub expires_at: u64,
+}
+
+/// PvP challenge waiting for a second player
+#[cw_serde]
+pub struct Challenge {
+    pub id: u64,
+    pub creator: Addr,
+    /// Stake of each player
+    pub amount: Coin,
+    /// Pick of the creator, the opponent gets the other side
+    pub pick: PickTypes,
+    /// Flip fee paid by the creator
+    pub fee: Uint128,
+    pub block: u64,
+    /// Block from which the challenge can't be accepted anymore
+    pub expires_at: u64,
+}
+
+/// How the bet of an auto-bet changes after each result
+#[cw_serde]
+pub enum BetProgression {
+    /// Always the base bet
+    Fixed,
+    /// Double the bet after a loss, back to the base bet after a win
+    Martingale,
+    /// Double the bet after a win, back to the base bet after a loss
+    AntiMartingale,
+}
+
+/// How the pick of an auto-bet changes after each result
+#[cw_serde]
+pub enum PickRule {
+    /// Always the first pick
+    Fixed,
+    /// Switch between heads and tails every round
+    Alternate,
+    /// Pick the side the last flip landed on
+    FollowLastSide,
+}
+
+#[cw_serde]
+pub struct AutoBetStrategy {
+    /// Pick of the first round
+    pub pick: PickTypes,
+    /// Only fixed for roll-under picks, they have no sides
+    pub pick_rule: PickRule,
+    pub base_bet: Uint128,
+    pub progression: BetProgression,
+    /// Stop once the budget is down this much from the deposit
+    pub stop_loss: Option<Uint128>,
+    /// Stop once the budget is up this much from the deposit
+    pub take_profit: Option<Uint128>,
+    pub max_rounds: u32,
+}
+
+impl AutoBetStrategy {
+    /// Bet of the round after this result
+    pub fn next_bet(&self, bet: Uint128, won: bool) -> Uint128 {
+        match (&self.progression, won) {
+            (BetProgression::Fixed, _) => self.base_bet,
+            (BetProgression::Martingale, false) | (BetProgression::AntiMartingale, true) => {
+                bet * Uint128::new(2)
+            }
+            (BetProgression::Martingale, true) | (BetProgression::AntiMartingale, false) => {
+                self.base_bet
+            }
+        }
+    }
+
+    /// Pick of the round after this result
+    pub fn next_pick(&self, pick: &PickTypes, won: bool) -> PickTypes {
+        let other_side = match pick {
+            PickTypes::Heads => PickTypes::Tails,
+            PickTypes::Tails => PickTypes::Heads,
+            PickTypes::RollUnder { .. } => return pick.clone(),
+        };
+
+        match (&self.pick_rule, won) {
+            (PickRule::Fixed, _) | (PickRule::FollowLastSide, true) => pick.clone(),
+            (PickRule::Alternate, _) | (PickRule::FollowLastSide, false) => other_side,
+        }
+    }
+}
+
+/// Flip series the contract plays for a wallet out of its deposited budget
+#[cw_serde]
+pub struct AutoBet {
+    pub wallet: Addr,
+    pub strategy: AutoBetStrategy,
+    pub denom: String,
+    /// Budget the strategy started with, stop-loss and take-profit are measured from it
+    pub deposit: Uint128,
+    /// What is left to bet with, winnings included, without the bet waiting to be done
+    pub budget: Uint128,
+    pub next_pick: PickTypes,
+    pub next_bet: Uint128,
+    /// Amount of rounds queued so far
+    pub rounds: u32,
+    /// Flip of the current round, waiting to be done
+    pub pending_flip: Option<u64>,
+}
+
+#[cw_serde]
+pub struct FlipCommit {
+    /// sha256 hex of the player secret
+    pub commitment: String,
+    /// The player secret, once revealed
+    pub secret: Option<String>,
+    /// The operator seed round this flip is bound to
+    pub operator_round: u64,
+}
+
+/// Amount of wallets kept on every board of a leaderboard, and max amount of prizes
+pub const LEADERBOARD_SIZE: usize = 10;
+
+/// How long a leaderboard epoch lasts
+#[cw_serde]
+pub enum EpochLength {
+    Daily,
+    Weekly,
+}
+
+impl EpochLength {
+    pub fn seconds(&self) -> u64 {
+        match self {
+            EpochLength::Daily => 86_400,
+            EpochLength::Weekly => 604_800,
+        }
+    }
+}
+
+/// Prize of a rank on a leaderboard
+#[cw_serde]
+pub enum LeaderboardPrize {
+    /// Paid from the prize pool of its denom, the pool gets the reserve share of the fees
+    Coin(Coin),
+    /// The oldest NFT of the NFT pool
+    Nft,
+}
+
+#[cw_serde]
+pub struct LeaderboardConfig {
+    pub epoch_length: EpochLength,
+    /// Only flips of this denom are ranked, amounts of different denoms don't compare
+    pub denom: String,
+    /// Prize of each rank, from the first one, paid on every board
+    pub prizes: Vec<LeaderboardPrize>,
+}
+
+impl LeaderboardConfig {
+    pub fn is_valid(&self, denoms: &HashSet<String>) -> bool {
+        denoms.contains(&self.denom)
+            && self.prizes.len() <= LEADERBOARD_SIZE
+            && self.prizes.iter().all(|prize| match prize {
+                LeaderboardPrize::Coin(coin) => {
+                    denoms.contains(&coin.denom) && !coin.amount.is_zero()
+                }
+                LeaderboardPrize::Nft => true,
+            })
+    }
+
+    /// Amount of a denom needed to pay the prizes of all the boards of an epoch
+    pub fn prizes_amount(&self, denom: &str) -> Uint128 {
+        let amount: Uint128 = self
+            .prizes
+            .iter()
+            .filter_map(|prize| match prize {
+                LeaderboardPrize::Coin(coin) if coin.denom == denom => Some(coin.amount),
+                _ => None,
+            })
+            .sum();
+        amount * Uint128::new(3)
+    }
+}
+
+#[cw_serde]
+pub struct Standing {
+    pub wallet: Addr,
+    pub value: Uint128,
+}
+
+/// Best wallets of an epoch, every board keeps its top LEADERBOARD_SIZE
+#[cw_serde]
+pub struct Leaderboard {
+    pub epoch: u64,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    /// Total amount bet
+    pub volume: Vec<Standing>,
+    /// Longest run of wins
+    pub win_streak: Vec<Standing>,
+    /// Biggest payout of a single flip
+    pub biggest_win: Vec<Standing>,
+    /// Prizes of the epoch were paid
+    pub finalized: bool,
+}
+
+/// What a wallet did in an epoch, its standings on the boards come from it
+#[cw_serde]
+#[derive(Default)]
+pub struct EpochScore {
+    pub volume: Uint128,
+    /// Current run of wins in the epoch
+    pub streak: u32,
+    pub best_streak: u32,
+    pub biggest_win: Uint128,
+}
+
+/// What a referrer got from the wallets it referred
+#[cw_serde]
+#[derive(Default)]
+pub struct ReferralStats {
+    /// Amount of wallets bound to the referrer
+    pub referrals: u32,
+    /// Everything credited so far, claimed or not
+    pub earned: Vec<Coin>,
+    /// Credited and not claimed yet
+    pub claimable: Vec<Coin>,
 }